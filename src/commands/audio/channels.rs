@@ -0,0 +1,223 @@
+use anyhow::{Context, Result};
+use hound::WavSpec;
+use rayon::prelude::*;
+use std::fs::copy;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::{
+    temp_output, with_writable_extension, AudioReader, AudioWriter, BLOCK_FRAMES,
+    READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioChannelsArgs, ChannelConversion};
+
+pub fn execute(args: AudioChannelsArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let conversion: ChannelConversion = args.channels;
+
+    let overwrite: bool = args.overwrite;
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    process(input, conversion, output, overwrite).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+/// Process all the content (single file or directory of files)
+fn process(
+    input: &Path,
+    conversion: ChannelConversion,
+    output: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        process_file(input, conversion, output, overwrite)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths, in a format that can be written
+        let outputs = nested_outputs(&files, input, output, with_writable_extension)?;
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, conversion, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
+    }
+    Ok(())
+}
+
+/// Weights of every input channel in every output channel
+fn mixing_matrix(channels: usize, conversion: ChannelConversion) -> Result<Vec<Vec<f64>>> {
+    let unit = |channel: usize| -> Vec<f64> {
+        (0..channels)
+            .map(|i| if i == channel { 1.0 } else { 0.0 })
+            .collect()
+    };
+
+    match conversion {
+        ChannelConversion::Pick(channel) if channel as usize >= channels => {
+            Err(anyhow::Error::msg(format!(
+                "Can't pick channel {} from a file with {} channels",
+                channel, channels
+            )))
+        }
+        ChannelConversion::Pick(channel) => Ok(vec![unit(channel as usize)]),
+        ChannelConversion::Mix | ChannelConversion::Count(1) => {
+            Ok(vec![vec![1.0 / channels as f64; channels]])
+        }
+        ChannelConversion::Count(n) if n as usize == channels => {
+            Ok((0..channels).map(unit).collect())
+        }
+        ChannelConversion::Count(2) if channels == 1 => Ok(vec![vec![1.0], vec![1.0]]),
+        ChannelConversion::Count(2) if (3..=6).contains(&channels) => Ok(stereo_downmix(channels)),
+        ChannelConversion::Count(n) => Err(anyhow::Error::msg(format!(
+            "No channel mapping defined from {} to {} channels",
+            channels, n
+        ))),
+    }
+}
+
+/// ITU-R BS.775 downmix to stereo of the 3.0, quad, 5.0 and 5.1 layouts, in the default WAV
+/// and FFmpeg channel order, leaving out the LFE and scaled down so that it can't clip
+fn stereo_downmix(channels: usize) -> Vec<Vec<f64>> {
+    let g = std::f64::consts::FRAC_1_SQRT_2;
+
+    // Weights of the input channels in the left and right outputs
+    let (left, right) = match channels {
+        // L R C
+        3 => (vec![1.0, 0.0, g], vec![0.0, 1.0, g]),
+        // L R Ls Rs
+        4 => (vec![1.0, 0.0, g, 0.0], vec![0.0, 1.0, 0.0, g]),
+        // L R C Ls Rs
+        5 => (vec![1.0, 0.0, g, g, 0.0], vec![0.0, 1.0, g, 0.0, g]),
+        // L R C LFE Ls Rs
+        _ => (
+            vec![1.0, 0.0, g, 0.0, g, 0.0],
+            vec![0.0, 1.0, g, 0.0, 0.0, g],
+        ),
+    };
+
+    [left, right]
+        .into_iter()
+        .map(|weights| {
+            let total: f64 = weights.iter().sum();
+            weights.iter().map(|weight| weight / total).collect()
+        })
+        .collect()
+}
+
+/// Convert interleaved samples to the target channels
+fn convert(samples: &[f64], channels: usize, matrix: &[Vec<f64>]) -> Vec<f64> {
+    let mut converted = Vec::with_capacity(samples.len() / channels * matrix.len());
+
+    for frame in samples.chunks_exact(channels) {
+        converted.extend(matrix.iter().map(|weights| {
+            weights
+                .iter()
+                .zip(frame)
+                .map(|(weight, sample)| weight * sample)
+                .sum::<f64>()
+        }));
+    }
+
+    converted
+}
+
+/// Process a single file
+fn process_file(
+    input: &Path,
+    conversion: ChannelConversion,
+    output: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    // Check that we can overwrite
+    if input == output && !overwrite {
+        return Err(anyhow::Error::msg("Can't overwrite files"));
+    }
+
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    // Find how the output channels are made from the input ones
+    let matrix = mixing_matrix(channels, conversion)?;
+    let target = matrix.len() as u16;
+
+    // If the channels do not change and neither does the format, no need to convert
+    if target == spec.channels && input.extension() == output.extension() {
+        // Just copy the file if input does not coincide with output
+        if input != output {
+            copy(input, output).with_context(|| "Failed to copy file")?;
+        }
+
+        return Ok(());
+    }
+
+    // Create a new specification for the converted audio
+    let converted_spec = WavSpec {
+        channels: target,
+        ..spec
+    };
+
+    // Write to a temporary file first, so that the input can be safely overwritten
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), converted_spec)?;
+
+    // Convert one block at a time
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+
+        writer
+            .write_samples(&convert(&block, channels, &matrix))
+            .with_context(|| "Failed to write audio samples")?;
+    }
+    writer.finalize()?;
+
+    // Move the converted file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use std::cmp::Ordering;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::{
+    fade_gain, temp_output, AudioReader, AudioWriter, BLOCK_FRAMES, READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, perform_io_sanity_check};
+use crate::{AudioConcatArgs, FadeCurve};
+
+pub fn execute(args: AudioConcatArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let crossfade: f32 = args.crossfade;
+    let curve: FadeCurve = args.curve;
+    let overwrite: bool = args.overwrite;
+
+    if crossfade < 0.0 {
+        return Err(anyhow::Error::msg("Crossfade duration can't be negative"));
+    }
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, true, true).with_context(|| "Sanity check failed")?;
+    if !input.is_dir() {
+        return Err(anyhow::Error::msg("Input must be a directory"));
+    }
+    if output.is_dir() {
+        return Err(anyhow::Error::msg("Output must be a file"));
+    }
+
+    // Check that we can overwrite
+    if output.exists() && !overwrite {
+        return Err(anyhow::Error::msg(
+            "Output file already exists, use --overwrite to replace it",
+        ));
+    }
+
+    // Find all files, leaving out the output in case it is inside the input directory
+    let mut files: Vec<PathBuf> = WalkDir::new(input)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+        .map(|e| e.path().to_path_buf())
+        .filter(|path| path != output)
+        .collect();
+
+    // Raise error if no files are admissible
+    if files.is_empty() {
+        return Err(anyhow::Error::msg("No admissible audio files detected"));
+    }
+
+    // Join in natural order, so that 2.wav comes before 10.wav
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+    // Process files
+    process(&files, crossfade, curve, output).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+// Compare strings treating runs of digits as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                // Compare whole numbers, ignoring leading zeros
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+                        digits.push(c);
+                        chars.next();
+                    }
+                    digits.trim_start_matches('0').to_string()
+                };
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+// Join the files one after the other into the output
+fn process(files: &[PathBuf], crossfade: f32, curve: FadeCurve, output: &Path) -> Result<()> {
+    // Every file must have the same specs as the first one
+    let spec = AudioReader::open(&files[0])
+        .with_context(|| format!("Failed to open {:?}", files[0]))?
+        .spec();
+    for file in &files[1..] {
+        let file_spec = AudioReader::open(file)
+            .with_context(|| format!("Failed to open {:?}", file))?
+            .spec();
+        if file_spec != spec {
+            return Err(anyhow::Error::msg(format!(
+                "Mismatched specs: {:?} has {:?}, expected {:?} as in {:?}",
+                file, file_spec, spec, files[0]
+            )));
+        }
+    }
+
+    let channels = spec.channels as usize;
+    let crossfade_frames = (spec.sample_rate as f32 * crossfade) as usize;
+
+    // Write to a temporary file first, so that the output is never left half-written
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), spec)?;
+
+    // The last frames written so far are held back to be mixed with the start of the next file
+    let mut pending: Vec<f64> = Vec::new();
+    let mut previous_frames = 0;
+    for (index, file) in files.iter().enumerate() {
+        let mut reader = AudioReader::open(file)?;
+        let mut frames = 0;
+
+        // Crossfade the start of the file with the end of the previous one
+        if index > 0 && crossfade_frames > 0 {
+            let mut head: Vec<f64> = Vec::new();
+            while head.len() / channels < crossfade_frames {
+                let block = reader
+                    .read_samples(crossfade_frames * channels - head.len())
+                    .with_context(|| format!("Couldn't read samples from {:?}", file))?;
+                if block.is_empty() {
+                    break;
+                }
+                head.extend_from_slice(&block);
+            }
+            frames += head.len() / channels;
+
+            // Overlap as much as both files allow
+            let overlap = [
+                pending.len() / channels,
+                head.len() / channels,
+                previous_frames,
+            ]
+            .into_iter()
+            .min()
+            .unwrap_or(0);
+            let start = pending.len() - overlap * channels;
+            for (i, (tail, head)) in pending[start..]
+                .chunks_exact_mut(channels)
+                .zip(head.chunks_exact(channels))
+                .enumerate()
+            {
+                let progress = (i as f64 + 0.5) / overlap as f64;
+                let (fade_out, fade_in) =
+                    (fade_gain(curve, 1.0 - progress), fade_gain(curve, progress));
+                for (tail, head) in tail.iter_mut().zip(head) {
+                    *tail = *tail * fade_out + head * fade_in;
+                }
+            }
+            pending.extend_from_slice(&head[overlap * channels..]);
+        }
+
+        loop {
+            // Write everything that can't be part of the next crossfade
+            let ready = (pending.len() / channels).saturating_sub(crossfade_frames);
+            writer
+                .write_samples(&pending[..ready * channels])
+                .with_context(|| "Failed to write audio samples")?;
+            pending.drain(..ready * channels);
+
+            let block = reader
+                .read_samples(BLOCK_FRAMES * channels)
+                .with_context(|| format!("Couldn't read samples from {:?}", file))?;
+            if block.is_empty() {
+                break;
+            }
+            frames += block.len() / channels;
+            pending.extend_from_slice(&block);
+        }
+        previous_frames = frames;
+    }
+
+    writer
+        .write_samples(&pending)
+        .with_context(|| "Failed to write audio samples")?;
+    writer.finalize()?;
+
+    // Move the joined file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
+}
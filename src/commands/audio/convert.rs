@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavSpec};
+use rayon::prelude::*;
+use std::fs::copy;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::{
+    temp_output, AudioReader, AudioWriter, BLOCK_FRAMES, READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioCodec, AudioConvertArgs, AudioSampleFormat};
+
+// Bit depth used when converting float samples to integers without an explicit one
+const DEFAULT_INT_BIT_DEPTH: u16 = 24;
+
+// Requested output format
+#[derive(Debug, Clone, Copy)]
+struct Conversion {
+    codec: AudioCodec,
+    bit_depth: Option<u16>,
+    sample_format: Option<AudioSampleFormat>,
+}
+
+pub fn execute(args: AudioConvertArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let conversion = Conversion {
+        codec: args.codec,
+        bit_depth: args.bit_depth,
+        sample_format: args.sample_format,
+    };
+
+    let overwrite: bool = args.overwrite;
+
+    // Lossy codecs pick their own sample format
+    if matches!(conversion.codec, AudioCodec::Mp3 | AudioCodec::Opus)
+        && (conversion.bit_depth.is_some() || conversion.sample_format.is_some())
+    {
+        return Err(anyhow::Error::msg(format!(
+            "Bit depth and sample format can't be set for {}",
+            conversion.codec.extension()
+        )));
+    }
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    process(input, conversion, output, overwrite).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+/// Process all the content (single file or directory of files)
+fn process(input: &Path, conversion: Conversion, output: &Path, overwrite: bool) -> Result<()> {
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+
+        // The output must match the codec
+        file_has_right_extension(output, &[conversion.codec.extension()]).with_context(|| {
+            format!(
+                "Output file must have the .{} extension",
+                conversion.codec.extension()
+            )
+        })?;
+
+        process_file(input, conversion, output, overwrite)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths with the extension of the codec
+        let outputs = nested_outputs(&files, input, output, |path| {
+            path.with_extension(conversion.codec.extension())
+        })?;
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, conversion, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
+    }
+    Ok(())
+}
+
+/// Find the specs of the converted file from the input ones
+fn output_spec(spec: WavSpec, conversion: Conversion) -> Result<WavSpec> {
+    // Lossy encoders work on float samples
+    if matches!(conversion.codec, AudioCodec::Mp3 | AudioCodec::Opus) {
+        return Ok(WavSpec {
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+            ..spec
+        });
+    }
+
+    // FLAC only stores integers, so default to them
+    let sample_format = match (conversion.sample_format, conversion.codec) {
+        (Some(AudioSampleFormat::Int), _) | (None, AudioCodec::Flac) => SampleFormat::Int,
+        (Some(AudioSampleFormat::Float), _) => SampleFormat::Float,
+        (None, _) => spec.sample_format,
+    };
+
+    let bits_per_sample = match sample_format {
+        SampleFormat::Float => match conversion.bit_depth {
+            None | Some(32) => 32,
+            Some(bits) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Float samples must be 32 bits, got {}",
+                    bits
+                )))
+            }
+        },
+        SampleFormat::Int => {
+            let bits = match (conversion.bit_depth, spec.sample_format) {
+                (Some(bits), _) => bits,
+                (None, SampleFormat::Int) => spec.bits_per_sample,
+                (None, SampleFormat::Float) => DEFAULT_INT_BIT_DEPTH,
+            };
+            if ![8, 16, 24, 32].contains(&bits) {
+                return Err(anyhow::Error::msg(format!(
+                    "Integer samples must be 8, 16, 24 or 32 bits, got {}",
+                    bits
+                )));
+            }
+            bits
+        }
+    };
+
+    Ok(WavSpec {
+        bits_per_sample,
+        sample_format,
+        ..spec
+    })
+}
+
+/// Process a single file
+fn process_file(
+    input: &Path,
+    conversion: Conversion,
+    output: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    // Check that we can overwrite
+    if input == output && !overwrite {
+        return Err(anyhow::Error::msg("Can't overwrite files"));
+    }
+
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let converted_spec = output_spec(spec, conversion)?;
+
+    // If neither the specs nor the format change, no need to convert
+    if converted_spec == spec && input.extension() == output.extension() {
+        // Just copy the file if input does not coincide with output
+        if input != output {
+            copy(input, output).with_context(|| "Failed to copy file")?;
+        }
+
+        return Ok(());
+    }
+
+    // Write to a temporary file first, so that the input can be safely overwritten
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), converted_spec)?;
+
+    // Convert one block at a time
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+
+        writer
+            .write_samples(&block)
+            .with_context(|| "Failed to write audio samples")?;
+    }
+    writer.finalize()?;
+
+    // Move the converted file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
+}
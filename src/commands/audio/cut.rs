@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::commands::audio::trim::{extract, resolve, Range};
+use crate::utils::audio::{with_writable_extension, AudioReader, READABLE_EXTENSIONS};
+use crate::utils::{file_has_right_extension, perform_io_sanity_check, read_table};
+use crate::{AudioCutArgs, Timestamp};
+
+// A row of the segments table
+#[derive(Debug)]
+struct Segment {
+    row: usize,
+    file: PathBuf,
+    range: Range,
+    label: Option<String>,
+}
+
+// A segment located in its file, with the path it is written to
+#[derive(Debug)]
+struct Extraction {
+    row: usize,
+    source: PathBuf,
+    start: usize,
+    end: Option<usize>,
+    output: PathBuf,
+}
+
+pub fn execute(args: AudioCutArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+    let segments_path = Path::new(&args.segments);
+
+    let clamp: bool = args.clamp;
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, false).with_context(|| "Sanity check failed")?;
+    if !input.is_dir() {
+        return Err(anyhow::Error::msg("Input must be a directory"));
+    }
+
+    // Read the segments
+    let segments = read_segments(segments_path, clamp)
+        .with_context(|| format!("Failed to read segments: {:?}", segments_path))?;
+
+    // Locate every segment in its file
+    let extractions = segments
+        .into_par_iter()
+        .map(|segment| {
+            let row = segment.row;
+            locate(input, output, segment)
+                .with_context(|| format!("Failed to locate segment of row {}", row))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Two segments written to the same file are most likely a duplicated row
+    let mut outputs: HashMap<&Path, usize> = HashMap::new();
+    for extraction in &extractions {
+        if let Some(row) = outputs.insert(&extraction.output, extraction.row) {
+            return Err(anyhow::Error::msg(format!(
+                "Segments of rows {} and {} would both be written to {:?}",
+                row, extraction.row, extraction.output
+            )));
+        }
+    }
+
+    // Parallel loop over segments
+    extractions
+        .par_iter()
+        .try_for_each(|extraction| -> Result<()> {
+            // Ensure the output directory exists
+            if let Some(parent) = extraction.output.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create output directory: {:?}", parent))?;
+            }
+
+            extract(
+                &extraction.source,
+                extraction.start,
+                extraction.end,
+                clamp,
+                &extraction.output,
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to cut segment of row {} from {:?}",
+                    extraction.row, extraction.source
+                )
+            })
+        })?;
+
+    println!("Cut {} segments", extractions.len());
+
+    Ok(())
+}
+
+// Read the segments table, with times given as in audio trim
+fn read_segments(path: &Path, clamp: bool) -> Result<Vec<Segment>> {
+    let df = read_table(path)?
+        .collect()
+        .with_context(|| "Failed to collect table")?;
+
+    // Columns other than times are read as text
+    let text_column = |name: &str| -> Result<Vec<Option<String>>> {
+        let column = df
+            .column(name)
+            .with_context(|| format!("Missing column: {}", name))?
+            .cast(&DataType::String)
+            .with_context(|| format!("Failed to read column as text: {}", name))?;
+        Ok(column
+            .str()?
+            .into_iter()
+            .map(|value| value.map(String::from))
+            .collect())
+    };
+
+    // Times are numbers of seconds in numeric columns, and parsed as timestamps in text ones
+    let time_column = |name: &str| -> Result<Vec<Option<Timestamp>>> {
+        let column = df
+            .column(name)
+            .with_context(|| format!("Missing column: {}", name))?;
+        let row_error = |row: usize, e: String| anyhow::Error::msg(format!("Row {}: {}", row, e));
+
+        match column.dtype().is_numeric() {
+            true => column
+                .cast(&DataType::Float64)
+                .with_context(|| format!("Failed to read column as seconds: {}", name))?
+                .f64()?
+                .into_iter()
+                .enumerate()
+                .map(|(row, value)| {
+                    value
+                        .map(|seconds| {
+                            Timestamp::from_seconds(seconds).map_err(|e| row_error(row, e))
+                        })
+                        .transpose()
+                })
+                .collect(),
+            false => text_column(name)?
+                .into_iter()
+                .enumerate()
+                .map(|(row, value)| {
+                    value
+                        .map(|value| value.parse::<Timestamp>().map_err(|e| row_error(row, e)))
+                        .transpose()
+                })
+                .collect(),
+        }
+    };
+
+    let files = text_column("file")?;
+    let starts = time_column("start")?;
+    let ends = time_column("end")?;
+    let labels = match df.get_column_names().iter().any(|name| *name == "label") {
+        true => text_column("label")?,
+        false => vec![None; df.height()],
+    };
+
+    (0..df.height())
+        .map(|row| {
+            let file = files[row]
+                .as_deref()
+                .ok_or_else(|| anyhow::Error::msg(format!("Row {}: missing file", row)))?;
+
+            // Labels become directories, so they can't reach outside the output
+            let label = labels[row]
+                .as_deref()
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .map(String::from);
+            if let Some(label) = &label {
+                if label == "." || label == ".." || label.contains(['/', '\\']) {
+                    return Err(anyhow::Error::msg(format!(
+                        "Row {}: invalid label {}",
+                        row, label
+                    )));
+                }
+            }
+
+            Ok(Segment {
+                row,
+                file: PathBuf::from(file),
+                range: Range {
+                    start: starts[row].unwrap_or_default(),
+                    end: ends[row],
+                    length: None,
+                    clamp,
+                },
+                label,
+            })
+        })
+        .collect()
+}
+
+// Resolve the frames of a segment and name its output after its source and times
+fn locate(input: &Path, output: &Path, segment: Segment) -> Result<Extraction> {
+    // Files are given relative to the input root
+    let relative = match segment.file.is_absolute() {
+        true => segment
+            .file
+            .strip_prefix(input)
+            .with_context(|| format!("File is not inside the input root: {:?}", segment.file))?
+            .to_path_buf(),
+        false => segment.file.clone(),
+    };
+    if relative
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return Err(anyhow::Error::msg(format!(
+            "File is not inside the input root: {:?}",
+            segment.file
+        )));
+    }
+
+    let source = input.join(&relative);
+    file_has_right_extension(&source, &READABLE_EXTENSIONS)
+        .with_context(|| format!("Unsupported file: {:?}", source))?;
+
+    let sample_rate = AudioReader::open(&source)
+        .with_context(|| format!("Failed to open {:?}", source))?
+        .spec()
+        .sample_rate;
+    let (start, end) = resolve(&source, segment.range, sample_rate)?;
+
+    // Deterministic name, from the times in milliseconds
+    let milliseconds =
+        |frames: usize| (frames as u64 * 1000 + sample_rate as u64 / 2) / sample_rate as u64;
+    let stem = relative
+        .file_stem()
+        .with_context(|| format!("Failed to extract stem from: {:?}", relative))?
+        .to_string_lossy();
+    let name = match end {
+        Some(end) => format!("{}@{}-{}", stem, milliseconds(start), milliseconds(end)),
+        None => format!("{}@{}-end", stem, milliseconds(start)),
+    };
+
+    // Segments go to the directory of their label, keeping the layout of the input root
+    let mut directory = output.to_path_buf();
+    if let Some(label) = &segment.label {
+        directory.push(label);
+    }
+    if let Some(parent) = relative.parent() {
+        directory.push(parent);
+    }
+    let extension = relative
+        .extension()
+        .with_context(|| format!("Failed to extract extension from: {:?}", relative))?
+        .to_string_lossy();
+    let output_path = with_writable_extension(&directory.join(format!("{}.{}", name, extension)));
+
+    Ok(Extraction {
+        row: segment.row,
+        source,
+        start,
+        end,
+        output: output_path,
+    })
+}
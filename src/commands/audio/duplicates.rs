@@ -0,0 +1,328 @@
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use rayon::prelude::*;
+use realfft::RealFftPlanner;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+use crate::utils::audio::{AudioReader, BLOCK_FRAMES, READABLE_EXTENSIONS};
+use crate::utils::duplicates::find_files;
+use crate::utils::{partition_results, report_failures, write_table};
+use crate::AudioDuplicatesArgs;
+
+// Frames are defined in seconds rather than samples, so that fingerprints don't depend on the sample rate
+const FRAME_SECONDS: f64 = 0.37;
+const HOP_SECONDS: f64 = FRAME_SECONDS / 32.0;
+
+// Frequency range split into logarithmic bands, one bit per pair of neighbouring bands
+const BANDS: usize = 33;
+const MIN_FREQUENCY: f64 = 300.0;
+const MAX_FREQUENCY: f64 = 2000.0;
+
+// Largest shift tried when aligning two fingerprints, e.g. for encoder delays
+const MAX_OFFSET_SECONDS: f64 = 0.5;
+
+// Acoustic fingerprint of a single audio file, with one 32-bit word per frame
+#[derive(Debug)]
+struct Fingerprint {
+    path: PathBuf,
+    words: Vec<u32>,
+}
+
+// Group of recordings similar to each other, with the best similarity of each to the others
+#[derive(Debug)]
+struct DuplicateGroup {
+    files: Vec<(PathBuf, f64)>,
+}
+
+pub fn execute(args: AudioDuplicatesArgs) -> Result<()> {
+    // Parse the arguments
+    let target = Path::new(&args.target);
+    let threshold: f64 = args.threshold;
+
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(anyhow::Error::msg("Threshold must be between 0 and 1"));
+    }
+
+    // Find all admissible files
+    let audio_files = find_files(
+        &[target.to_path_buf()],
+        args.recursive,
+        &READABLE_EXTENSIONS,
+    )?;
+
+    // Raise error if impossible to find duplicates
+    if audio_files.len() < 2 {
+        return Err(anyhow::Error::msg("Directory contains less than 2 files"));
+    }
+
+    // Calculate fingerprints
+    let results: Vec<(PathBuf, Result<Fingerprint>)> = audio_files
+        .into_par_iter()
+        .map(|file| {
+            let result = fingerprint(&file);
+            (file, result)
+        })
+        .collect();
+
+    // Separate readable files from failures and report the latter
+    let (mut fingerprints, failures) = partition_results(results);
+    report_failures(&failures, args.errors.as_deref().map(Path::new))?;
+    fingerprints.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Group similar recordings
+    let groups = find_duplicates(&fingerprints, threshold);
+
+    // Write the groups if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&groups)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
+    }
+
+    // Print duplicates
+    for group in &groups {
+        println!("Duplicate recordings:");
+        for (file, similarity) in &group.files {
+            println!("  {} (similarity {:.3})", file.display(), similarity);
+        }
+        println!();
+    }
+
+    if groups.is_empty() {
+        println!("No duplicate files found.");
+    }
+
+    // In strict mode any unreadable file is an error
+    if args.strict && !failures.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} files could not be read",
+            failures.len()
+        )));
+    }
+
+    Ok(())
+}
+
+// Compute the fingerprint of a file, mixing all the channels into mono
+//
+// Each frame gets the energies of bands spread over the range where most of the
+// tonal content lies. Every bit tells whether the energy difference of two
+// neighbouring bands increased since the previous frame, which is robust to gain,
+// equalisation and lossy compression.
+fn fingerprint(path: &Path) -> Result<Fingerprint> {
+    // Open the audio file
+    let mut reader = AudioReader::open(path)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let sample_rate = spec.sample_rate as f64;
+
+    let frame_len = (sample_rate * FRAME_SECONDS).round() as usize;
+    let hop = sample_rate * HOP_SECONDS;
+    if frame_len < 2 {
+        return Err(anyhow::Error::msg(
+            "Sample rate too low to compute fingerprint",
+        ));
+    }
+
+    // Hann window and FFT bins of every band
+    let window: Vec<f64> = (0..frame_len)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / frame_len as f64).cos())
+        .collect();
+    let bins = frame_len / 2 + 1;
+    let bands: Vec<(usize, usize)> = (0..BANDS)
+        .map(|band| {
+            let edge = |i: usize| {
+                let frequency =
+                    MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(i as f64 / BANDS as f64);
+                usize::min(
+                    (frequency * frame_len as f64 / sample_rate).ceil() as usize,
+                    bins,
+                )
+            };
+            (edge(band), edge(band + 1))
+        })
+        .collect();
+
+    let fft = RealFftPlanner::<f64>::new().plan_fft_forward(frame_len);
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+
+    // Mono samples from `offset` on, consumed frame by frame
+    let mut samples: Vec<f64> = Vec::new();
+    let mut offset = 0;
+    let mut frame = 0;
+    let mut previous: Option<Vec<f64>> = None;
+    let mut words = Vec::new();
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", path))?;
+        if block.is_empty() {
+            break;
+        }
+        samples.extend(
+            block
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f64>() / channels as f64),
+        );
+
+        loop {
+            // Frame starts are rounded from their time so that they never drift
+            let start = (frame as f64 * hop).round() as usize - offset;
+            if start + frame_len > samples.len() {
+                samples.drain(..start);
+                offset += start;
+                break;
+            }
+
+            for ((input, sample), weight) in input
+                .iter_mut()
+                .zip(&samples[start..start + frame_len])
+                .zip(&window)
+            {
+                *input = sample * weight;
+            }
+            fft.process(&mut input, &mut spectrum)
+                .map_err(|e| anyhow::Error::msg(format!("FFT failed: {}", e)))?;
+
+            // Energy differences between neighbouring bands
+            let energies: Vec<f64> = bands
+                .iter()
+                .map(|&(low, high)| spectrum[low..high].iter().map(|c| c.norm_sqr()).sum())
+                .collect();
+            let differences: Vec<f64> = energies.windows(2).map(|pair| pair[0] - pair[1]).collect();
+
+            if let Some(previous) = &previous {
+                let word = differences
+                    .iter()
+                    .zip(previous)
+                    .enumerate()
+                    .filter(|(_, (current, previous))| current > previous)
+                    .fold(0u32, |word, (bit, _)| word | (1 << bit));
+                words.push(word);
+            }
+            previous = Some(differences);
+            frame += 1;
+        }
+    }
+
+    if words.is_empty() {
+        return Err(anyhow::Error::msg("File too short to compute fingerprint"));
+    }
+
+    Ok(Fingerprint {
+        path: path.to_path_buf(),
+        words,
+    })
+}
+
+// Similarity between 0 and 1 of two fingerprints, at their best alignment
+//
+// This is the fraction of matching bits over the overlap, scaled down by how much
+// of the longest fingerprint is left out, so that a short excerpt doesn't match a
+// whole recording.
+fn similarity(a: &[u32], b: &[u32]) -> f64 {
+    let longest = usize::max(a.len(), b.len());
+    let max_offset = (MAX_OFFSET_SECONDS / HOP_SECONDS).round() as isize;
+
+    (-max_offset..=max_offset)
+        .filter_map(|shift| {
+            // Pairs of words a[i] and b[i + shift]
+            let (a, b) = match shift >= 0 {
+                true => (a, b.get(shift as usize..)?),
+                false => (a.get((-shift) as usize..)?, b),
+            };
+            let overlap = usize::min(a.len(), b.len());
+            if overlap == 0 {
+                return None;
+            }
+
+            let errors: u32 = a.iter().zip(b).map(|(a, b)| (a ^ b).count_ones()).sum();
+            let matching = 1.0 - errors as f64 / (32 * overlap) as f64;
+            Some(matching * overlap as f64 / longest as f64)
+        })
+        .fold(0.0, f64::max)
+}
+
+// Group fingerprints linked by a similarity at least equal to the threshold
+fn find_duplicates(fingerprints: &[Fingerprint], threshold: f64) -> Vec<DuplicateGroup> {
+    // Sort by length, so that the files long enough to match each one follow it.
+    // The similarity can't exceed the ratio of the lengths, so the others are never scored
+    let mut order: Vec<usize> = (0..fingerprints.len()).collect();
+    order.sort_by_key(|&i| fingerprints[i].words.len());
+
+    let pairs: Vec<(usize, usize, f64)> = (0..order.len())
+        .into_par_iter()
+        .flat_map_iter(|p| {
+            let i = order[p];
+            let a = &fingerprints[i].words;
+            order[p + 1..]
+                .iter()
+                .map(|&j| (j, &fingerprints[j].words))
+                .take_while(move |(_, b)| a.len() as f64 >= threshold * b.len() as f64)
+                .filter_map(move |(j, b)| {
+                    let similarity = similarity(a, b);
+                    (similarity >= threshold).then_some((i, j, similarity))
+                })
+        })
+        .collect();
+
+    // Union-find over the similar pairs
+    let mut parents: Vec<usize> = (0..fingerprints.len()).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut best: Vec<Option<f64>> = vec![None; fingerprints.len()];
+    for &(i, j, similarity) in &pairs {
+        let (root_i, root_j) = (root(&mut parents, i), root(&mut parents, j));
+        parents[root_j] = root_i;
+        for k in [i, j] {
+            best[k] = Some(best[k].map_or(similarity, |best| f64::max(best, similarity)));
+        }
+    }
+
+    // Collect the groups in path order, leaving out files without duplicates
+    let mut groups: Vec<(usize, DuplicateGroup)> = Vec::new();
+    for i in 0..fingerprints.len() {
+        let Some(similarity) = best[i] else {
+            continue;
+        };
+        let group = root(&mut parents, i);
+        let entry = (fingerprints[i].path.clone(), similarity);
+        match groups.iter_mut().find(|(root, _)| *root == group) {
+            Some((_, group)) => group.files.push(entry),
+            None => groups.push((group, DuplicateGroup { files: vec![entry] })),
+        }
+    }
+
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+// Build a table with one row per duplicate file
+fn build_manifest(groups: &[DuplicateGroup]) -> Result<DataFrame> {
+    let rows: Vec<(usize, &PathBuf, f64)> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(i, group)| {
+            group
+                .files
+                .iter()
+                .map(move |(path, similarity)| (i, path, *similarity))
+        })
+        .collect();
+
+    df!(
+        "group" => rows.iter().map(|row| row.0 as u32).collect::<Vec<_>>(),
+        "path" => rows.iter().map(|row| row.1.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "similarity" => rows.iter().map(|row| row.2).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")
+}
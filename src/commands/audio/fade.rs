@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::{
+    fade_gain, temp_output, with_writable_extension, AudioReader, AudioWriter, BLOCK_FRAMES,
+    READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioFadeArgs, FadeCurve};
+
+// Durations and shape of the fades
+#[derive(Debug, Clone, Copy)]
+struct Fades {
+    fade_in: f32,
+    fade_out: f32,
+    curve: FadeCurve,
+}
+
+pub fn execute(args: AudioFadeArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let fades = Fades {
+        fade_in: args.fade_in,
+        fade_out: args.fade_out,
+        curve: args.curve,
+    };
+
+    let overwrite: bool = args.overwrite;
+
+    if fades.fade_in < 0.0 || fades.fade_out < 0.0 {
+        return Err(anyhow::Error::msg("Fade durations can't be negative"));
+    }
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    process(input, fades, output, overwrite).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+// Process all the content (single file or directory of files)
+fn process(input: &Path, fades: Fades, output: &Path, overwrite: bool) -> Result<()> {
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        process_file(input, fades, output, overwrite)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths, in a format that can be written
+        let outputs = nested_outputs(&files, input, output, with_writable_extension)?;
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, fades, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
+    }
+    Ok(())
+}
+
+// Process a single file
+fn process_file(input: &Path, fades: Fades, output: &Path, overwrite: bool) -> Result<()> {
+    // Check that we can overwrite
+    if input == output && !overwrite {
+        return Err(anyhow::Error::msg("Can't overwrite files"));
+    }
+
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let sample_rate = spec.sample_rate as f32;
+    let channels = spec.channels as usize;
+
+    // Compute the fade durations in frames
+    let fade_in_frames = (sample_rate * fades.fade_in) as usize;
+    let fade_out_frames = (sample_rate * fades.fade_out) as usize;
+
+    // Write to a temporary file first, so that the input can be safely overwritten
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), spec)?;
+
+    // The end of the file is only known once read, so the last frames are held back
+    let mut pending: Vec<f64> = Vec::new();
+    let mut position = 0;
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+        pending.extend_from_slice(&block);
+
+        // Fade in and write everything that can't be part of the fade out
+        let ready = (pending.len() / channels).saturating_sub(fade_out_frames);
+        let mut samples: Vec<f64> = pending.drain(..ready * channels).collect();
+        for (i, frame) in samples.chunks_exact_mut(channels).enumerate() {
+            if position + i >= fade_in_frames {
+                break;
+            }
+            let gain = fade_gain(fades.curve, (position + i) as f64 / fade_in_frames as f64);
+            frame.iter_mut().for_each(|sample| *sample *= gain);
+        }
+        writer
+            .write_samples(&samples)
+            .with_context(|| "Failed to write audio samples")?;
+        position += ready;
+    }
+
+    // Fade out the held back frames, which may still be part of the fade in for short files
+    let remaining = pending.len() / channels;
+    for (i, frame) in pending.chunks_exact_mut(channels).enumerate() {
+        let mut gain = fade_gain(
+            fades.curve,
+            (remaining - 1 - i) as f64 / fade_out_frames as f64,
+        );
+        if position + i < fade_in_frames {
+            gain *= fade_gain(fades.curve, (position + i) as f64 / fade_in_frames as f64);
+        }
+        frame.iter_mut().for_each(|sample| *sample *= gain);
+    }
+    writer
+        .write_samples(&pending)
+        .with_context(|| "Failed to write audio samples")?;
+    writer.finalize()?;
+
+    // Move the faded file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
+}
@@ -1,4 +1,15 @@
+pub mod channels;
+pub mod concat;
+pub mod convert;
+pub mod cut;
+pub mod duplicates;
+pub mod fade;
+pub mod normalize;
+pub mod pad;
 pub mod resample;
+pub mod spectrogram;
 pub mod split;
 pub mod summary;
+pub mod tags;
 pub mod trim;
+pub mod vad;
@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use rayon::prelude::*;
+use std::fs::copy;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::loudness::LoudnessMeter;
+use crate::utils::audio::{
+    temp_output, with_writable_extension, AudioReader, AudioWriter, BLOCK_FRAMES,
+    READABLE_EXTENSIONS,
+};
+use crate::utils::{
+    file_has_right_extension, nested_outputs, perform_io_sanity_check, write_table,
+};
+use crate::{AudioNormalizeArgs, NormalizeMode};
+
+// Default targets, in LUFS for loudness and dBFS for peak
+const DEFAULT_LOUDNESS_TARGET: f64 = -23.0;
+const DEFAULT_PEAK_TARGET: f64 = -1.0;
+
+// How the level of a file is brought to the target
+#[derive(Debug, Clone, Copy)]
+struct Normalization {
+    mode: NormalizeMode,
+    target: f64,
+    true_peak: f64,
+}
+
+// Levels of a file before normalization and gain applied to it
+struct Measurement {
+    path: PathBuf,
+    loudness: f64,
+    sample_peak: f64,
+    true_peak: f64,
+    gain: f64,
+}
+
+pub fn execute(args: AudioNormalizeArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let normalization = Normalization {
+        mode: args.mode,
+        target: args.target.unwrap_or(match args.mode {
+            NormalizeMode::Loudness => DEFAULT_LOUDNESS_TARGET,
+            NormalizeMode::Peak => DEFAULT_PEAK_TARGET,
+        }),
+        true_peak: args.true_peak,
+    };
+
+    let overwrite: bool = args.overwrite;
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    let mut measurements =
+        process(input, normalization, output, overwrite).with_context(|| "Processing failed")?;
+    measurements.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Report the gain applied to every file
+    for measurement in &measurements {
+        let level = match normalization.mode {
+            NormalizeMode::Loudness => measurement.loudness,
+            NormalizeMode::Peak => measurement.sample_peak,
+        };
+        match level.is_finite() {
+            true => println!(
+                "{}: {:+.2} dB",
+                measurement.path.display(),
+                measurement.gain
+            ),
+            false => println!(
+                "{}: no measurable level, left as is",
+                measurement.path.display()
+            ),
+        }
+    }
+
+    // Write the report if requested
+    if let Some(path) = &args.report {
+        let path = Path::new(path);
+        write_table(&mut build_report(&measurements)?, path)
+            .with_context(|| format!("Failed to write report: {:?}", path))?;
+    }
+
+    Ok(())
+}
+
+/// Process all the content (single file or directory of files)
+fn process(
+    input: &Path,
+    normalization: Normalization,
+    output: &Path,
+    overwrite: bool,
+) -> Result<Vec<Measurement>> {
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        let measurement = process_file(input, normalization, output, overwrite)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+        Ok(vec![measurement])
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths, in a format that can be written
+        let outputs = nested_outputs(&files, input, output, with_writable_extension)?;
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .map(|(file, file_output)| -> Result<Measurement> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, normalization, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))
+            })
+            .collect()
+    }
+}
+
+/// Measure the levels of a file in a first pass
+fn measure(input: &Path) -> Result<LoudnessMeter> {
+    let mut reader = AudioReader::open(input)?;
+    let channels = reader.spec().channels as usize;
+    let mut meter = LoudnessMeter::new(reader.spec());
+
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+
+        meter.push(&block);
+    }
+
+    Ok(meter)
+}
+
+/// Process a single file
+fn process_file(
+    input: &Path,
+    normalization: Normalization,
+    output: &Path,
+    overwrite: bool,
+) -> Result<Measurement> {
+    // Check that we can overwrite
+    if input == output && !overwrite {
+        return Err(anyhow::Error::msg("Can't overwrite files"));
+    }
+
+    // Measure the levels
+    let meter = measure(input)?;
+    let loudness = meter.integrated_loudness();
+    let sample_peak = meter.sample_peak();
+    let true_peak = meter.true_peak();
+
+    // Gain to reach the target, lowered if the true peak would go over the ceiling
+    //
+    // Files without a measurable level are left untouched: silent ones, and for loudness those
+    // shorter than a block or gated out entirely, which the ceiling would otherwise boost.
+    let level = match normalization.mode {
+        NormalizeMode::Loudness => loudness,
+        NormalizeMode::Peak => sample_peak,
+    };
+    let gain = match level.is_finite() {
+        true => f64::min(
+            normalization.target - level,
+            normalization.true_peak - true_peak,
+        ),
+        false => 0.0,
+    };
+
+    let measurement = Measurement {
+        path: input.to_path_buf(),
+        loudness,
+        sample_peak,
+        true_peak,
+        gain,
+    };
+
+    // If there is no gain to apply and the format does not change, no need to rewrite the file
+    if gain == 0.0 && input.extension() == output.extension() {
+        // Just copy the file if input does not coincide with output
+        if input != output {
+            copy(input, output).with_context(|| "Failed to copy file")?;
+        }
+
+        return Ok(measurement);
+    }
+
+    // Open the audio file again to apply the gain
+    let mut reader = AudioReader::open(input)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let factor = 10_f64.powf(gain / 20.0);
+
+    // Write to a temporary file first, so that the input can be safely overwritten
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), spec)?;
+
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+
+        let scaled: Vec<f64> = block.iter().map(|sample| sample * factor).collect();
+        writer
+            .write_samples(&scaled)
+            .with_context(|| "Failed to write audio samples")?;
+    }
+    writer.finalize()?;
+
+    // Move the normalized file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(measurement)
+}
+
+// Collect the levels and gain of every file into a table, one row per file
+fn build_report(measurements: &[Measurement]) -> Result<DataFrame> {
+    // Silent files have no finite level
+    let finite = |value: f64| value.is_finite().then_some(value);
+
+    df!(
+        "path" => measurements.iter().map(|m| m.path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "loudness_lufs" => measurements.iter().map(|m| finite(m.loudness)).collect::<Vec<_>>(),
+        "sample_peak_dbfs" => measurements.iter().map(|m| finite(m.sample_peak)).collect::<Vec<_>>(),
+        "true_peak_dbtp" => measurements.iter().map(|m| finite(m.true_peak)).collect::<Vec<_>>(),
+        "gain_db" => measurements.iter().map(|m| m.gain).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build report")
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::{
+    temp_output, with_writable_extension, AudioReader, AudioWriter, BLOCK_FRAMES,
+    READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioPadArgs, PadMode, PadPosition};
+
+// How files shorter than the target are padded
+#[derive(Debug, Clone, Copy)]
+struct Padding {
+    length: f32,
+    position: PadPosition,
+    mode: PadMode,
+}
+
+pub fn execute(args: AudioPadArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let padding = Padding {
+        length: args.length,
+        position: args.position,
+        mode: args.mode,
+    };
+
+    let overwrite: bool = args.overwrite;
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    process(input, padding, output, overwrite).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+// Process all the content (single file or directory of files)
+fn process(input: &Path, padding: Padding, output: &Path, overwrite: bool) -> Result<()> {
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        process_file(input, padding, output, overwrite)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths, in a format that can be written
+        let outputs = nested_outputs(&files, input, output, with_writable_extension)?;
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, padding, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
+    }
+    Ok(())
+}
+
+// Index of the frame mirrored into [0, n) around the first and last frames, without repeating them
+fn reflect(index: i64, n: i64) -> usize {
+    if n == 1 {
+        return 0;
+    }
+
+    let period = 2 * (n - 1);
+    let index = index.rem_euclid(period);
+    match index < n {
+        true => index as usize,
+        false => (period - index) as usize,
+    }
+}
+
+// Frames added before or after the audio, `indices` being relative to its first frame
+fn fill(audio: &[f64], channels: usize, indices: std::ops::Range<i64>, mode: PadMode) -> Vec<f64> {
+    match mode {
+        PadMode::Silence => vec![0.0; indices.count() * channels],
+        PadMode::Reflect => {
+            let n = (audio.len() / channels) as i64;
+            indices
+                .flat_map(|index| {
+                    let frame = reflect(index, n);
+                    audio[frame * channels..(frame + 1) * channels]
+                        .iter()
+                        .copied()
+                })
+                .collect()
+        }
+    }
+}
+
+// Process a single file
+fn process_file(input: &Path, padding: Padding, output: &Path, overwrite: bool) -> Result<()> {
+    // Check that we can overwrite
+    if input == output && !overwrite {
+        return Err(anyhow::Error::msg("Can't overwrite files"));
+    }
+
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let sample_rate = spec.sample_rate as f32;
+    let channels = spec.channels as usize;
+
+    // Compute the requested length in frames
+    let length_frames = (sample_rate * padding.length) as usize;
+
+    // Only files shorter than the target are padded, so reading up to it is enough
+    let mut audio = Vec::new();
+    loop {
+        let remaining = (length_frames + 1).saturating_sub(audio.len() / channels);
+        if remaining == 0 {
+            break;
+        }
+
+        let block = reader
+            .read_samples(usize::min(remaining, BLOCK_FRAMES) * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+        audio.extend_from_slice(&block);
+    }
+    let frames = audio.len() / channels;
+
+    if frames == 0 && padding.mode == PadMode::Reflect {
+        return Err(anyhow::Error::msg("Can't reflect an empty file"));
+    }
+
+    // Write to a temporary file first, so that the input can be safely overwritten
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), spec)?;
+
+    if frames > length_frames {
+        // Longer files are written unchanged
+        writer
+            .write_samples(&audio)
+            .with_context(|| "Failed to write audio samples")?;
+        loop {
+            let block = reader
+                .read_samples(BLOCK_FRAMES * channels)
+                .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+            if block.is_empty() {
+                break;
+            }
+            writer
+                .write_samples(&block)
+                .with_context(|| "Failed to write audio samples")?;
+        }
+    } else {
+        // Split the missing frames between start and end
+        let missing = length_frames - frames;
+        let (before, after) = match padding.position {
+            PadPosition::Start => (missing, 0),
+            PadPosition::End => (0, missing),
+            PadPosition::Both => (missing / 2, missing - missing / 2),
+        };
+
+        let (frames, before, after) = (frames as i64, before as i64, after as i64);
+        let leading = fill(&audio, channels, -before..0, padding.mode);
+        let trailing = fill(&audio, channels, frames..frames + after, padding.mode);
+        for samples in [&leading, &audio, &trailing] {
+            writer
+                .write_samples(samples)
+                .with_context(|| "Failed to write audio samples")?;
+        }
+    }
+    writer.finalize()?;
+
+    // Move the padded file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
+}
@@ -1,44 +1,75 @@
 use anyhow::{Context, Result};
-use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use hound::{SampleFormat, WavSpec};
 use rayon::prelude::*;
-use rubato::{FftFixedIn, Resampler};
-use std::fs::{copy, File};
-use std::io::BufReader;
+use rubato::{
+    calculate_cutoff, FftFixedIn, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
+    VecResampler, WindowFunction,
+};
+use std::fs::copy;
 use std::path::Path;
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
-use crate::utils::{file_has_right_extension, perform_io_sanity_check};
-use crate::AudioResampleArgs;
-
-// Admissible extensions for this command
-const EXTENSIONS: [&str; 1] = ["wav"];
+use crate::utils::audio::{
+    temp_output, with_writable_extension, AudioReader, AudioWriter, Dither, READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioResampleArgs, ResampleQuality};
+
+// Number of input frames per channel fed to the resampler at a time
+const CHUNK_FRAMES: usize = 8192;
+
+// Largest bit depth whose quantization noise is worth dithering
+const MAX_DITHER_BIT_DEPTH: u16 = 16;
+
+// Target and method of the resampling
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    sr: u32,
+    quality: ResampleQuality,
+    bit_depth: Option<u16>,
+    dither: bool,
+}
 
 pub fn execute(args: AudioResampleArgs) -> Result<()> {
     // Parse the arguments
     let input = Path::new(&args.input);
     let output = Path::new(&args.output);
 
-    let sr: u32 = args.sr;
+    let settings = Settings {
+        sr: args.sr,
+        quality: args.quality,
+        bit_depth: args.bit_depth,
+        dither: args.dither,
+    };
 
     let overwrite: bool = args.overwrite;
 
+    if let Some(bits) = settings.bit_depth {
+        if ![8, 16, 24, 32].contains(&bits) {
+            return Err(anyhow::Error::msg(format!(
+                "Bit depth must be 8, 16, 24 or 32, got {}",
+                bits
+            )));
+        }
+    }
+
     // Sanity checks on I/O
     perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
 
     // Process files
-    process(input, sr, output, overwrite).with_context(|| "Processing failed")?;
+    process(input, settings, output, overwrite).with_context(|| "Processing failed")?;
 
     Ok(())
 }
 
 /// Process all the content (single file or directory of files)
-fn process(input: &Path, sr: u32, output: &Path, overwrite: bool) -> Result<()> {
+fn process(input: &Path, settings: Settings, output: &Path, overwrite: bool) -> Result<()> {
     // Case of single input file
     if input.is_file() {
         // Check if the file has the right extension and process it
-        file_has_right_extension(input, &EXTENSIONS)?;
-        process_file(input, sr, output, overwrite)
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        process_file(input, settings, output, overwrite)
             .with_context(|| format!("Failed to process file: {:?}", input))?;
     }
     // Case of input being a directory
@@ -47,80 +78,159 @@ fn process(input: &Path, sr: u32, output: &Path, overwrite: bool) -> Result<()>
         let files: Vec<PathBuf> = WalkDir::new(input)
             .into_iter()
             .filter_map(|e| e.ok())
-            .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
             .map(|e| e.path().to_path_buf())
             .collect();
 
+        // Nested output paths, in a format that can be written
+        let outputs = nested_outputs(&files, input, output, with_writable_extension)?;
+
         // Parallel loop over entries
-        files.par_iter().try_for_each(|file| -> Result<()> {
-            // Relative path wrt input directory
-            let relative_path = file
-                .strip_prefix(input)
-                .with_context(|| format!("Failed to strip prefix from path: {:?}", file))?;
-
-            // Nested output path
-            let file_output = output.join(relative_path);
-
-            // Ensure the output directory exists
-            if let Some(parent) = file_output.parent() {
-                std::fs::create_dir_all(parent)
-                    .with_context(|| format!("Failed to create output directory: {:?}", parent))?;
-            }
-
-            // Process the file
-            process_file(file, sr, &file_output, overwrite)
-                .with_context(|| format!("Failed to process file: {:?}", file))?;
-
-            Ok(())
-        })?;
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, settings, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
     }
     Ok(())
 }
 
-/// Read samples and convert to f64
-fn read_samples(
-    reader: &mut WavReader<BufReader<File>>,
+/// De-interleave samples by channel
+fn deinterleave(samples: &[f64], channels: usize) -> Vec<Vec<f64>> {
+    (0..channels)
+        .map(|channel| {
+            samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+/// Resampler of a quality preset, fed with fixed-size chunks
+fn resampler(
+    quality: ResampleQuality,
+    from: u32,
+    to: u32,
     channels: usize,
-    bits_per_sample: u16,
-) -> Result<Vec<Vec<f64>>> {
-    // Init samples vec
-    let mut samples: Vec<Vec<f64>> = vec![Vec::new(); channels];
-
-    // Calculate the maximum value based on bits_per_sample
-    let max_value = 2_f64.powi(bits_per_sample as i32 - 1);
-
-    // Read into samples vec
-    reader
-        .samples::<i32>()
-        .map(|s| s.with_context(|| "Couldn't read samples".to_string()))
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .enumerate()
-        .for_each(|(i, &sample)| {
-            // Normalize by dividing by max_value
-            samples[i % channels].push(sample as f64 / max_value);
-        });
-
-    Ok(samples)
+) -> Result<Box<dyn VecResampler<f64>>> {
+    let sinc = |sinc_len: usize,
+                interpolation: SincInterpolationType,
+                window: WindowFunction|
+     -> Result<Box<dyn VecResampler<f64>>> {
+        let parameters = SincInterpolationParameters {
+            sinc_len,
+            f_cutoff: calculate_cutoff(sinc_len, window),
+            oversampling_factor: sinc_len,
+            interpolation,
+            window,
+        };
+        let resampler = SincFixedIn::<f64>::new(
+            to as f64 / from as f64,
+            1.0,
+            parameters,
+            CHUNK_FRAMES,
+            channels,
+        )?;
+        Ok(Box::new(resampler))
+    };
+
+    match quality {
+        ResampleQuality::Fast => sinc(64, SincInterpolationType::Linear, WindowFunction::Hann2),
+        ResampleQuality::Balanced => Ok(Box::new(FftFixedIn::<f64>::new(
+            from as usize,
+            to as usize,
+            CHUNK_FRAMES,
+            2,
+            channels,
+        )?)),
+        ResampleQuality::Best => sinc(
+            256,
+            SincInterpolationType::Cubic,
+            WindowFunction::BlackmanHarris2,
+        ),
+    }
+}
+
+/// Interleave resampled channels and write them, dropping the resampler delay
+/// and anything past the expected length
+fn write_resampled(
+    writer: &mut AudioWriter,
+    dither: &mut Option<Dither>,
+    resampled: &[Vec<f64>],
+    delay: &mut usize,
+    written: &mut usize,
+    expected: usize,
+) -> Result<()> {
+    let frames = resampled[0].len();
+    let start = usize::min(*delay, frames);
+    let end = usize::min(
+        frames,
+        start.saturating_add(expected.saturating_sub(*written)),
+    );
+    *delay -= start;
+
+    let mut interleaved: Vec<f64> = (start..end)
+        .flat_map(|i| resampled.iter().map(move |channel| channel[i]))
+        .collect();
+    if let Some(dither) = dither {
+        dither.apply(&mut interleaved);
+    }
+
+    writer
+        .write_samples(&interleaved)
+        .with_context(|| "Failed to write audio samples")?;
+    *written += end - start;
+
+    Ok(())
 }
 
 /// Process a single file
-fn process_file(input: &Path, sr: u32, output: &Path, overwrite: bool) -> Result<()> {
+fn process_file(input: &Path, settings: Settings, output: &Path, overwrite: bool) -> Result<()> {
     // Check that we can overwrite
     if input == output && !overwrite {
         return Err(anyhow::Error::msg("Can't overwrite files"));
     }
 
-    // Open the WAV file
-    let mut reader = WavReader::open(input).with_context(|| "Failed to open WavReader")?;
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
 
     // Extract info from file
     let spec = reader.spec();
     let original_sr = spec.sample_rate;
+    let sr = settings.sr;
     let channels = spec.channels as usize;
 
-    // If the original sample rate is the same as the target, no need to resample
-    if original_sr == sr {
+    // Create a new specification for the resampled audio
+    let resampled_spec = match settings.bit_depth {
+        Some(bits_per_sample) => WavSpec {
+            sample_rate: sr,
+            bits_per_sample,
+            sample_format: SampleFormat::Int,
+            ..spec
+        },
+        None => WavSpec {
+            sample_rate: sr,
+            ..spec
+        },
+    };
+
+    // If the specs and the format do not change, no need to resample
+    if resampled_spec == spec && input.extension() == output.extension() {
         // Just copy the file if input does not coincide with output
         if input != output {
             copy(input, output).with_context(|| "Failed to copy file")?;
@@ -129,60 +239,85 @@ fn process_file(input: &Path, sr: u32, output: &Path, overwrite: bool) -> Result
         return Ok(());
     }
 
-    // Read samples
-    let samples = read_samples(&mut reader, channels, spec.bits_per_sample)
-        .with_context(|| "Couldn't read file")?;
-
     // Initialize the resampler
-    let mut resampler = FftFixedIn::<f64>::new(
-        original_sr as usize,
-        sr as usize,
-        samples[0].len(), // Number of frames per channel
-        1024,
-        channels,
-    )
-    .with_context(|| "Can't initiate resampler")?;
-
-    // Perform the resampling
-    let resampled_64 = resampler
-        .process(&samples, None)
+    let mut resampler = resampler(settings.quality, original_sr, sr, channels)
+        .with_context(|| "Can't initiate resampler")?;
+
+    // Dither only matters when quantization noise is audible
+    let mut dither = match settings.dither
+        && resampled_spec.sample_format == SampleFormat::Int
+        && resampled_spec.bits_per_sample <= MAX_DITHER_BIT_DEPTH
+    {
+        true => Some(Dither::new(resampled_spec.bits_per_sample)),
+        false => None,
+    };
+
+    // Write to a temporary file first, so that the input can be safely overwritten
+    let temp = temp_output(output)?;
+    let mut writer = AudioWriter::create(temp.path(), resampled_spec)?;
+
+    // The output lags behind the input by the resampler delay, and its length is only
+    // known once the whole input has been read
+    let mut delay = resampler.output_delay();
+    let mut frames_in = 0;
+    let mut written = 0;
+    let mut expected = usize::MAX;
+
+    loop {
+        // Read the next chunk
+        let needed = resampler.input_frames_next();
+        let block = reader
+            .read_samples(needed * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        let frames = block.len() / channels;
+        frames_in += frames;
+
+        // Perform the resampling, on a shorter chunk at the end of the file
+        let resampled = if frames == needed {
+            resampler.process(&deinterleave(&block, channels), None)
+        } else {
+            expected = (frames_in as u64 * sr as u64).div_ceil(original_sr as u64) as usize;
+            resampler.process_partial(Some(&deinterleave(&block, channels)), None)
+        }
         .with_context(|| "Can't resample file")?;
 
-    // Create a new WAV specification for the resampled audio
-    let resampled_spec = WavSpec {
-        channels: channels as u16,
-        sample_rate: sr,
-        bits_per_sample: spec.bits_per_sample,
-        sample_format: SampleFormat::Int,
-    };
+        write_resampled(
+            &mut writer,
+            &mut dither,
+            &resampled,
+            &mut delay,
+            &mut written,
+            expected,
+        )?;
+
+        if frames < needed {
+            break;
+        }
+    }
 
-    // Init writer
-    let mut writer = WavWriter::create(output, resampled_spec)
-        .with_context(|| format!("Couldn't write to {:?}", output))?;
-
-    // Calculate the max value based on bits_per_sample for proper scaling
-    let max_value = 2_f64.powi((spec.bits_per_sample - 1) as i32);
-
-    // Write samples interleaved
-    for i in 0..resampled_64[0].len() {
-        for channel in &resampled_64 {
-            // Scale back to the appropriate integer range
-            let scaled_sample = (channel[i] * max_value).round();
-
-            // Write sample based on bits_per_sample
-            match spec.bits_per_sample {
-                8 => writer.write_sample(scaled_sample as i8)?,
-                16 => writer.write_sample(scaled_sample as i16)?,
-                24 | 32 => writer.write_sample(scaled_sample as i32)?,
-                _ => {
-                    return Err(anyhow::Error::msg(format!(
-                        "Unsupported bits per sample: {}",
-                        spec.bits_per_sample
-                    )))
-                }
-            }
+    // Flush the samples still held by the resampler
+    while written < expected {
+        let resampled = resampler
+            .process_partial(None, None)
+            .with_context(|| "Can't resample file")?;
+        if resampled[0].is_empty() {
+            break;
         }
+
+        write_resampled(
+            &mut writer,
+            &mut dither,
+            &resampled,
+            &mut delay,
+            &mut written,
+            expected,
+        )?;
     }
+    writer.finalize()?;
+
+    // Move the resampled file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
 
     Ok(())
 }
@@ -0,0 +1,531 @@
+use anyhow::{Context, Result};
+use image::{GrayImage, Luma};
+use polars::io::parquet::write::BatchedWriter;
+use polars::prelude::*;
+use rayon::prelude::*;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::utils::audio::{temp_output, AudioReader, BLOCK_FRAMES, READABLE_EXTENSIONS};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioSpectrogramArgs, SpectrogramFormat, SpectrogramWindow};
+
+// Power below which bins are clipped, to keep the logarithm finite
+const POWER_FLOOR: f64 = 1e-10;
+
+// Range of levels mapped to grey levels in images, in dB below the maximum
+const IMAGE_DYNAMIC_RANGE: f32 = 80.0;
+
+// Parameters of the short-time Fourier transform
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    format: SpectrogramFormat,
+    n_fft: usize,
+    hop: usize,
+    window: SpectrogramWindow,
+    mels: Option<usize>,
+}
+
+// Frames written to parquet files at a time, as one row group
+const PARQUET_BATCH_FRAMES: usize = 4096;
+
+// Longest spectrogram saved as an image, which has to be built in memory
+const MAX_IMAGE_FRAMES: usize = 65536;
+
+// Length of the NumPy preamble and header, enough for any shape so that it can be rewritten in place
+const NPY_HEADER_LEN: usize = 128;
+
+pub fn execute(args: AudioSpectrogramArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    let settings = Settings {
+        format: args.format,
+        n_fft: args.n_fft,
+        hop: args.hop,
+        window: args.window,
+        mels: args.mels,
+    };
+
+    // Check the frames cover the whole signal
+    if settings.n_fft == 0 || settings.hop == 0 {
+        return Err(anyhow::Error::msg("FFT size and hop must be positive"));
+    }
+    if settings.hop > settings.n_fft {
+        return Err(anyhow::Error::msg("Hop can't be larger than the FFT size"));
+    }
+    if settings.mels == Some(0) {
+        return Err(anyhow::Error::msg("Number of mel bands must be positive"));
+    }
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    process(input, settings, output).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+/// Process all the content (single file or directory of files)
+fn process(input: &Path, settings: Settings, output: &Path) -> Result<()> {
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+
+        // The output must match the format
+        file_has_right_extension(output, &[settings.format.extension()]).with_context(|| {
+            format!(
+                "Output file must have the .{} extension",
+                settings.format.extension()
+            )
+        })?;
+
+        process_file(input, settings, output)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths with the extension of the format
+        let outputs = nested_outputs(&files, input, output, |path| {
+            path.with_extension(settings.format.extension())
+        })?;
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, settings, file_output)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
+    }
+    Ok(())
+}
+
+/// Coefficients of the window function
+fn window(kind: SpectrogramWindow, n: usize) -> Vec<f64> {
+    // Periodic windows, as customary for spectral analysis
+    (0..n)
+        .map(|i| {
+            let x = 2.0 * PI * i as f64 / n as f64;
+            match kind {
+                SpectrogramWindow::Hann => 0.5 - 0.5 * x.cos(),
+                SpectrogramWindow::Hamming => 0.54 - 0.46 * x.cos(),
+                SpectrogramWindow::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+                SpectrogramWindow::Rectangular => 1.0,
+            }
+        })
+        .collect()
+}
+
+fn hz_to_mel(hz: f64) -> f64 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    700.0 * (10_f64.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular mel filters (HTK scale) from 0 Hz to Nyquist, as sparse (bin, weight) lists
+fn mel_filterbank(mels: usize, n_fft: usize, sample_rate: u32) -> Vec<Vec<(usize, f64)>> {
+    let nyquist = sample_rate as f64 / 2.0;
+    let top = hz_to_mel(nyquist);
+
+    // Edges of the filters, equally spaced on the mel scale
+    let edges: Vec<f64> = (0..mels + 2)
+        .map(|i| mel_to_hz(top * i as f64 / (mels + 1) as f64))
+        .collect();
+
+    (0..mels)
+        .map(|m| {
+            let (low, centre, high) = (edges[m], edges[m + 1], edges[m + 2]);
+            (0..n_fft / 2 + 1)
+                .filter_map(|bin| {
+                    let hz = bin as f64 * sample_rate as f64 / n_fft as f64;
+                    let weight =
+                        f64::min((hz - low) / (centre - low), (high - hz) / (high - centre));
+                    (weight > 0.0).then_some((bin, weight))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Short-time Fourier transform of one frame at a time
+struct Stft {
+    fft: std::sync::Arc<dyn RealToComplex<f64>>,
+    window: Vec<f64>,
+    scale: f64,
+    filterbank: Option<Vec<Vec<(usize, f64)>>>,
+}
+
+impl Stft {
+    fn new(settings: Settings, sample_rate: u32) -> Self {
+        let fft = RealFftPlanner::<f64>::new().plan_fft_forward(settings.n_fft);
+        let window = window(settings.window, settings.n_fft);
+
+        // Normalise so that a full-scale sine peaks at about -6 dB whatever the window
+        let scale = 1.0 / window.iter().sum::<f64>().powi(2);
+
+        let filterbank = settings
+            .mels
+            .map(|mels| mel_filterbank(mels, settings.n_fft, sample_rate));
+
+        Self {
+            fft,
+            window,
+            scale,
+            filterbank,
+        }
+    }
+
+    /// Number of values in each column
+    fn bins(&self) -> usize {
+        match &self.filterbank {
+            Some(filterbank) => filterbank.len(),
+            None => self.window.len() / 2 + 1,
+        }
+    }
+
+    /// Levels in dB of a frame of `n_fft` samples
+    fn column(&self, frame: &[f64]) -> Result<Vec<f32>> {
+        let mut input: Vec<f64> = frame
+            .iter()
+            .zip(&self.window)
+            .map(|(sample, weight)| sample * weight)
+            .collect();
+        let mut spectrum = self.fft.make_output_vec();
+        self.fft
+            .process(&mut input, &mut spectrum)
+            .map_err(|e| anyhow::Error::msg(e.to_string()))
+            .with_context(|| "Failed to compute FFT")?;
+
+        let power: Vec<f64> = spectrum
+            .iter()
+            .map(|value| value.norm_sqr() * self.scale)
+            .collect();
+
+        // Optionally collapse the bins into mel bands
+        let levels = match &self.filterbank {
+            Some(filterbank) => filterbank
+                .iter()
+                .map(|filter| {
+                    filter
+                        .iter()
+                        .map(|&(bin, weight)| power[bin] * weight)
+                        .sum()
+                })
+                .collect(),
+            None => power,
+        };
+
+        Ok(levels
+            .into_iter()
+            .map(|level| (10.0 * level.max(POWER_FLOOR).log10()) as f32)
+            .collect())
+    }
+}
+
+/// Destination of the columns of a spectrogram, written as they are computed
+enum Sink {
+    // Images need the loudest bin, so the columns are kept until the end
+    Png {
+        columns: Vec<Vec<f32>>,
+        bins: usize,
+    },
+    // Stored in column-major order, so that every frame is appended as is
+    Npy {
+        writer: BufWriter<File>,
+        bins: usize,
+        frames: usize,
+    },
+    // Rows are buffered and written one row group at a time
+    Parquet {
+        writer: Box<BatchedWriter<File>>,
+        names: Vec<String>,
+        rows: Vec<Vec<f32>>,
+        frames: usize,
+        seconds_per_frame: f64,
+    },
+}
+
+impl Sink {
+    fn create(settings: Settings, bins: usize, sample_rate: u32, path: &Path) -> Result<Self> {
+        match settings.format {
+            SpectrogramFormat::Png => Ok(Self::Png {
+                columns: Vec::new(),
+                bins,
+            }),
+            SpectrogramFormat::Npy => {
+                let file = File::create(path)
+                    .with_context(|| format!("Failed to create file: {:?}", path))?;
+                let mut writer = BufWriter::new(file);
+
+                // Placeholder header, rewritten with the number of frames once known
+                writer.write_all(&npy_header(bins, 0)?)?;
+
+                Ok(Self::Npy {
+                    writer,
+                    bins,
+                    frames: 0,
+                })
+            }
+            SpectrogramFormat::Parquet => {
+                let prefix = match settings.mels {
+                    Some(_) => "mel",
+                    None => "bin",
+                };
+                let width = (bins - 1).to_string().len();
+                let names: Vec<String> = (0..bins)
+                    .map(|bin| format!("{}_{:0width$}", prefix, bin, width = width))
+                    .collect();
+
+                let schema = Schema::from_iter(
+                    std::iter::once(Field::new("time_seconds".into(), DataType::Float64)).chain(
+                        names
+                            .iter()
+                            .map(|name| Field::new(name.into(), DataType::Float32)),
+                    ),
+                );
+                let file = File::create(path)
+                    .with_context(|| format!("Failed to create file: {:?}", path))?;
+                let writer = ParquetWriter::new(file).batched(&schema)?;
+
+                Ok(Self::Parquet {
+                    writer: Box::new(writer),
+                    names,
+                    rows: Vec::new(),
+                    frames: 0,
+                    seconds_per_frame: settings.hop as f64 / sample_rate as f64,
+                })
+            }
+        }
+    }
+
+    /// Number of frames received so far
+    fn frames(&self) -> usize {
+        match self {
+            Self::Png { columns, .. } => columns.len(),
+            Self::Npy { frames, .. } => *frames,
+            Self::Parquet { frames, rows, .. } => frames + rows.len(),
+        }
+    }
+
+    /// Add the levels of the next frame
+    fn push(&mut self, column: Vec<f32>) -> Result<()> {
+        match self {
+            Self::Png { columns, .. } => {
+                if columns.len() == MAX_IMAGE_FRAMES {
+                    return Err(anyhow::Error::msg(format!(
+                        "Spectrogram longer than {} frames can't be saved as an image, use npy or parquet",
+                        MAX_IMAGE_FRAMES
+                    )));
+                }
+                columns.push(column);
+            }
+            Self::Npy { writer, frames, .. } => {
+                for level in column {
+                    writer.write_all(&level.to_le_bytes())?;
+                }
+                *frames += 1;
+            }
+            Self::Parquet { rows, .. } => {
+                rows.push(column);
+                if rows.len() == PARQUET_BATCH_FRAMES {
+                    self.write_rows()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the buffered rows of a parquet file as one row group
+    fn write_rows(&mut self) -> Result<()> {
+        if let Self::Parquet {
+            writer,
+            names,
+            rows,
+            frames,
+            seconds_per_frame,
+        } = self
+        {
+            if rows.is_empty() {
+                return Ok(());
+            }
+
+            let times: Vec<f64> = (*frames..*frames + rows.len())
+                .map(|frame| frame as f64 * *seconds_per_frame)
+                .collect();
+            let mut series = vec![Series::new("time_seconds".into(), times)];
+            for (bin, name) in names.iter().enumerate() {
+                let levels: Vec<f32> = rows.iter().map(|row| row[bin]).collect();
+                series.push(Series::new(name.into(), levels));
+            }
+            writer.write_batch(&DataFrame::new(series)?)?;
+
+            *frames += rows.len();
+            rows.clear();
+        }
+        Ok(())
+    }
+
+    /// Complete the file once all the frames are received
+    fn finish(mut self, path: &Path) -> Result<()> {
+        self.write_rows()?;
+
+        match self {
+            Self::Png { columns, bins } => write_png(&columns, bins, path),
+            Self::Npy {
+                mut writer,
+                bins,
+                frames,
+            } => {
+                writer.seek(SeekFrom::Start(0))?;
+                writer.write_all(&npy_header(bins, frames)?)?;
+                writer
+                    .flush()
+                    .with_context(|| format!("Failed to write file: {:?}", path))
+            }
+            Self::Parquet { writer, .. } => {
+                writer
+                    .finish()
+                    .with_context(|| format!("Failed to write parquet file: {:?}", path))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Preamble and header of a little-endian float32 NumPy array of shape (bins, frames), in column-major order
+fn npy_header(bins: usize, frames: usize) -> Result<Vec<u8>> {
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': True, 'shape': ({}, {}), }}",
+        bins, frames
+    );
+
+    // Padded with spaces so that the data starts on a multiple of 64 bytes
+    let preamble = 10;
+    if preamble + header.len() + 1 > NPY_HEADER_LEN {
+        return Err(anyhow::Error::msg("Shape too large for the NumPy header"));
+    }
+    header.push_str(&" ".repeat(NPY_HEADER_LEN - preamble - header.len() - 1));
+    header.push('\n');
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    Ok(bytes)
+}
+
+/// Save as a grayscale image, with the loudest bin in white
+fn write_png(columns: &[Vec<f32>], bins: usize, output: &Path) -> Result<()> {
+    let max = columns
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    let mut image = GrayImage::new(columns.len() as u32, bins as u32);
+    for (x, column) in columns.iter().enumerate() {
+        for (bin, &level) in column.iter().enumerate() {
+            let value = (level - max + IMAGE_DYNAMIC_RANGE) / IMAGE_DYNAMIC_RANGE;
+            let y = (bins - 1 - bin) as u32;
+            image.put_pixel(
+                x as u32,
+                y,
+                Luma([(value.clamp(0.0, 1.0) * 255.0).round() as u8]),
+            );
+        }
+    }
+
+    image
+        .save(output)
+        .with_context(|| format!("Failed to save image: {:?}", output))
+}
+
+/// Process a single file, mixing all the channels into mono
+fn process_file(input: &Path, settings: Settings, output: &Path) -> Result<()> {
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let stft = Stft::new(settings, spec.sample_rate);
+
+    // Write to a temporary file first, so that the output is never left half-written
+    let temp = temp_output(output)?;
+    let mut sink = Sink::create(settings, stft.bins(), spec.sample_rate, temp.path())?;
+
+    let mut buffer: Vec<f64> = Vec::new();
+
+    // Process the frames available after every block
+    loop {
+        let block = reader
+            .read_samples(BLOCK_FRAMES * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+        if block.is_empty() {
+            break;
+        }
+
+        buffer.extend(
+            block
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f64>() / channels as f64),
+        );
+
+        let mut start = 0;
+        while start + settings.n_fft <= buffer.len() {
+            sink.push(stft.column(&buffer[start..start + settings.n_fft])?)?;
+            start += settings.hop;
+        }
+        buffer.drain(..start);
+    }
+
+    // Pad the last frame with zeros if some samples were not covered yet
+    let covered = match sink.frames() {
+        0 => 0,
+        _ => settings.n_fft - settings.hop,
+    };
+    if buffer.len() > covered {
+        buffer.resize(settings.n_fft, 0.0);
+        sink.push(stft.column(&buffer)?)?;
+    }
+
+    if sink.frames() == 0 {
+        return Err(anyhow::Error::msg("Audio file is empty"));
+    }
+
+    sink.finish(temp.path())?;
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
+}
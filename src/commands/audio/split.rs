@@ -1,23 +1,85 @@
 use anyhow::{Context, Result};
-use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use hound::WavSpec;
+use polars::prelude::*;
 use rayon::prelude::*;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
+use tempfile::TempPath;
 use walkdir::WalkDir;
 
-use crate::utils::{file_has_right_extension, perform_io_sanity_check};
-use crate::AudioSplitArgs;
+use crate::utils::audio::{
+    temp_output, with_writable_extension, AudioReader, AudioWriter, BLOCK_FRAMES,
+    READABLE_EXTENSIONS,
+};
+use crate::utils::{
+    file_has_right_extension, nested_outputs, perform_io_sanity_check, write_table,
+};
+use crate::{AudioSplitArgs, SplitMode, SplitTail};
 
-// Admissible extensions for this command
-const EXTENSIONS: [&str; 1] = ["wav"];
+// Duration in seconds of the windows whose level is measured in silence mode
+const SILENCE_WINDOW_SECONDS: f32 = 0.02;
+
+// Most overlapping chunks written at the same time, each holding an open file
+const MAX_OPEN_CHUNKS: usize = 64;
+
+// How files are cut into segments, with durations in seconds
+#[derive(Debug, Clone, Copy)]
+enum Cut {
+    Fixed {
+        chunk_duration: f32,
+        hop: f32,
+        tail: SplitTail,
+    },
+    Silence {
+        max_segment: f32,
+        threshold: f32,
+        min_silence: f32,
+        min_segment: f32,
+    },
+}
+
+// Position in seconds of a written segment within its source file
+struct Boundary {
+    source: PathBuf,
+    segment: PathBuf,
+    start: f64,
+    end: f64,
+}
 
 pub fn execute(args: AudioSplitArgs) -> Result<()> {
     // Parse the arguments
     let input = Path::new(&args.input);
     let output = Path::new(&args.output);
 
-    let chunk_duration_sec: f32 = args.chunk_duration;
+    let cut = match args.mode {
+        SplitMode::Fixed => {
+            let hop = args.hop.unwrap_or(args.chunk_duration);
+
+            // Every chunk overlapping the current position is being written
+            if hop <= 0.0 {
+                return Err(anyhow::Error::msg("Hop must be positive"));
+            }
+            if (args.chunk_duration / hop).ceil() as usize > MAX_OPEN_CHUNKS {
+                return Err(anyhow::Error::msg(format!(
+                    "Hop is too short, chunks can overlap at most {} times",
+                    MAX_OPEN_CHUNKS
+                )));
+            }
+
+            Cut::Fixed {
+                chunk_duration: args.chunk_duration,
+                hop,
+                tail: args.tail,
+            }
+        }
+        SplitMode::Silence => Cut::Silence {
+            max_segment: args.chunk_duration,
+            threshold: args.threshold,
+            min_silence: args.min_silence,
+            min_segment: args.min_segment,
+        },
+    };
 
     let delete_original: bool = args.delete_original;
 
@@ -25,29 +87,32 @@ pub fn execute(args: AudioSplitArgs) -> Result<()> {
     perform_io_sanity_check(input, output, false, false).with_context(|| "Sanity check failed")?;
 
     // Process files
-    process(input, chunk_duration_sec, output, delete_original)
-        .with_context(|| "Processing failed")?;
+    let boundaries =
+        process(input, cut, output, delete_original).with_context(|| "Processing failed")?;
+
+    // Write the segment boundaries if requested
+    if let Some(path) = &args.boundaries {
+        let path = Path::new(path);
+        write_table(&mut build_boundaries(&boundaries)?, path)
+            .with_context(|| format!("Failed to write boundaries: {:?}", path))?;
+    }
 
     Ok(())
 }
 
 // Process all the content (single file or directory of files)
-fn process(
-    input: &Path,
-    chunk_duration_sec: f32,
-    output: &Path,
-    delete_original: bool,
-) -> Result<()> {
+fn process(input: &Path, cut: Cut, output: &Path, delete_original: bool) -> Result<Vec<Boundary>> {
     // Case of single input file
     if input.is_file() {
         // Check if the file has the right extension and process it
-        file_has_right_extension(input, &EXTENSIONS)?;
-        process_file(input, chunk_duration_sec, output)
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        let boundaries = process_file(input, cut, output)
             .with_context(|| format!("Failed to process file: {:?}", input))?;
         if delete_original {
             fs::remove_file(input)
                 .with_context(|| format!("Failed to delete file: {:?}", input))?;
         }
+        Ok(boundaries)
     }
     // Case of input being a directory
     else {
@@ -55,67 +120,57 @@ fn process(
         let files: Vec<PathBuf> = WalkDir::new(input)
             .into_iter()
             .filter_map(|e| e.ok())
-            .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
             .map(|e| e.path().to_path_buf())
             .collect();
 
+        // Chunks are named after their file, which must stay unique once written as WAV
+        nested_outputs(&files, input, output, with_writable_extension)?;
+
         // Parallel loop over entries
-        files.par_iter().try_for_each(|file| -> Result<()> {
-            // Relative path wrt input directory
-            let relative_path = file
-                .strip_prefix(input)
-                .with_context(|| format!("Failed to strip prefix from path: {:?}", file))?;
-
-            let joined_path = output.join(relative_path);
-            let output_directory = joined_path.parent().with_context(|| {
-                format!("Failed to get parent directory of: {:?}", relative_path)
-            })?;
-
-            // Create output directory
-            std::fs::create_dir_all(output_directory).with_context(|| {
-                format!("Failed to create output directory: {:?}", output_directory)
-            })?;
-
-            // Process the file
-            process_file(file, chunk_duration_sec, output_directory)
-                .with_context(|| format!("Failed to process file: {:?}", file))?;
-
-            if delete_original {
-                fs::remove_file(file)
-                    .with_context(|| format!("Failed to delete file: {:?}", file))?;
-            }
+        let boundaries = files
+            .par_iter()
+            .map(|file| -> Result<Vec<Boundary>> {
+                // Relative path wrt input directory
+                let relative_path = file
+                    .strip_prefix(input)
+                    .with_context(|| format!("Failed to strip prefix from path: {:?}", file))?;
+
+                let joined_path = output.join(relative_path);
+                let output_directory = joined_path.parent().with_context(|| {
+                    format!("Failed to get parent directory of: {:?}", relative_path)
+                })?;
+
+                // Create output directory
+                std::fs::create_dir_all(output_directory).with_context(|| {
+                    format!("Failed to create output directory: {:?}", output_directory)
+                })?;
+
+                // Process the file
+                let boundaries = process_file(file, cut, output_directory)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                if delete_original {
+                    fs::remove_file(file)
+                        .with_context(|| format!("Failed to delete file: {:?}", file))?;
+                }
+
+                Ok(boundaries)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-            Ok(())
-        })?;
+        Ok(boundaries.into_iter().flatten().collect())
     }
-    Ok(())
 }
 
 // Process a single file
-fn process_file(input: &Path, chunk_duration_sec: f32, output: &Path) -> Result<()> {
-    // Open the WAV file
-    let mut reader = WavReader::open(input).with_context(|| "Failed to open WavReader")?;
+fn process_file(input: &Path, cut: Cut, output: &Path) -> Result<Vec<Boundary>> {
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
 
     // Extract info from file
     let spec = reader.spec();
     let sample_rate = spec.sample_rate as f32;
-    let channels = spec.channels as usize;
-
-    // Compute the expected size in samples for a chunk
-    let chunk_size = (sample_rate * chunk_duration_sec) as usize * channels;
-
-    // Read the samples and find the total number of samples
-    let samples: Vec<i32> = reader
-        .samples::<i32>()
-        .collect::<Result<Vec<i32>, _>>()
-        .with_context(|| format!("Couldn't read samples from {:?}", input))?;
-    let total_samples = samples.len();
-
-    // Calculate the number of chunks the file will be split into
-    let num_chunks = total_samples.div_ceil(chunk_size);
-
-    // Calculate the number of digits needed when padding the name with 0's
-    let padding_width = format!("{}", num_chunks - 1).len();
 
     // Calculate the stem
     let stem = input
@@ -124,41 +179,338 @@ fn process_file(input: &Path, chunk_duration_sec: f32, output: &Path) -> Result<
         .to_str()
         .with_context(|| format!("Failed to convert stem to string for: {:?}", input))?;
 
-    for i in 0..num_chunks {
-        // Determine start and end of each chunk wrt original file
-        let start = i * chunk_size;
-        let end = usize::min(start + chunk_size, total_samples);
+    // Chunks are written in the input format if possible, as WAV otherwise
+    let extension = with_writable_extension(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_string())
+        .with_context(|| format!("Failed to extract extension from: {:?}", input))?;
 
-        // Extract the samples for that chunk
-        let mut chunk_samples: Vec<i32> = samples[start..end].to_vec();
+    // Segments are written to temporary files as they are found, since their final names
+    // depend on how many there are
+    let template = output.join(format!("{}.{}", stem, extension));
+    let segments = match cut {
+        Cut::Fixed {
+            chunk_duration,
+            hop,
+            tail,
+        } => {
+            let chunk_frames = (sample_rate * chunk_duration) as usize;
+            let hop_frames = (sample_rate * hop) as usize;
+            if hop_frames == 0 {
+                return Err(anyhow::Error::msg("Hop is too short"));
+            }
 
-        // Pad with zeros if the chunk is not full
-        if chunk_samples.len() < chunk_size {
-            chunk_samples.extend(vec![0; chunk_size - chunk_samples.len()]);
+            let mut sink = SegmentSink::new(&template, spec, 0, chunk_frames)?;
+            split_fixed(&mut reader, &mut sink, hop_frames, tail)
+                .with_context(|| format!("Couldn't split {:?}", input))?;
+            sink.segments
         }
+        Cut::Silence {
+            max_segment,
+            threshold,
+            min_silence,
+            min_segment,
+        } => {
+            let min_frames = (sample_rate * min_segment) as usize;
+            let max_frames = (sample_rate * max_segment) as usize;
+            let mut sink = SegmentSink::new(&template, spec, min_frames, max_frames)?;
+            split_silence(&mut reader, &mut sink, threshold, min_silence)
+                .with_context(|| format!("Couldn't split {:?}", input))?;
+            sink.segments
+        }
+    };
 
-        // Define the output path for the chunk
-        let output_path = output.join(format!("{}@{:0width$}.wav", stem, i, width = padding_width));
+    // Calculate the number of digits needed when padding the name with 0's
+    let padding_width = format!("{}", segments.len().saturating_sub(1)).len();
 
-        // Define the specs for writing to file
-        let spec = WavSpec {
-            channels: spec.channels,
-            sample_rate: spec.sample_rate,
-            bits_per_sample: spec.bits_per_sample,
-            sample_format: SampleFormat::Int,
-        };
+    // Move the segments to their final names
+    let mut boundaries = Vec::with_capacity(segments.len());
+    for (i, segment) in segments.into_iter().enumerate() {
+        let output_path = output.join(format!(
+            "{}@{:0width$}.{}",
+            stem,
+            i,
+            extension,
+            width = padding_width
+        ));
 
-        // Init writer
-        let mut writer = WavWriter::create(&output_path, spec)
-            .with_context(|| format!("Couldn't write to {:?}", output_path))?;
+        segment
+            .temp
+            .persist(&output_path)
+            .with_context(|| format!("Failed to write {:?}", output_path))?;
 
-        // Write to file
-        chunk_samples.iter().try_for_each(|&sample| {
-            writer
-                .write_sample(sample)
-                .with_context(|| "Failed to write audio sample")
-        })?;
+        boundaries.push(Boundary {
+            source: input.to_path_buf(),
+            segment: output_path,
+            start: segment.start as f64 / spec.sample_rate as f64,
+            end: segment.end as f64 / spec.sample_rate as f64,
+        });
     }
 
-    Ok(())
+    Ok(boundaries)
+}
+
+// Cut a file into chunks of the maximum length starting every `hop_frames`, possibly overlapping
+fn split_fixed(
+    reader: &mut AudioReader,
+    sink: &mut SegmentSink,
+    hop_frames: usize,
+    tail: SplitTail,
+) -> Result<()> {
+    let channels = sink.spec.channels as usize;
+
+    let mut position = 0;
+    let mut next_start = 0;
+    loop {
+        let block = reader.read_samples(BLOCK_FRAMES * channels)?;
+        if block.is_empty() {
+            break;
+        }
+        let end = position + block.len() / channels;
+
+        // Open the chunks starting within the block
+        while next_start < end {
+            sink.open(next_start)?;
+            next_start += hop_frames;
+        }
+
+        sink.write(&block, position)?;
+        position = end;
+    }
+
+    // Chunks still open were cut short by the end of the file
+    match tail {
+        SplitTail::Pad => sink.pad(),
+        SplitTail::Drop => sink.discard(),
+        SplitTail::Keep => sink.close(),
+    }
+}
+
+// Cut a file at silences long enough, dropping them
+fn split_silence(
+    reader: &mut AudioReader,
+    sink: &mut SegmentSink,
+    threshold: f32,
+    min_silence: f32,
+) -> Result<()> {
+    let channels = sink.spec.channels as usize;
+    let sample_rate = sink.spec.sample_rate as f32;
+
+    let window_frames = usize::max((sample_rate * SILENCE_WINDOW_SECONDS) as usize, 1);
+    let min_silence_frames = (sample_rate * min_silence) as usize;
+
+    // Convert the threshold from dBFS to a linear RMS level
+    let threshold = 10_f64.powf(threshold as f64 / 20.0);
+
+    // Silent windows following the current segment, kept until they are known to be a pause
+    let mut silence: Vec<f64> = Vec::new();
+
+    let mut position = 0;
+    loop {
+        let window = reader.read_samples(window_frames * channels)?;
+        if window.is_empty() {
+            break;
+        }
+
+        if rms(&window) < threshold {
+            // Silence before the first segment or after a cut is dropped
+            if sink.is_open() {
+                silence.extend_from_slice(&window);
+
+                // A long enough silence ends the segment
+                if silence.len() / channels >= min_silence_frames {
+                    silence.clear();
+                    sink.close()?;
+                }
+            }
+        } else {
+            // Pauses shorter than the minimum silence belong to the segment
+            let pause_frames = silence.len() / channels;
+            sink.append(&silence, position - pause_frames)?;
+            silence.clear();
+
+            sink.append(&window, position)?;
+        }
+
+        position += window.len() / channels;
+    }
+
+    // Trailing silence is dropped
+    sink.close()
+}
+
+// Root mean square of a block of samples
+fn rms(samples: &[f64]) -> f64 {
+    (samples.iter().map(|sample| sample * sample).sum::<f64>() / samples.len() as f64).sqrt()
+}
+
+// A segment being written, with its start and length in frames
+struct OpenSegment {
+    temp: TempPath,
+    writer: AudioWriter,
+    start: usize,
+    frames: usize,
+}
+
+// A segment fully written, with its boundaries in frames
+struct Segment {
+    temp: TempPath,
+    start: usize,
+    end: usize,
+}
+
+// Receives consecutive samples of a file and writes them into the segments covering them
+struct SegmentSink {
+    template: PathBuf,
+    spec: WavSpec,
+    min_frames: usize,
+    max_frames: usize,
+    open: Vec<OpenSegment>,
+    segments: Vec<Segment>,
+}
+
+impl SegmentSink {
+    fn new(template: &Path, spec: WavSpec, min_frames: usize, max_frames: usize) -> Result<Self> {
+        if max_frames == 0 {
+            return Err(anyhow::Error::msg("Chunk duration is too short"));
+        }
+
+        Ok(Self {
+            template: template.to_path_buf(),
+            spec,
+            min_frames,
+            max_frames,
+            open: Vec::new(),
+            segments: Vec::new(),
+        })
+    }
+
+    fn is_open(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    // Start a new segment at frame `start`
+    fn open(&mut self, start: usize) -> Result<()> {
+        let temp = temp_output(&self.template)?.into_temp_path();
+        let writer = AudioWriter::create(&temp, self.spec)?;
+        self.open.push(OpenSegment {
+            temp,
+            writer,
+            start,
+            frames: 0,
+        });
+
+        Ok(())
+    }
+
+    // Write samples starting at frame `start` to the open segments covering them, closing the full ones
+    fn write(&mut self, samples: &[f64], start: usize) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        let end = start + samples.len() / channels;
+
+        for segment in &mut self.open {
+            let from = usize::max(start, segment.start + segment.frames);
+            let to = usize::min(end, segment.start + self.max_frames);
+            if from < to {
+                segment
+                    .writer
+                    .write_samples(&samples[(from - start) * channels..(to - start) * channels])
+                    .with_context(|| "Failed to write audio samples")?;
+                segment.frames += to - from;
+            }
+        }
+
+        let (full, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|segment| segment.frames == self.max_frames);
+        self.open = open;
+        full.into_iter()
+            .try_for_each(|segment| self.finish(segment))
+    }
+
+    // Append samples starting at frame `start` to a single segment, moving on to a new one when it is full
+    fn append(&mut self, samples: &[f64], start: usize) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        let end = start + samples.len() / channels;
+
+        let mut position = start;
+        while position < end {
+            if self.open.is_empty() {
+                self.open(position)?;
+            }
+
+            let until = usize::min(end, self.open[0].start + self.max_frames);
+            self.write(
+                &samples[(position - start) * channels..(until - start) * channels],
+                position,
+            )?;
+            position = until;
+        }
+
+        Ok(())
+    }
+
+    // Fill the first open segment with zeros up to the maximum length and finish it
+    //
+    // Later segments overlap it and would mostly hold padding, so they are thrown away.
+    fn pad(&mut self) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        let mut open = std::mem::take(&mut self.open).into_iter();
+
+        if let Some(mut segment) = open.next() {
+            let mut missing = self.max_frames - segment.frames;
+            while missing > 0 {
+                let frames = usize::min(missing, BLOCK_FRAMES);
+                segment
+                    .writer
+                    .write_samples(&vec![0.0; frames * channels])
+                    .with_context(|| "Failed to write audio samples")?;
+                missing -= frames;
+            }
+            self.finish(segment)?;
+        }
+
+        open.try_for_each(|segment| segment.writer.finalize())
+    }
+
+    // Finish the open segments
+    fn close(&mut self) -> Result<()> {
+        std::mem::take(&mut self.open)
+            .into_iter()
+            .try_for_each(|segment| self.finish(segment))
+    }
+
+    // Throw away the open segments
+    fn discard(&mut self) -> Result<()> {
+        std::mem::take(&mut self.open)
+            .into_iter()
+            .try_for_each(|segment| segment.writer.finalize())
+    }
+
+    // Finish a segment, discarding it if too short
+    fn finish(&mut self, segment: OpenSegment) -> Result<()> {
+        segment.writer.finalize()?;
+
+        if segment.frames >= self.min_frames {
+            self.segments.push(Segment {
+                temp: segment.temp,
+                start: segment.start,
+                end: segment.start + segment.frames,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Collect the boundaries of every segment into a table, one row per segment
+fn build_boundaries(boundaries: &[Boundary]) -> Result<DataFrame> {
+    df!(
+        "source" => boundaries.iter().map(|b| b.source.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "segment" => boundaries.iter().map(|b| b.segment.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "start_seconds" => boundaries.iter().map(|b| b.start).collect::<Vec<_>>(),
+        "end_seconds" => boundaries.iter().map(|b| b.end).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build boundaries")
 }
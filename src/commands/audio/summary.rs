@@ -1,15 +1,35 @@
 use anyhow::{Context, Result};
+use hound::SampleFormat;
 use lofty::{AudioFile, Probe};
+use polars::prelude::*;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-use crate::utils::file_has_right_extension;
+use crate::utils::audio::{AudioReader, BLOCK_FRAMES, READABLE_EXTENSIONS};
+use crate::utils::{
+    file_has_right_extension, partition_results, print_summary, report_failures, write_table,
+};
 use crate::AudioSummaryArgs;
 
-// Admissible extensions for this command
-const EXTENSIONS: [&str; 6] = ["mp3", "wav", "ogg", "flac", "aac", "m4a"];
+// Duration in seconds of the windows used to estimate the SNR
+const SNR_WINDOW_SECONDS: f64 = 0.05;
+
+// Percentiles of the window energies taken as signal and noise levels
+const SNR_SIGNAL_PERCENTILE: f64 = 0.9;
+const SNR_NOISE_PERCENTILE: f64 = 0.1;
+
+// Thresholds above (or below) which a file is flagged
+const MAX_CLIPPING_RATIO: f64 = 0.001;
+const MAX_DC_OFFSET: f64 = 0.01;
+const MAX_SILENCE_RATIO: f64 = 0.5;
+const MIN_SNR_DB: f64 = 15.0;
+
+// Robust z-score of the RMS level above which a file is a loudness outlier
+const MAX_RMS_Z_SCORE: f64 = 3.5;
 
 pub fn execute(args: AudioSummaryArgs) -> Result<()> {
     // Parse the arguments
@@ -25,7 +45,7 @@ pub fn execute(args: AudioSummaryArgs) -> Result<()> {
     // Find all admissible files
     let files: Vec<PathBuf> = match target.is_file() {
         true => {
-            if file_has_right_extension(target, &EXTENSIONS).is_ok() {
+            if file_has_right_extension(target, &READABLE_EXTENSIONS).is_ok() {
                 vec![target.to_path_buf()]
             } else {
                 vec![]
@@ -34,7 +54,7 @@ pub fn execute(args: AudioSummaryArgs) -> Result<()> {
         false => WalkDir::new(target)
             .into_iter()
             .filter_map(|e| e.ok())
-            .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
             .map(|e| e.path().to_path_buf())
             .collect(),
     };
@@ -45,54 +65,490 @@ pub fn execute(args: AudioSummaryArgs) -> Result<()> {
     }
 
     // Process files
-    let info: Vec<(u128, u32, u8, u8)> = files
+    let results: Vec<(PathBuf, Result<AudioInfo>)> = files
         .into_par_iter()
-        .filter_map(|file| process_audio(&file).ok())
+        .map(|file| {
+            let result = process_audio(&file, args.analyze);
+            (file, result)
+        })
         .collect();
 
-    // Calculate total number of files
-    let n_files = info.len();
+    // Separate readable files from failures and report the latter
+    let (info, failures) = partition_results(results);
+    report_failures(&failures, args.errors.as_deref().map(Path::new))?;
 
-    // Compute duration in seconds
-    let total_duration_seconds: u64 = info
-        .iter()
-        .map(|(duration, _, _, _)| (duration / 1_000_000_000) as u64)
-        .sum();
-
-    // Get unique values
-    let unique_sample_rates: HashSet<_> = info.iter().map(|(_, sr, _, _)| sr).collect();
-    let unique_channels: HashSet<_> = info.iter().map(|(_, _, channels, _)| channels).collect();
-    let unique_bit_depths: HashSet<_> = info.iter().map(|(_, _, _, depth)| depth).collect();
-    let unique_durations: HashSet<_> = info.iter().map(|(duration, _, _, _)| duration).collect();
-
-    // Format duration
-    let hours = total_duration_seconds / 3600;
-    let remainder = total_duration_seconds % 3600;
-    let minutes = remainder / 60;
-    let seconds = remainder % 60;
-
-    // Print results
-    println!("Total files: {}", n_files);
-    println!("Total Duration: {:02}:{:02}:{:02}", hours, minutes, seconds);
-    println!(
-        "Average Duration: {} s",
-        total_duration_seconds / n_files as u64
-    );
-    println!("Sample Rates: {:?} Hz", unique_sample_rates);
-    println!("Channels: {:?}", unique_channels);
-    println!("Bit Depths: {:?}", unique_bit_depths);
-    println!("Unique durations: {}", unique_durations.len());
-
-    if let (Some(min), Some(max)) = (unique_durations.iter().min(), unique_durations.iter().max()) {
-        println!("Min duration: {:} s", (**min as f64 / 1_000_000_000_f64));
-        println!("Max duration: {:} s", (**max as f64 / 1_000_000_000_f64));
+    // Write the per-file manifest if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&info)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
+    }
+
+    // Build and print the summary
+    let summary = AudioSummary::from_info(&info, failures.len());
+    print_summary(&summary, args.format)?;
+
+    // In strict mode any unreadable file is an error
+    if args.strict && !failures.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} files could not be read",
+            failures.len()
+        )));
     }
 
     Ok(())
 }
 
+// Properties of a single audio file
+#[derive(Debug)]
+struct AudioInfo {
+    path: PathBuf,
+    duration: u128,
+    sample_rate: u32,
+    channels: u8,
+    // Lossy formats have no bit depth
+    bit_depth: Option<u8>,
+    stats: Option<AudioStats>,
+}
+
+// Level and quality statistics of the samples of a single audio file
+#[derive(Debug, Clone, Copy)]
+struct AudioStats {
+    peak_dbfs: f64,
+    rms_dbfs: f64,
+    dc_offset: f64,
+    clipping_ratio: f64,
+    silence_ratio: f64,
+    snr_db: Option<f64>,
+}
+
+// File with statistics out of the expected range
+#[derive(Debug, Serialize)]
+pub struct FlaggedFile {
+    pub path: String,
+    pub flags: Vec<String>,
+}
+
+// Aggregated statistics of a collection of audio files
+#[derive(Debug, Serialize)]
+pub struct AudioAnalysis {
+    pub max_peak_dbfs: Option<f64>,
+    pub mean_rms_dbfs: Option<f64>,
+    pub max_dc_offset: f64,
+    pub mean_clipping_ratio: f64,
+    pub mean_silence_ratio: f64,
+    pub median_snr_db: Option<f64>,
+    pub flagged_files: Vec<FlaggedFile>,
+}
+
+// Aggregated properties of a collection of audio files
+#[derive(Debug, Serialize)]
+pub struct AudioSummary {
+    pub total_files: usize,
+    pub failed_files: usize,
+    pub total_duration_seconds: f64,
+    pub average_duration_seconds: f64,
+    pub sample_rates: Vec<u32>,
+    pub channels: Vec<u8>,
+    pub bit_depths: Vec<Option<u8>>,
+    pub unique_durations: usize,
+    pub min_duration_seconds: Option<f64>,
+    pub max_duration_seconds: Option<f64>,
+    #[serde(flatten)]
+    pub analysis: Option<AudioAnalysis>,
+}
+
+impl AudioSummary {
+    // Aggregate the probed properties of every file
+    fn from_info(info: &[AudioInfo], failed_files: usize) -> Self {
+        // Calculate total number of files
+        let total_files = info.len();
+
+        // Compute duration in seconds
+        let total_duration_seconds: f64 = info
+            .iter()
+            .map(|file| file.duration as f64 / 1_000_000_000_f64)
+            .sum();
+
+        // Get unique values
+        let sample_rates: BTreeSet<_> = info.iter().map(|file| file.sample_rate).collect();
+        let channels: BTreeSet<_> = info.iter().map(|file| file.channels).collect();
+        let bit_depths: BTreeSet<_> = info.iter().map(|file| file.bit_depth).collect();
+        let durations: BTreeSet<_> = info.iter().map(|file| file.duration).collect();
+
+        Self {
+            total_files,
+            failed_files,
+            total_duration_seconds,
+            average_duration_seconds: match total_files {
+                0 => 0.0,
+                n => total_duration_seconds / n as f64,
+            },
+            sample_rates: sample_rates.into_iter().collect(),
+            channels: channels.into_iter().collect(),
+            bit_depths: bit_depths.into_iter().collect(),
+            unique_durations: durations.len(),
+            min_duration_seconds: durations.first().map(|min| *min as f64 / 1_000_000_000_f64),
+            max_duration_seconds: durations.last().map(|max| *max as f64 / 1_000_000_000_f64),
+            analysis: AudioAnalysis::from_info(info),
+        }
+    }
+}
+
+impl AudioAnalysis {
+    // Aggregate the statistics of every file, if they have been computed
+    fn from_info(info: &[AudioInfo]) -> Option<Self> {
+        let stats: Vec<&AudioStats> = info
+            .iter()
+            .map(|file| file.stats.as_ref())
+            .collect::<Option<_>>()?;
+
+        let finite = |values: Vec<f64>| -> Vec<f64> {
+            values
+                .into_iter()
+                .filter(|value| value.is_finite())
+                .collect()
+        };
+        let peaks = finite(stats.iter().map(|s| s.peak_dbfs).collect());
+        let rms = finite(stats.iter().map(|s| s.rms_dbfs).collect());
+        let mut snr: Vec<f64> = stats.iter().filter_map(|s| s.snr_db).collect();
+        snr.sort_by(f64::total_cmp);
+
+        let mut flagged_files: Vec<FlaggedFile> = info
+            .iter()
+            .zip(flag_files(info))
+            .filter(|(_, flags)| !flags.is_empty())
+            .map(|(file, flags)| FlaggedFile {
+                path: file.path.to_string_lossy().to_string(),
+                flags: flags.into_iter().map(String::from).collect(),
+            })
+            .collect();
+        flagged_files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Some(Self {
+            max_peak_dbfs: peaks.iter().copied().reduce(f64::max),
+            mean_rms_dbfs: mean(&rms),
+            max_dc_offset: stats.iter().map(|s| s.dc_offset.abs()).fold(0.0, f64::max),
+            mean_clipping_ratio: mean(&stats.iter().map(|s| s.clipping_ratio).collect::<Vec<_>>())
+                .unwrap_or(0.0),
+            mean_silence_ratio: mean(&stats.iter().map(|s| s.silence_ratio).collect::<Vec<_>>())
+                .unwrap_or(0.0),
+            median_snr_db: median(&snr),
+            flagged_files,
+        })
+    }
+}
+
+impl fmt::Display for AudioSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Format duration
+        let total_seconds = self.total_duration_seconds as u64;
+        let hours = total_seconds / 3600;
+        let remainder = total_seconds % 3600;
+        let minutes = remainder / 60;
+        let seconds = remainder % 60;
+
+        writeln!(f, "Total files: {}", self.total_files)?;
+        writeln!(f, "Failed files: {}", self.failed_files)?;
+        writeln!(
+            f,
+            "Total Duration: {:02}:{:02}:{:02}",
+            hours, minutes, seconds
+        )?;
+        writeln!(
+            f,
+            "Average Duration: {} s",
+            self.average_duration_seconds as u64
+        )?;
+        writeln!(f, "Sample Rates: {:?} Hz", self.sample_rates)?;
+        writeln!(f, "Channels: {:?}", self.channels)?;
+        let bit_depths: Vec<String> = self
+            .bit_depths
+            .iter()
+            .map(|bit_depth| match bit_depth {
+                Some(bit_depth) => bit_depth.to_string(),
+                None => "n/a".to_string(),
+            })
+            .collect();
+        writeln!(f, "Bit Depths: [{}]", bit_depths.join(", "))?;
+        writeln!(f, "Unique durations: {}", self.unique_durations)?;
+
+        if let (Some(min), Some(max)) = (self.min_duration_seconds, self.max_duration_seconds) {
+            writeln!(f, "Min duration: {:} s", min)?;
+            writeln!(f, "Max duration: {:} s", max)?;
+        }
+
+        if let Some(analysis) = &self.analysis {
+            let level = |value: Option<f64>, unit: &str| match value {
+                Some(value) => format!("{:.2} {}", value, unit),
+                None => "-".to_string(),
+            };
+
+            writeln!(f, "Max peak: {}", level(analysis.max_peak_dbfs, "dBFS"))?;
+            writeln!(f, "Mean RMS: {}", level(analysis.mean_rms_dbfs, "dBFS"))?;
+            writeln!(f, "Max DC offset: {:.4}", analysis.max_dc_offset)?;
+            writeln!(
+                f,
+                "Mean clipping ratio: {:.4}",
+                analysis.mean_clipping_ratio
+            )?;
+            writeln!(f, "Mean silence ratio: {:.4}", analysis.mean_silence_ratio)?;
+            writeln!(f, "Median SNR: {}", level(analysis.median_snr_db, "dB"))?;
+            writeln!(f, "Flagged files: {}", analysis.flagged_files.len())?;
+            for file in &analysis.flagged_files {
+                writeln!(f, "  {}: {}", file.path, file.flags.join(", "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Collect the properties of every file into a table, one row per file
+fn build_manifest(info: &[AudioInfo]) -> Result<DataFrame> {
+    let mut df = df!(
+        "path" => info.iter().map(|file| file.path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "duration_seconds" => info.iter().map(|file| file.duration as f64 / 1_000_000_000_f64).collect::<Vec<_>>(),
+        "sample_rate" => info.iter().map(|file| file.sample_rate).collect::<Vec<_>>(),
+        "channels" => info.iter().map(|file| file.channels as u32).collect::<Vec<_>>(),
+        "bit_depth" => info.iter().map(|file| file.bit_depth.map(u32::from)).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")?;
+
+    // Add the statistics if they have been computed
+    if let Some(stats) = info
+        .iter()
+        .map(|file| file.stats)
+        .collect::<Option<Vec<_>>>()
+    {
+        // Levels of silent files are not finite
+        let finite = |value: f64| value.is_finite().then_some(value);
+        let flags: Vec<String> = flag_files(info)
+            .into_iter()
+            .map(|flags| flags.join(";"))
+            .collect();
+
+        let columns = [
+            Series::new(
+                "peak_dbfs".into(),
+                stats
+                    .iter()
+                    .map(|s| finite(s.peak_dbfs))
+                    .collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "rms_dbfs".into(),
+                stats.iter().map(|s| finite(s.rms_dbfs)).collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "dc_offset".into(),
+                stats.iter().map(|s| s.dc_offset).collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "clipping_ratio".into(),
+                stats.iter().map(|s| s.clipping_ratio).collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "silence_ratio".into(),
+                stats.iter().map(|s| s.silence_ratio).collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "snr_db".into(),
+                stats.iter().map(|s| s.snr_db).collect::<Vec<_>>(),
+            ),
+            Series::new("flags".into(), flags),
+        ];
+        for column in columns {
+            df.with_column(column)
+                .with_context(|| "Failed to build manifest")?;
+        }
+    }
+
+    Ok(df)
+}
+
+// Find the issues of every file, from fixed thresholds and from the spread of the RMS levels
+fn flag_files(info: &[AudioInfo]) -> Vec<Vec<&'static str>> {
+    // Median and median absolute deviation of the RMS levels
+    let mut rms: Vec<f64> = info
+        .iter()
+        .filter_map(|file| file.stats.map(|s| s.rms_dbfs))
+        .filter(|rms| rms.is_finite())
+        .collect();
+    rms.sort_by(f64::total_cmp);
+    let rms_median = median(&rms);
+    let mut deviations: Vec<f64> = rms
+        .iter()
+        .map(|rms| (rms - rms_median.unwrap_or(0.0)).abs())
+        .collect();
+    deviations.sort_by(f64::total_cmp);
+    let rms_mad = median(&deviations);
+
+    info.iter()
+        .map(|file| {
+            let mut flags = Vec::new();
+            let Some(stats) = file.stats else {
+                return flags;
+            };
+
+            if stats.clipping_ratio > MAX_CLIPPING_RATIO {
+                flags.push("clipping");
+            }
+            if stats.dc_offset.abs() > MAX_DC_OFFSET {
+                flags.push("dc_offset");
+            }
+            if stats.silence_ratio > MAX_SILENCE_RATIO {
+                flags.push("mostly_silent");
+            }
+            if stats.snr_db.is_some_and(|snr| snr < MIN_SNR_DB) {
+                flags.push("low_snr");
+            }
+
+            // Robust z-score, scaled so that the MAD matches the standard deviation of normal data
+            if let (Some(median), Some(mad)) = (rms_median, rms_mad) {
+                if rms.len() >= 3
+                    && mad > 0.0
+                    && stats.rms_dbfs.is_finite()
+                    && (stats.rms_dbfs - median).abs() / (1.4826 * mad) > MAX_RMS_Z_SCORE
+                {
+                    flags.push("loudness_outlier");
+                }
+            }
+
+            flags
+        })
+        .collect()
+}
+
+// Mean of values, if any
+fn mean(values: &[f64]) -> Option<f64> {
+    match values.len() {
+        0 => None,
+        n => Some(values.iter().sum::<f64>() / n as f64),
+    }
+}
+
+// Median of sorted values, if any
+fn median(sorted: &[f64]) -> Option<f64> {
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2.0),
+        n => Some(sorted[n / 2]),
+    }
+}
+
+// Value at a given fraction of sorted values
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * fraction).round() as usize]
+}
+
+// Decode all the samples of a file and compute their statistics
+fn analyze_audio(file: &Path) -> Result<AudioStats> {
+    let mut reader = AudioReader::open(file)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    // Integer samples are clipped at the largest representable value
+    let clipping_level = match spec.sample_format {
+        SampleFormat::Float => 1.0,
+        SampleFormat::Int => {
+            let max_value = 2_f64.powi(spec.bits_per_sample as i32 - 1);
+            (max_value - 1.0) / max_value
+        }
+    };
+    let window_frames = usize::max((spec.sample_rate as f64 * SNR_WINDOW_SECONDS) as usize, 1);
+
+    let mut samples = 0_u64;
+    let mut frames = 0_u64;
+    let mut peak = 0_f64;
+    let mut sum = 0_f64;
+    let mut sum_squares = 0_f64;
+    let mut clipped = 0_u64;
+    // Sign of the last sample of every channel if at full scale, and whether it was counted
+    let mut clip_runs: Vec<(i8, bool)> = vec![(0, false); channels];
+    let mut silent_frames = 0_u64;
+    let mut window_energies: Vec<f64> = Vec::new();
+
+    loop {
+        // Read whole windows at a time
+        let block = reader
+            .read_samples(BLOCK_FRAMES / window_frames * window_frames * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", file))?;
+        if block.is_empty() {
+            break;
+        }
+
+        for frame in block.chunks_exact(channels) {
+            if frame.iter().all(|&sample| sample == 0.0) {
+                silent_frames += 1;
+            }
+        }
+
+        for window in block.chunks(window_frames * channels) {
+            let energy = window.iter().map(|sample| sample * sample).sum::<f64>();
+            window_energies.push(energy / window.len() as f64);
+        }
+
+        for frame in block.chunks_exact(channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                peak = f64::max(peak, sample.abs());
+                sum += sample;
+                sum_squares += sample * sample;
+
+                // Only consecutive samples stuck at full scale count as clipped
+                let sign = match sample {
+                    s if s >= clipping_level => 1,
+                    s if s <= -clipping_level => -1,
+                    _ => 0,
+                };
+                if sign != 0 && sign == clip_runs[channel].0 {
+                    clipped += match clip_runs[channel].1 {
+                        true => 1,
+                        false => 2,
+                    };
+                    clip_runs[channel].1 = true;
+                } else {
+                    clip_runs[channel].1 = false;
+                }
+                clip_runs[channel].0 = sign;
+            }
+        }
+
+        samples += block.len() as u64;
+        frames += (block.len() / channels) as u64;
+    }
+
+    if samples == 0 {
+        return Err(anyhow::Error::msg("No samples to analyze"));
+    }
+
+    // Estimate the SNR from loud and quiet windows, leaving digital silence aside
+    let mut energies: Vec<f64> = window_energies
+        .into_iter()
+        .filter(|&energy| energy > 0.0)
+        .collect();
+    energies.sort_by(f64::total_cmp);
+    let snr_db = match energies.len() {
+        0 | 1 => None,
+        _ => Some(
+            10.0 * (percentile(&energies, SNR_SIGNAL_PERCENTILE)
+                / percentile(&energies, SNR_NOISE_PERCENTILE))
+            .log10(),
+        ),
+    };
+
+    Ok(AudioStats {
+        peak_dbfs: 20.0 * peak.log10(),
+        rms_dbfs: 10.0 * (sum_squares / samples as f64).log10(),
+        dc_offset: sum / samples as f64,
+        clipping_ratio: clipped as f64 / samples as f64,
+        silence_ratio: silent_frames as f64 / frames as f64,
+        snr_db,
+    })
+}
+
 // Function for getting relevant info of an audio file by just probing it
-fn process_audio(file: &Path) -> Result<(u128, u32, u8, u8)> {
+fn process_audio(file: &Path, analyze: bool) -> Result<AudioInfo> {
     // Probe the audio file
     let audio_file = Probe::open(file)
         .with_context(|| format!("Failed to open audio file: {:?}", file))?
@@ -115,10 +571,24 @@ fn process_audio(file: &Path) -> Result<(u128, u32, u8, u8)> {
         .sample_rate()
         .with_context(|| "Failed to read sample rate")?;
 
-    // Get the bit depth
-    let bit_depth = properties
-        .bit_depth()
-        .with_context(|| "Failed to read bit depth")?;
+    // Get the bit depth, which lossy formats don't have
+    let bit_depth = properties.bit_depth();
+
+    // Decode the samples if requested
+    let stats = match analyze {
+        true => Some(
+            analyze_audio(file)
+                .with_context(|| format!("Failed to analyze audio samples of: {:?}", file))?,
+        ),
+        false => None,
+    };
 
-    Ok((duration, sample_rate, channels, bit_depth))
+    Ok(AudioInfo {
+        path: file.to_path_buf(),
+        duration,
+        sample_rate,
+        channels,
+        bit_depth,
+        stats,
+    })
 }
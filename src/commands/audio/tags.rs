@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use lofty::{ItemKey, ItemValue, Probe, Tag, TagExt, TagType, TaggedFileExt};
+use polars::prelude::*;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::utils::{file_has_right_extension, partition_results, report_failures, write_table};
+use crate::{AudioTagsArgs, AudioTagsCommand, AudioTagsSetArgs, AudioTagsStripArgs};
+
+// Admissible extensions for this command
+const EXTENSIONS: [&str; 7] = ["mp3", "wav", "ogg", "flac", "aac", "m4a", "opus"];
+
+// Names of the most common keys, valid whatever the tag format
+const KEY_NAMES: [(&str, ItemKey); 11] = [
+    ("title", ItemKey::TrackTitle),
+    ("artist", ItemKey::TrackArtist),
+    ("album", ItemKey::AlbumTitle),
+    ("album_artist", ItemKey::AlbumArtist),
+    ("genre", ItemKey::Genre),
+    ("date", ItemKey::RecordingDate),
+    ("track", ItemKey::TrackNumber),
+    ("comment", ItemKey::Comment),
+    ("composer", ItemKey::Composer),
+    ("copyright", ItemKey::CopyrightMessage),
+    ("encoder", ItemKey::EncoderSoftware),
+];
+
+// A single tag item (or picture) of a file
+#[derive(Debug)]
+struct TagEntry {
+    tag_type: TagType,
+    key: String,
+    value: String,
+}
+
+// All the tag items of a file
+#[derive(Debug)]
+struct FileTags {
+    path: PathBuf,
+    entries: Vec<TagEntry>,
+}
+
+pub fn execute(args: AudioTagsArgs) -> Result<()> {
+    match args.command {
+        Some(AudioTagsCommand::Set(args)) => execute_set(args),
+        Some(AudioTagsCommand::Strip(args)) => execute_strip(args),
+        None => execute_list(args),
+    }
+}
+
+// Find all admissible files of a target
+fn find_files(target: &Path) -> Result<Vec<PathBuf>> {
+    // Error if it does not exist at all
+    if !target.exists() {
+        return Err(anyhow::Error::msg(
+            "Target file or directory does not exist",
+        ));
+    }
+
+    let files: Vec<PathBuf> = match target.is_file() {
+        true => {
+            if file_has_right_extension(target, &EXTENSIONS).is_ok() {
+                vec![target.to_path_buf()]
+            } else {
+                vec![]
+            }
+        }
+        false => WalkDir::new(target)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect(),
+    };
+
+    // Raise error if no files are admissible
+    if files.is_empty() {
+        return Err(anyhow::Error::msg("No admissible audio files detected"));
+    }
+
+    Ok(files)
+}
+
+// Name of a key as shown to the user, in the native format of the tag if not a common one
+fn key_name(key: &ItemKey, tag_type: TagType) -> String {
+    match KEY_NAMES.iter().find(|(_, known)| known == key) {
+        Some((name, _)) => name.to_string(),
+        None => match key {
+            ItemKey::Unknown(name) => name.clone(),
+            key => key
+                .map_key(tag_type, true)
+                .map(String::from)
+                .unwrap_or_else(|| format!("{:?}", key)),
+        },
+    }
+}
+
+// Key from a name given by the user, either a common one or native to the tag format
+fn parse_key(name: &str, tag_type: TagType) -> ItemKey {
+    match KEY_NAMES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+    {
+        Some((_, key)) => key.clone(),
+        None => ItemKey::from_key(tag_type, name),
+    }
+}
+
+// List the tags of every file
+fn execute_list(args: AudioTagsArgs) -> Result<()> {
+    let target = args
+        .target
+        .as_deref()
+        .ok_or_else(|| anyhow::Error::msg("Missing target"))?;
+    let files = find_files(Path::new(target))?;
+
+    // Process files
+    let results: Vec<(PathBuf, Result<FileTags>)> = files
+        .into_par_iter()
+        .map(|file| {
+            let result = read_tags(&file);
+            (file, result)
+        })
+        .collect();
+
+    // Separate readable files from failures and report the latter
+    let (mut tags, failures) = partition_results(results);
+    report_failures(&failures, args.errors.as_deref().map(Path::new))?;
+    tags.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Write the tags if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&tags)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
+    }
+
+    // Print the tags file by file
+    for file in &tags {
+        println!("{}", file.path.display());
+        if file.entries.is_empty() {
+            println!("  (no tags)");
+        }
+        for entry in &file.entries {
+            println!("  [{:?}] {}: {}", entry.tag_type, entry.key, entry.value);
+        }
+    }
+
+    // In strict mode any unreadable file is an error
+    if args.strict && !failures.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} files could not be read",
+            failures.len()
+        )));
+    }
+
+    Ok(())
+}
+
+// Read all the tag items and pictures of a file
+fn read_tags(path: &Path) -> Result<FileTags> {
+    let tagged_file = Probe::open(path)
+        .with_context(|| format!("Failed to open file: {:?}", path))?
+        .read()
+        .with_context(|| format!("Failed to read tags: {:?}", path))?;
+
+    let mut entries = Vec::new();
+    for tag in tagged_file.tags() {
+        let tag_type = tag.tag_type();
+
+        for item in tag.items() {
+            let value = match item.value() {
+                ItemValue::Text(text) | ItemValue::Locator(text) => text.clone(),
+                ItemValue::Binary(data) => format!("<{} bytes>", data.len()),
+            };
+            entries.push(TagEntry {
+                tag_type,
+                key: key_name(item.key(), tag_type),
+                value,
+            });
+        }
+
+        for picture in tag.pictures() {
+            entries.push(TagEntry {
+                tag_type,
+                key: "picture".to_string(),
+                value: format!(
+                    "{:?} {} ({} bytes)",
+                    picture.pic_type(),
+                    picture.mime_type().as_str(),
+                    picture.data().len()
+                ),
+            });
+        }
+    }
+
+    Ok(FileTags {
+        path: path.to_path_buf(),
+        entries,
+    })
+}
+
+// Build a table with one row per tag item
+fn build_manifest(tags: &[FileTags]) -> Result<DataFrame> {
+    let rows: Vec<(&Path, &TagEntry)> = tags
+        .iter()
+        .flat_map(|file| {
+            file.entries
+                .iter()
+                .map(move |entry| (file.path.as_path(), entry))
+        })
+        .collect();
+
+    df!(
+        "path" => rows.iter().map(|(path, _)| path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "tag_type" => rows.iter().map(|(_, entry)| format!("{:?}", entry.tag_type)).collect::<Vec<_>>(),
+        "key" => rows.iter().map(|(_, entry)| entry.key.clone()).collect::<Vec<_>>(),
+        "value" => rows.iter().map(|(_, entry)| entry.value.clone()).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")
+}
+
+// Set tags of every file, creating the main tag of the format if missing
+fn execute_set(args: AudioTagsSetArgs) -> Result<()> {
+    let files = find_files(Path::new(&args.target))?;
+
+    files.par_iter().try_for_each(|file| -> Result<()> {
+        let tagged_file = Probe::open(file)
+            .with_context(|| format!("Failed to open file: {:?}", file))?
+            .read()
+            .with_context(|| format!("Failed to read tags: {:?}", file))?;
+
+        let tag_type = tagged_file.primary_tag_type();
+        let mut tag = match tagged_file.primary_tag() {
+            Some(tag) => tag.clone(),
+            None => Tag::new(tag_type),
+        };
+
+        for assignment in &args.tags {
+            let key = parse_key(&assignment.key, tag_type);
+            if assignment.value.is_empty() {
+                tag.remove_key(&key);
+            } else if !tag.insert_text(key, assignment.value.clone()) {
+                return Err(anyhow::Error::msg(format!(
+                    "Key {} is not supported by {:?} tags of {:?}",
+                    assignment.key, tag_type, file
+                )));
+            }
+        }
+
+        tag.save_to_path(file)
+            .with_context(|| format!("Failed to write tags: {:?}", file))?;
+
+        Ok(())
+    })?;
+
+    println!("Updated tags of {} files", files.len());
+
+    Ok(())
+}
+
+// Remove all the tags of every file, or only some keys or the pictures
+fn execute_strip(args: AudioTagsStripArgs) -> Result<()> {
+    let files = find_files(Path::new(&args.target))?;
+    let strip_all = args.keys.is_empty() && !args.pictures;
+
+    files.par_iter().try_for_each(|file| -> Result<()> {
+        let tagged_file = Probe::open(file)
+            .with_context(|| format!("Failed to open file: {:?}", file))?
+            .read()
+            .with_context(|| format!("Failed to read tags: {:?}", file))?;
+
+        for tag in tagged_file.tags() {
+            let tag_type = tag.tag_type();
+
+            if strip_all {
+                tag_type
+                    .remove_from_path(file)
+                    .with_context(|| format!("Failed to remove tags: {:?}", file))?;
+                continue;
+            }
+
+            let mut tag = tag.clone();
+            for name in &args.keys {
+                tag.remove_key(&parse_key(name, tag_type));
+            }
+            if args.pictures {
+                while tag.picture_count() > 0 {
+                    tag.remove_picture(0);
+                }
+            }
+            tag.save_to_path(file)
+                .with_context(|| format!("Failed to write tags: {:?}", file))?;
+        }
+
+        Ok(())
+    })?;
+
+    println!("Stripped tags of {} files", files.len());
+
+    Ok(())
+}
@@ -1,42 +1,72 @@
 use anyhow::{Context, Result};
-use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
 use rayon::prelude::*;
 use std::path::Path;
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
-use crate::utils::{file_has_right_extension, perform_io_sanity_check};
-use crate::AudioTrimArgs;
-
-// Admissible extensions for this command
-const EXTENSIONS: [&str; 1] = ["wav"];
+use crate::utils::audio::{
+    temp_output, with_writable_extension, AudioReader, AudioWriter, BLOCK_FRAMES,
+    READABLE_EXTENSIONS,
+};
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{AudioTrimArgs, Timestamp};
+
+// Time range kept in every file
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Range {
+    pub(crate) start: Timestamp,
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) length: Option<Timestamp>,
+    pub(crate) clamp: bool,
+}
 
 pub fn execute(args: AudioTrimArgs) -> Result<()> {
     // Parse the arguments
     let input = Path::new(&args.input);
     let output = Path::new(&args.output);
 
-    let offset: f32 = args.offset;
-    let length: f32 = args.length;
+    // The former length and offset are seconds from the start
+    let seconds = |value: Option<f32>| -> Result<Option<Timestamp>> {
+        value
+            .map(|value| Timestamp::from_seconds(value as f64).map_err(anyhow::Error::msg))
+            .transpose()
+    };
+
+    let range = Range {
+        start: args
+            .start
+            .or(seconds(args.legacy_offset)?)
+            .unwrap_or_default(),
+        end: args.end,
+        length: args.duration.or(seconds(args.legacy_length)?),
+        clamp: args.clamp,
+    };
 
     let overwrite: bool = args.overwrite;
 
+    if range.end.is_some() && range.length.is_some() {
+        return Err(anyhow::Error::msg("End and length can't both be given"));
+    }
+    if range.length.is_some_and(|length| length.from_end) {
+        return Err(anyhow::Error::msg("Length can't be negative"));
+    }
+
     // Sanity checks on I/O
     perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
 
     // Process files
-    process(input, offset, length, output, overwrite).with_context(|| "Processing failed")?;
+    process(input, range, output, overwrite).with_context(|| "Processing failed")?;
 
     Ok(())
 }
 
 // Process all the content (single file or directory of files)
-fn process(input: &Path, offset: f32, length: f32, output: &Path, overwrite: bool) -> Result<()> {
+fn process(input: &Path, range: Range, output: &Path, overwrite: bool) -> Result<()> {
     // Case of single input file
     if input.is_file() {
         // Check if the file has the right extension and process it
-        file_has_right_extension(input, &EXTENSIONS)?;
-        process_file(input, offset, length, output, overwrite)
+        file_has_right_extension(input, &READABLE_EXTENSIONS)?;
+        process_file(input, range, output, overwrite)
             .with_context(|| format!("Failed to process file: {:?}", input))?;
     }
     // Case of input being a directory
@@ -45,102 +75,155 @@ fn process(input: &Path, offset: f32, length: f32, output: &Path, overwrite: boo
         let files: Vec<PathBuf> = WalkDir::new(input)
             .into_iter()
             .filter_map(|e| e.ok())
-            .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
             .map(|e| e.path().to_path_buf())
             .collect();
 
-        // Parallel loop over entries
-        files.par_iter().try_for_each(|file| -> Result<()> {
-            // Relative path wrt input directory
-            let relative_path = file
-                .strip_prefix(input)
-                .with_context(|| format!("Failed to strip prefix from path: {:?}", file))?;
-
-            // Nested output path
-            let file_output = output.join(relative_path);
-
-            // Ensure the output directory exists
-            if let Some(parent) = file_output.parent() {
-                std::fs::create_dir_all(parent)
-                    .with_context(|| format!("Failed to create output directory: {:?}", parent))?;
-            }
+        // Nested output paths, in a format that can be written
+        let outputs = nested_outputs(&files, input, output, with_writable_extension)?;
 
-            // Process the file
-            process_file(file, offset, length, &file_output, overwrite)
-                .with_context(|| format!("Failed to process file: {:?}", file))?;
-
-            Ok(())
-        })?;
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, range, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
     }
     Ok(())
 }
 
-// Process a single file
-fn process_file(
+// First and last frames of the range in a file, the latter being None for the end of the file
+pub(crate) fn resolve(
     input: &Path,
-    offset: f32,
-    length: f32,
-    output: &Path,
-    overwrite: bool,
-) -> Result<()> {
+    range: Range,
+    sample_rate: u32,
+) -> Result<(usize, Option<usize>)> {
+    // Positions relative to the end need the exact length, which only decoding gives for compressed files
+    let relative_to_end = range.start.from_end || range.end.is_some_and(|end| end.from_end);
+    let total = match relative_to_end {
+        true => Some(
+            AudioReader::open(input)?
+                .skip_frames(usize::MAX)
+                .with_context(|| format!("Couldn't read samples from {:?}", input))?,
+        ),
+        false => None,
+    };
+
+    // Frame of a timestamp, counted from the start of the file
+    let position = |timestamp: Timestamp| -> Result<usize> {
+        let frames = timestamp.frames(sample_rate);
+        match (timestamp.from_end, total) {
+            (true, Some(total)) => match total.checked_sub(frames) {
+                Some(position) => Ok(position),
+                None if range.clamp => Ok(0),
+                None => Err(anyhow::Error::msg(
+                    "Requested time before the start of the file",
+                )),
+            },
+            _ => Ok(frames),
+        }
+    };
+
+    let start = position(range.start)?;
+    let end = match (range.end, range.length) {
+        (Some(end), _) => Some(position(end)?),
+        (None, Some(length)) => Some(start.saturating_add(length.frames(sample_rate))),
+        (None, None) => None,
+    };
+
+    match end {
+        Some(end) if end < start && !range.clamp => {
+            Err(anyhow::Error::msg("Requested end before start"))
+        }
+        Some(end) => Ok((start, Some(usize::max(start, end)))),
+        None => Ok((start, None)),
+    }
+}
+
+// Process a single file
+fn process_file(input: &Path, range: Range, output: &Path, overwrite: bool) -> Result<()> {
     // Check that we can overwrite
     if input == output && !overwrite {
         return Err(anyhow::Error::msg("Can't overwrite files"));
     }
 
-    // Open the WAV file
-    let mut reader = WavReader::open(input).with_context(|| "Failed to open WavReader")?;
+    // Compute the requested range in frames
+    let sample_rate = AudioReader::open(input)?.spec().sample_rate;
+    let (start, end) = resolve(input, range, sample_rate)?;
+
+    extract(input, start, end, range.clamp, output)
+}
+
+// Write the frames from `start` to `end` of a file (to its end if None) into the output
+pub(crate) fn extract(
+    input: &Path,
+    start: usize,
+    end: Option<usize>,
+    clamp: bool,
+    output: &Path,
+) -> Result<()> {
+    // Open the audio file
+    let mut reader = AudioReader::open(input)?;
 
     // Extract info from file
     let spec = reader.spec();
-    let sample_rate = spec.sample_rate as f32;
     let channels = spec.channels as usize;
+    let length_frames = end.map_or(usize::MAX, |end| end - start);
 
-    // Compute the requested length in samples
-    let offset_samples = (sample_rate * offset) as usize * channels;
-    let length_samples = (sample_rate * length) as usize * channels;
-
-    // Read the samples and find the total number of samples
-    let samples: Vec<i32> = reader
-        .samples::<i32>()
-        .collect::<Result<Vec<i32>, _>>()
+    // Move to the start, raising error if it is past the end of the file
+    let skipped = reader
+        .skip_frames(start)
         .with_context(|| format!("Couldn't read samples from {:?}", input))?;
-    let total_samples = samples.len();
-
-    // Raise error if offset longer than file length
-    if offset_samples > total_samples {
-        Err(anyhow::Error::msg(
-            "Requested offset larger than file length",
-        ))
+    if skipped < start && !clamp {
+        return Err(anyhow::Error::msg(
+            "Requested start past the end of the file",
+        ));
     }
-    // Raise error if combined offset and length is longer than file length
-    else if offset_samples + length_samples > total_samples {
-        Err(anyhow::Error::msg(
-            "Requested length larger than file length",
-        ))
-    } else {
-        // Trim the audio
-        let trimmed_samples: Vec<i32> = samples[offset_samples..length_samples].to_vec();
-
-        // Define file specs
-        let spec = WavSpec {
-            channels: spec.channels,
-            sample_rate: spec.sample_rate,
-            bits_per_sample: spec.bits_per_sample,
-            sample_format: SampleFormat::Int,
-        };
-
-        // Init writer
-        let mut writer = WavWriter::create(output, spec)
-            .with_context(|| format!("Couldn't write to {:?}", output))?;
-
-        // Write to file
-        trimmed_samples.iter().try_for_each(|&sample| {
-            writer
-                .write_sample(sample)
-                .with_context(|| "Failed to write audio sample")
-        })?;
-
-        Ok(())
+
+    // Write to a temporary file first, so that the output is never left half-written
+    // and the input can be safely overwritten
+    let temp = temp_output(output)?;
+
+    // Init writer with the same specs as the input
+    let mut writer = AudioWriter::create(temp.path(), spec)?;
+
+    // Copy the trimmed audio one block at a time
+    let mut written = 0;
+    while written < length_frames {
+        let block = reader
+            .read_samples(usize::min(length_frames - written, BLOCK_FRAMES) * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", input))?;
+
+        // Raise error if the range ends past the end of the file, unless trimming to the end
+        if block.is_empty() {
+            if end.is_some() && !clamp {
+                return Err(anyhow::Error::msg("Requested end past the end of the file"));
+            }
+            break;
+        }
+
+        writer
+            .write_samples(&block)
+            .with_context(|| "Failed to write audio samples")?;
+        written += block.len() / channels;
     }
+    writer.finalize()?;
+
+    // Move the trimmed file to its final location
+    temp.persist(output)
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    Ok(())
 }
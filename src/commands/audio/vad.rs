@@ -0,0 +1,302 @@
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::utils::audio::{AudioReader, READABLE_EXTENSIONS};
+use crate::utils::{
+    file_has_right_extension, partition_results, print_summary, report_failures, write_table,
+};
+use crate::AudioVadArgs;
+
+// Duration in seconds of the windows classified as speech or not
+const VAD_WINDOW_SECONDS: f64 = 0.02;
+
+// Unvoiced sounds (e.g. fricatives) are quieter than the threshold but cross zero often
+const UNVOICED_MARGIN_DB: f64 = 10.0;
+const UNVOICED_MIN_ZCR: f64 = 0.3;
+
+// How windows are classified and merged into segments
+#[derive(Debug, Clone, Copy)]
+struct Detector {
+    threshold: f64,
+    min_speech: f64,
+    min_silence: f64,
+}
+
+// Speech segments of a single audio file
+#[derive(Debug)]
+struct VadInfo {
+    path: PathBuf,
+    duration: f64,
+    segments: Vec<(f64, f64)>,
+}
+
+impl VadInfo {
+    fn speech_seconds(&self) -> f64 {
+        self.segments.iter().map(|(start, end)| end - start).sum()
+    }
+
+    fn speech_ratio(&self) -> f64 {
+        match self.duration > 0.0 {
+            true => self.speech_seconds() / self.duration,
+            false => 0.0,
+        }
+    }
+}
+
+pub fn execute(args: AudioVadArgs) -> Result<()> {
+    // Parse the arguments
+    let target = Path::new(&args.target);
+
+    let detector = Detector {
+        threshold: args.threshold,
+        min_speech: args.min_speech,
+        min_silence: args.min_silence,
+    };
+
+    // Error if it does not exist at all
+    if !target.exists() {
+        return Err(anyhow::Error::msg(
+            "Target file or directory does not exist",
+        ));
+    }
+
+    // Find all admissible files
+    let files: Vec<PathBuf> = match target.is_file() {
+        true => {
+            if file_has_right_extension(target, &READABLE_EXTENSIONS).is_ok() {
+                vec![target.to_path_buf()]
+            } else {
+                vec![]
+            }
+        }
+        false => WalkDir::new(target)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &READABLE_EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect(),
+    };
+
+    // Raise error if no files are admissible
+    if files.is_empty() {
+        return Err(anyhow::Error::msg("No admissible audio files detected"));
+    }
+
+    // Process files
+    let results: Vec<(PathBuf, Result<VadInfo>)> = files
+        .into_par_iter()
+        .map(|file| {
+            let result = process_audio(&file, detector);
+            (file, result)
+        })
+        .collect();
+
+    // Separate readable files from failures and report the latter
+    let (mut info, failures) = partition_results(results);
+    report_failures(&failures, args.errors.as_deref().map(Path::new))?;
+    info.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Write the per-file manifest if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&info)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
+    }
+
+    // Write the segments if requested
+    if let Some(segments) = &args.segments {
+        let segments = Path::new(segments);
+        write_table(&mut build_segments(&info)?, segments)
+            .with_context(|| format!("Failed to write segments: {:?}", segments))?;
+    }
+
+    // Build and print the summary
+    let summary = VadSummary::from_info(&info, failures.len());
+    print_summary(&summary, args.format)?;
+
+    // In strict mode any unreadable file is an error
+    if args.strict && !failures.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} files could not be read",
+            failures.len()
+        )));
+    }
+
+    Ok(())
+}
+
+// Speech statistics over all the files
+#[derive(Debug, Serialize)]
+pub struct VadSummary {
+    pub total_files: usize,
+    pub failed_files: usize,
+    pub total_duration_seconds: f64,
+    pub speech_seconds: f64,
+    pub speech_ratio: f64,
+    pub speech_segments: usize,
+}
+
+impl VadSummary {
+    fn from_info(info: &[VadInfo], failed_files: usize) -> Self {
+        let total_duration_seconds: f64 = info.iter().map(|file| file.duration).sum();
+        let speech_seconds: f64 = info.iter().map(|file| file.speech_seconds()).sum();
+
+        Self {
+            total_files: info.len(),
+            failed_files,
+            total_duration_seconds,
+            speech_seconds,
+            speech_ratio: match total_duration_seconds > 0.0 {
+                true => speech_seconds / total_duration_seconds,
+                false => 0.0,
+            },
+            speech_segments: info.iter().map(|file| file.segments.len()).sum(),
+        }
+    }
+}
+
+impl fmt::Display for VadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Format durations
+        let hms = |seconds: f64| {
+            let seconds = seconds as u64;
+            format!(
+                "{:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds % 3600 / 60,
+                seconds % 60
+            )
+        };
+
+        writeln!(f, "Total files: {}", self.total_files)?;
+        writeln!(f, "Failed files: {}", self.failed_files)?;
+        writeln!(f, "Total Duration: {}", hms(self.total_duration_seconds))?;
+        writeln!(f, "Speech Duration: {}", hms(self.speech_seconds))?;
+        writeln!(f, "Speech ratio: {:.1}%", self.speech_ratio * 100.0)?;
+        writeln!(f, "Speech segments: {}", self.speech_segments)?;
+
+        Ok(())
+    }
+}
+
+// Build a table with one row per file
+fn build_manifest(info: &[VadInfo]) -> Result<DataFrame> {
+    df!(
+        "path" => info.iter().map(|file| file.path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "duration_seconds" => info.iter().map(|file| file.duration).collect::<Vec<_>>(),
+        "speech_seconds" => info.iter().map(|file| file.speech_seconds()).collect::<Vec<_>>(),
+        "speech_ratio" => info.iter().map(|file| file.speech_ratio()).collect::<Vec<_>>(),
+        "segments" => info.iter().map(|file| file.segments.len() as u32).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")
+}
+
+// Build a table with one row per speech segment
+fn build_segments(info: &[VadInfo]) -> Result<DataFrame> {
+    let rows: Vec<(&VadInfo, usize, f64, f64)> = info
+        .iter()
+        .flat_map(|file| {
+            file.segments
+                .iter()
+                .enumerate()
+                .map(move |(i, &(start, end))| (file, i, start, end))
+        })
+        .collect();
+
+    df!(
+        "path" => rows.iter().map(|row| row.0.path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "segment" => rows.iter().map(|row| row.1 as u32).collect::<Vec<_>>(),
+        "start_seconds" => rows.iter().map(|row| row.2).collect::<Vec<_>>(),
+        "end_seconds" => rows.iter().map(|row| row.3).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build segments")
+}
+
+// Level in dBFS and zero-crossing rate of a mono window
+fn window_features(window: &[f64]) -> (f64, f64) {
+    let rms =
+        (window.iter().map(|sample| sample * sample).sum::<f64>() / window.len() as f64).sqrt();
+
+    let crossings = window
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+    let zcr = match window.len() > 1 {
+        true => crossings as f64 / (window.len() - 1) as f64,
+        false => 0.0,
+    };
+
+    (20.0 * rms.log10(), zcr)
+}
+
+// Detect the speech segments of a single file, mixing all the channels into mono
+fn process_audio(path: &Path, detector: Detector) -> Result<VadInfo> {
+    // Open the audio file
+    let mut reader = AudioReader::open(path)?;
+
+    // Extract info from file
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let sample_rate = spec.sample_rate as f64;
+
+    let window_frames = usize::max((sample_rate * VAD_WINDOW_SECONDS) as usize, 1);
+
+    // Classify one window at a time, keeping the runs of speech as frame ranges
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut position = 0;
+    loop {
+        let window = reader
+            .read_samples(window_frames * channels)
+            .with_context(|| format!("Couldn't read samples from {:?}", path))?;
+        if window.is_empty() {
+            break;
+        }
+
+        let mono: Vec<f64> = window
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f64>() / channels as f64)
+            .collect();
+        let (level, zcr) = window_features(&mono);
+
+        let voiced = level >= detector.threshold;
+        let unvoiced = level >= detector.threshold - UNVOICED_MARGIN_DB && zcr >= UNVOICED_MIN_ZCR;
+
+        let end = position + mono.len();
+        if voiced || unvoiced {
+            match runs.last_mut() {
+                Some(run) if run.1 == position => run.1 = end,
+                _ => runs.push((position, end)),
+            }
+        }
+        position = end;
+    }
+
+    // Bridge pauses shorter than the minimum silence
+    let min_silence_frames = (detector.min_silence * sample_rate) as usize;
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for run in runs {
+        match merged.last_mut() {
+            Some(last) if run.0 - last.1 < min_silence_frames => last.1 = run.1,
+            _ => merged.push(run),
+        }
+    }
+
+    // Drop segments shorter than the minimum speech
+    let min_speech_frames = (detector.min_speech * sample_rate) as usize;
+    let segments = merged
+        .into_iter()
+        .filter(|(start, end)| end - start >= min_speech_frames)
+        .map(|(start, end)| (start as f64 / sample_rate, end as f64 / sample_rate))
+        .collect();
+
+    Ok(VadInfo {
+        path: path.to_path_buf(),
+        duration: position as f64 / sample_rate,
+        segments,
+    })
+}
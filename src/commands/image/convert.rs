@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use image::io::Reader as ImageReader;
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::utils::image_metadata::ImageMetadata;
+use crate::utils::{file_has_right_extension, nested_outputs, perform_io_sanity_check};
+use crate::{Color, ImageCodec, ImageConvertArgs};
+
+// Admissible extensions for this command
+const EXTENSIONS: [&str; 8] = ["jpg", "jpeg", "png", "bmp", "gif", "tiff", "tif", "webp"];
+
+// Quality of JPEG output without an explicit one
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+// Requested output format
+#[derive(Debug, Clone, Copy)]
+struct Conversion {
+    codec: ImageCodec,
+    quality: u8,
+    background: Color,
+    strip_metadata: bool,
+}
+
+pub fn execute(args: ImageConvertArgs) -> Result<()> {
+    // Parse the arguments
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+
+    // Only JPEG is lossy
+    let quality = match (args.to, args.quality) {
+        (ImageCodec::Jpeg, quality) => quality.unwrap_or(DEFAULT_JPEG_QUALITY),
+        (_, None) => DEFAULT_JPEG_QUALITY,
+        (_, Some(_)) => return Err(anyhow::Error::msg("Quality only applies to JPEG")),
+    };
+    if !(1..=100).contains(&quality) {
+        return Err(anyhow::Error::msg("Quality must be between 1 and 100"));
+    }
+
+    let conversion = Conversion {
+        codec: args.to,
+        quality,
+        background: args.background,
+        strip_metadata: args.strip_metadata,
+    };
+
+    let overwrite: bool = args.overwrite;
+
+    // Sanity checks on I/O
+    perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
+
+    // Process files
+    process(input, conversion, output, overwrite).with_context(|| "Processing failed")?;
+
+    Ok(())
+}
+
+// Process all the content (single file or directory of files)
+fn process(input: &Path, conversion: Conversion, output: &Path, overwrite: bool) -> Result<()> {
+    let extensions = conversion.codec.extensions();
+
+    // Case of single input file
+    if input.is_file() {
+        // Check if the file has the right extension and process it
+        file_has_right_extension(input, &EXTENSIONS)?;
+
+        // The output must match the format
+        file_has_right_extension(output, extensions).with_context(|| {
+            format!(
+                "Output file must have the .{} extension",
+                extensions.join(" or .")
+            )
+        })?;
+
+        process_file(input, conversion, output, overwrite)
+            .with_context(|| format!("Failed to process file: {:?}", input))?;
+    }
+    // Case of input being a directory
+    else {
+        // Find all files
+        let files: Vec<PathBuf> = WalkDir::new(input)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        // Nested output paths with the extension of the format
+        let outputs = nested_outputs(&files, input, output, |path| {
+            path.with_extension(extensions[0])
+        })?;
+
+        // Converting in place would replace the inputs
+        let inputs: HashSet<&PathBuf> = files.iter().collect();
+        if let Some(file_output) = outputs.iter().find(|path| inputs.contains(path)) {
+            if !overwrite {
+                return Err(anyhow::Error::msg(format!(
+                    "Output {:?} is an input file, pass --overwrite to replace it",
+                    file_output
+                )));
+            }
+        }
+
+        // Parallel loop over entries
+        files
+            .par_iter()
+            .zip(&outputs)
+            .try_for_each(|(file, file_output)| -> Result<()> {
+                // Ensure the output directory exists
+                if let Some(parent) = file_output.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {:?}", parent)
+                    })?;
+                }
+
+                // Process the file
+                process_file(file, conversion, file_output, overwrite)
+                    .with_context(|| format!("Failed to process file: {:?}", file))?;
+
+                Ok(())
+            })?;
+    }
+    Ok(())
+}
+
+// Convert the pixels to a color type the encoder supports, keeping as much as it can store
+fn encodable(image: DynamicImage, conversion: Conversion) -> DynamicImage {
+    let color = image.color();
+    let gray = color.channel_count() <= 2;
+    let alpha = color.has_alpha();
+    let wide = color.bytes_per_pixel() > color.channel_count();
+
+    // Which formats keep 16-bit channels, and gray with transparency
+    let (wide, gray_alpha) = match conversion.codec {
+        ImageCodec::Jpeg => {
+            let image = match alpha {
+                true => DynamicImage::ImageRgb8(flatten(&image, conversion.background)),
+                false => image,
+            };
+            return match gray && !alpha {
+                true => DynamicImage::ImageLuma8(image.to_luma8()),
+                false => DynamicImage::ImageRgb8(image.to_rgb8()),
+            };
+        }
+        ImageCodec::Png => (wide, true),
+        ImageCodec::Tiff => (wide, false),
+        ImageCodec::Webp | ImageCodec::Bmp => (false, true),
+    };
+
+    match (wide, gray, alpha) {
+        (false, true, false) => DynamicImage::ImageLuma8(image.to_luma8()),
+        (false, true, true) if gray_alpha => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+        (false, _, false) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        (false, _, true) => DynamicImage::ImageRgba8(image.to_rgba8()),
+        (true, true, false) => DynamicImage::ImageLuma16(image.to_luma16()),
+        (true, true, true) if gray_alpha => DynamicImage::ImageLumaA16(image.to_luma_alpha16()),
+        (true, _, false) => DynamicImage::ImageRgb16(image.to_rgb16()),
+        (true, _, true) => DynamicImage::ImageRgba16(image.to_rgba16()),
+    }
+}
+
+// Blend an image over an opaque background, whose own alpha is ignored
+fn flatten(image: &DynamicImage, background: Color) -> RgbImage {
+    let image = image.to_rgba8();
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = image.get_pixel(x, y).0;
+        let alpha = pixel[3] as u32;
+        Rgb(std::array::from_fn(|c| {
+            ((pixel[c] as u32 * alpha + background.0[c] as u32 * (255 - alpha) + 127) / 255) as u8
+        }))
+    })
+}
+
+// Process a single file
+fn process_file(
+    input: &Path,
+    conversion: Conversion,
+    output: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    // Check that we can overwrite
+    if input == output && !overwrite {
+        return Err(anyhow::Error::msg("Can't overwrite files"));
+    }
+
+    // Read image, whatever its extension says
+    let bytes = std::fs::read(input).with_context(|| "Can't open image")?;
+    let input_img = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .with_context(|| "Can't guess image format")?
+        .decode()
+        .with_context(|| "Can't decode image")?;
+
+    // Encode it in the requested format
+    let format = match conversion.codec {
+        ImageCodec::Png => ImageOutputFormat::Png,
+        ImageCodec::Jpeg => ImageOutputFormat::Jpeg(conversion.quality),
+        ImageCodec::Webp => ImageOutputFormat::WebP,
+        ImageCodec::Tiff => ImageOutputFormat::Tiff,
+        ImageCodec::Bmp => ImageOutputFormat::Bmp,
+    };
+    let mut encoded = Cursor::new(Vec::new());
+    encodable(input_img, conversion)
+        .write_to(&mut encoded, format)
+        .with_context(|| "Can't encode image")?;
+
+    // Carry the metadata over, which the encoders leave out
+    let metadata = match conversion.strip_metadata {
+        true => ImageMetadata::default(),
+        false => ImageMetadata::read(&bytes),
+    };
+    let encoded = metadata
+        .embed(encoded.into_inner())
+        .with_context(|| "Can't write metadata")?;
+
+    // Save image
+    std::fs::write(output, encoded)
+        .with_context(|| format!("Couldn't save image to {:?}", output))?;
+
+    Ok(())
+}
@@ -1,100 +1,422 @@
 use anyhow::{Context, Result};
+use image::imageops::thumbnail;
+use image::io::Reader as ImageReader;
+use image::{DynamicImage, GrayImage};
+use polars::prelude::*;
 use rayon::prelude::*;
-use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::f64::consts::PI;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
-use crate::utils::file_has_right_extension;
+use crate::utils::duplicates::{
+    apply_action, check_action, exact_duplicates, find_files, keep_one, DuplicateGroup,
+};
+use crate::utils::write_table;
 
-use crate::ImageDuplicatesArgs;
+use crate::{ContentHash, DuplicateAction, ImageDuplicatesArgs, ImageHashMethod};
 
 // Admissible extensions for this command
 const EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "bmp", "gif", "tiff", "tif"];
 
+// Perceptual hashes have 64 bits, and copies resized or recompressed usually differ by a few of them
+const HASH_BITS: u32 = 64;
+const DEFAULT_THRESHOLD: u32 = 10;
+
+// Side of the thumbnail whose DCT gives the perceptual hash
+const DCT_SIDE: usize = 32;
+
+// Perceptual hash of a single image file
+#[derive(Debug)]
+struct ImageHash {
+    path: PathBuf,
+    hash: u64,
+}
+
+// BK-tree of hashes, to find those within a Hamming distance without comparing all pairs
+#[derive(Debug, Default)]
+struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+#[derive(Debug)]
+struct BkNode {
+    hash: u64,
+    // Children by distance to this node
+    children: Vec<(u32, usize)>,
+}
+
+impl BkTree {
+    // Insert a hash, unless it is already in the tree
+    fn insert(&mut self, hash: u64) {
+        let new = self.nodes.len();
+        let mut node = 0;
+        while node < new {
+            let distance = hamming_distance(self.nodes[node].hash, hash);
+            if distance == 0 {
+                return;
+            }
+            match self.nodes[node]
+                .children
+                .iter()
+                .find(|(child_distance, _)| *child_distance == distance)
+            {
+                Some(&(_, child)) => node = child,
+                None => {
+                    self.nodes[node].children.push((distance, new));
+                    break;
+                }
+            }
+        }
+        self.nodes.push(BkNode {
+            hash,
+            children: Vec::new(),
+        });
+    }
+
+    // All the hashes within a distance of the given one, itself included
+    fn find(&self, hash: u64, max_distance: u32) -> Vec<u64> {
+        let mut found = Vec::new();
+        let mut stack = match self.nodes.is_empty() {
+            true => vec![],
+            false => vec![0],
+        };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let distance = hamming_distance(node.hash, hash);
+            if distance <= max_distance {
+                found.push(node.hash);
+            }
+
+            // By the triangle inequality, other subtrees are too far away
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(child_distance, _)| child_distance.abs_diff(distance) <= max_distance)
+                    .map(|&(_, child)| child),
+            );
+        }
+        found
+    }
+}
+
 pub fn execute(args: ImageDuplicatesArgs) -> Result<()> {
     // Parse the arguments
-    let target = Path::new(&args.target);
+    let targets: Vec<PathBuf> = args.targets.iter().map(PathBuf::from).collect();
+    let move_to = args.move_to.as_deref().map(Path::new);
 
-    // Error if it does not exist at all
-    if !target.exists() {
-        return Err(anyhow::Error::msg("Target directory does not exist"));
+    let threshold = match (args.method, args.threshold) {
+        (ImageHashMethod::Sha256 | ImageHashMethod::Xxh3, Some(_)) => {
+            return Err(anyhow::Error::msg(
+                "Threshold only applies to perceptual hashes",
+            ))
+        }
+        (_, threshold) => threshold.unwrap_or(DEFAULT_THRESHOLD),
+    };
+    if threshold > HASH_BITS {
+        return Err(anyhow::Error::msg(format!(
+            "Threshold can't exceed {} bits",
+            HASH_BITS
+        )));
     }
+    check_action(args.action, move_to)?;
 
-    // Error if target is not a directory
-    if !target.is_dir() {
-        return Err(anyhow::Error::msg("Target must be a directory"));
+    // Only exact copies can share their bytes
+    let exact = matches!(args.method, ImageHashMethod::Sha256 | ImageHashMethod::Xxh3);
+    if args.action == DuplicateAction::Hardlink && !exact {
+        return Err(anyhow::Error::msg(
+            "Hard links only apply to exact duplicates, found with sha256 or xxh3",
+        ));
     }
 
     // Find all admissible files
-    let image_files: Vec<PathBuf> = WalkDir::new(target)
-        .max_depth(1)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
-        .map(|e| e.path().to_path_buf())
-        .collect();
+    let image_files = find_files(&targets, args.recursive, &EXTENSIONS)?;
 
     // Raise error if impossible to find duplicates
     if image_files.len() < 2 {
-        return Err(anyhow::Error::msg("Directory contains less than 2 files"));
+        return Err(anyhow::Error::msg("Targets contain less than 2 files"));
     }
 
-    // Calculate hashes
-    let hashes: Vec<(String, PathBuf)> = image_files
-        .par_iter()
-        .filter_map(|file| process_image(file).ok().map(|hash| (hash, file.clone())))
-        .collect();
+    // Group duplicates, with the perceptual hashes of the images if any
+    let (clusters, hashes) = match args.method {
+        ImageHashMethod::Sha256 => (
+            exact_duplicates(&image_files, ContentHash::Sha256),
+            HashMap::new(),
+        ),
+        ImageHashMethod::Xxh3 => (
+            exact_duplicates(&image_files, ContentHash::Xxh3),
+            HashMap::new(),
+        ),
+        ImageHashMethod::Ahash => near_duplicates(&image_files, average_hash, threshold),
+        ImageHashMethod::Dhash => near_duplicates(&image_files, difference_hash, threshold),
+        ImageHashMethod::Phash => near_duplicates(&image_files, dct_hash, threshold),
+    };
+
+    // Choose the image kept out of every group
+    let mut groups = clusters
+        .into_par_iter()
+        .map(|files| keep_one(files, args.keep, image_pixels))
+        .collect::<Result<Vec<_>>>()?;
+    groups.sort_by(|a, b| a.kept.cmp(&b.kept));
 
-    // Group files by hash to find duplicates
-    let mut hash_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
-    for (hash, path) in hashes {
-        hash_map.entry(hash).or_default().push(path);
+    // Distance of an image to the one kept, none for exact copies
+    let distance = |group: &DuplicateGroup, file: &Path| {
+        hashes
+            .get(file)
+            .zip(hashes.get(&group.kept))
+            .map(|(a, b)| hamming_distance(*a, *b))
+    };
+
+    // Write the groups if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&groups, distance)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
     }
 
     // Print duplicates
-    let mut found_duplicates = false;
-    for (hash, files) in hash_map.iter() {
-        if files.len() > 1 {
-            found_duplicates = true;
-            println!("Duplicate files with hash {}:", hash);
-            for file in files {
-                println!("  {}", file.display());
+    for group in &groups {
+        println!("Duplicate images of {}:", group.kept.display());
+        for file in &group.duplicates {
+            match distance(group, file) {
+                Some(distance) if distance > threshold => println!(
+                    "  {} (distance {}, only close through other images, left as is)",
+                    file.display(),
+                    distance
+                ),
+                Some(distance) => println!("  {} (distance {})", file.display(), distance),
+                None => println!("  {}", file.display()),
             }
-            println!();
         }
+        println!();
     }
 
-    if !found_duplicates {
+    if groups.is_empty() {
         println!("No duplicate files found.");
     }
 
-    Ok(())
+    // Groups chain close images, so only those within the threshold of the kept one are acted on
+    let groups: Vec<DuplicateGroup> = groups
+        .iter()
+        .map(|group| DuplicateGroup {
+            kept: group.kept.clone(),
+            duplicates: group
+                .duplicates
+                .iter()
+                .filter(|file| distance(group, file).map_or(true, |distance| distance <= threshold))
+                .cloned()
+                .collect(),
+        })
+        .collect();
+
+    // Get rid of the duplicates if requested
+    apply_action(&groups, args.action, &targets, move_to, args.apply)
 }
 
-fn process_image(path: &Path) -> Result<String> {
-    // Open file
-    let file = File::open(path).with_context(|| "Impossible to open file")?;
+// Group images whose perceptual hashes are within the threshold, including through a
+// chain of close images
+fn near_duplicates(
+    files: &[PathBuf],
+    hash: fn(&DynamicImage) -> u64,
+    threshold: u32,
+) -> (Vec<Vec<PathBuf>>, HashMap<PathBuf, u64>) {
+    // Calculate hashes
+    let hashes: Vec<ImageHash> = files
+        .par_iter()
+        .filter_map(|file| perceptual_hash(file, hash).ok())
+        .collect();
 
-    // Init buffer
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha256::new();
-    let mut buffer = vec![0; 1024 * 1024];
+    // Identical hashes are indexed once
+    let mut identical: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, image) in hashes.iter().enumerate() {
+        identical.entry(image.hash).or_default().push(i);
+    }
+    let mut unique: Vec<u64> = identical.keys().copied().collect();
+    unique.sort_unstable();
 
-    // Read bytes untile there are left
-    loop {
-        let bytes_read = reader.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
+    let mut tree = BkTree::default();
+    for &hash in &unique {
+        tree.insert(hash);
+    }
+
+    // Pairs of distinct hashes close to each other
+    let pairs: Vec<(u64, u64)> = unique
+        .par_iter()
+        .flat_map_iter(|&hash| {
+            tree.find(hash, threshold)
+                .into_iter()
+                .filter(move |&other| other > hash)
+                .map(move |other| (hash, other))
+        })
+        .collect();
+
+    // Union-find over identical and close hashes
+    let mut parents: Vec<usize> = (0..hashes.len()).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+    let mut union = |i: usize, j: usize| {
+        let (root_i, root_j) = (root(&mut parents, i), root(&mut parents, j));
+        parents[root_j] = root_i;
+    };
+    for indices in identical.values() {
+        for &i in &indices[1..] {
+            union(indices[0], i);
         }
-        hasher.update(&buffer[..bytes_read]);
     }
+    for (a, b) in pairs {
+        union(identical[&a][0], identical[&b][0]);
+    }
+
+    // Collect the groups, leaving out images without duplicates
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..hashes.len() {
+        members.entry(root(&mut parents, i)).or_default().push(i);
+    }
+
+    let groups = members
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|indices| indices.iter().map(|&i| hashes[i].path.clone()).collect())
+        .collect();
+
+    (
+        groups,
+        hashes
+            .into_iter()
+            .map(|image| (image.path, image.hash))
+            .collect(),
+    )
+}
+
+// Number of differing bits between two hashes
+fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
 
-    // Finalise the hash
-    let result = hasher.finalize();
+fn perceptual_hash(path: &Path, hash: fn(&DynamicImage) -> u64) -> Result<ImageHash> {
+    // Read image
+    let image = ImageReader::open(path)
+        .with_context(|| "Can't open image")?
+        .decode()
+        .with_context(|| "Can't decode image")?;
+
+    Ok(ImageHash {
+        path: path.to_path_buf(),
+        hash: hash(&image),
+    })
+}
+
+// Number of pixels of an image, only reading its header
+fn image_pixels(path: &Path) -> Result<u64> {
+    let (width, height) = image::image_dimensions(path).with_context(|| "Can't read image size")?;
+    Ok(width as u64 * height as u64)
+}
+
+// Grayscale thumbnail of an image, ignoring its aspect ratio
+fn gray_thumbnail(image: &DynamicImage, width: u32, height: u32) -> GrayImage {
+    thumbnail(&image.to_luma8(), width, height)
+}
+
+// Average hash: one bit per pixel of an 8x8 thumbnail, set if brighter than the mean
+fn average_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = gray_thumbnail(image, 8, 8);
+    let sum: u32 = thumbnail.pixels().map(|pixel| pixel.0[0] as u32).sum();
+
+    thumbnail
+        .pixels()
+        .enumerate()
+        .filter(|(_, pixel)| pixel.0[0] as u32 * HASH_BITS > sum)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+// Difference hash: one bit per pair of neighbours in the rows of a 9x8 thumbnail, set if
+// the left one is brighter
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = gray_thumbnail(image, 9, 8);
+
+    (0..8)
+        .flat_map(|y| (0..8).map(move |x| (x, y)))
+        .enumerate()
+        .filter(|(_, (x, y))| {
+            thumbnail.get_pixel(*x, *y).0[0] > thumbnail.get_pixel(x + 1, *y).0[0]
+        })
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+// DCT hash: one bit per coefficient of the 8x8 lowest frequencies of the DCT of a 32x32
+// thumbnail, set if above their median
+fn dct_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = gray_thumbnail(image, DCT_SIDE as u32, DCT_SIDE as u32);
+
+    // DCT-II basis, only for the lowest frequencies
+    let basis: Vec<Vec<f64>> = (0..8)
+        .map(|k| {
+            (0..DCT_SIDE)
+                .map(|n| (PI * (2 * n + 1) as f64 * k as f64 / (2 * DCT_SIDE) as f64).cos())
+                .collect()
+        })
+        .collect();
+
+    // Separable transform, first along rows then along columns
+    let rows: Vec<Vec<f64>> = (0..DCT_SIDE)
+        .map(|y| {
+            basis
+                .iter()
+                .map(|cosines| {
+                    (0..DCT_SIDE)
+                        .map(|x| thumbnail.get_pixel(x as u32, y as u32).0[0] as f64 * cosines[x])
+                        .sum()
+                })
+                .collect()
+        })
+        .collect();
+    let coefficients: Vec<f64> = basis
+        .iter()
+        .flat_map(|cosines| {
+            let rows = &rows;
+            (0..8).map(move |u| (0..DCT_SIDE).map(|y| rows[y][u] * cosines[y]).sum::<f64>())
+        })
+        .collect();
+
+    let mut sorted = coefficients.clone();
+    sorted.sort_by(f64::total_cmp);
+    let median = (sorted[31] + sorted[32]) / 2.0;
+
+    coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, coefficient)| **coefficient > median)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+// Build a table with one row per image of a duplicate group
+fn build_manifest(
+    groups: &[DuplicateGroup],
+    distance: impl Fn(&DuplicateGroup, &Path) -> Option<u32>,
+) -> Result<DataFrame> {
+    let distance = &distance;
+    let rows: Vec<(usize, &PathBuf, bool, u32)> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(i, group)| {
+            std::iter::once((i, &group.kept, true, 0)).chain(
+                group
+                    .duplicates
+                    .iter()
+                    .map(move |path| (i, path, false, distance(group, path).unwrap_or(0))),
+            )
+        })
+        .collect();
 
-    // Return the hash as a result
-    Ok(format!("{:x}", result))
+    df!(
+        "group" => rows.iter().map(|row| row.0 as u32).collect::<Vec<_>>(),
+        "path" => rows.iter().map(|row| row.1.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "representative" => rows.iter().map(|row| row.2).collect::<Vec<_>>(),
+        "distance" => rows.iter().map(|row| row.3).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")
 }
@@ -1,3 +1,4 @@
+pub mod convert;
 pub mod duplicates;
 pub mod resize;
 pub mod summary;
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
-use image::imageops::{resize, FilterType};
+use image::imageops::{replace, FilterType};
 use image::io::Reader as ImageReader;
+use image::{DynamicImage, Rgba, RgbaImage};
 use rayon::prelude::*;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
@@ -10,7 +11,18 @@ use crate::utils::{file_has_right_extension, perform_io_sanity_check};
 // Admissible extensions for this command
 const EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "bmp", "gif", "tiff", "tif"];
 
-use crate::ImageResizeArgs;
+use crate::{Color, ImageResizeArgs, ResizeFilter, ResizeMode};
+
+// Requested size and how images are brought to it
+#[derive(Debug, Clone, Copy)]
+struct Resize {
+    height: Option<u32>,
+    width: Option<u32>,
+    max_side: Option<u32>,
+    mode: ResizeMode,
+    filter: ResizeFilter,
+    pad_color: Color,
+}
 
 // Execute the resize command
 pub fn execute(args: ImageResizeArgs) -> Result<()> {
@@ -18,27 +30,49 @@ pub fn execute(args: ImageResizeArgs) -> Result<()> {
     let input = Path::new(&args.input);
     let output = Path::new(&args.output);
 
-    let height: u32 = args.height;
-    let width: u32 = args.width;
+    let resize = Resize {
+        height: args.height.or(args.legacy_size.first().copied()),
+        width: args.width.or(args.legacy_size.get(1).copied()),
+        max_side: args.max_side,
+        mode: args.mode,
+        filter: args.filter,
+        pad_color: args.pad_color,
+    };
 
     let overwrite: bool = args.overwrite;
 
+    // Check a size was requested, and that it is not empty
+    let sizes = [resize.height, resize.width, resize.max_side];
+    if sizes.iter().all(Option::is_none) {
+        return Err(anyhow::Error::msg(
+            "At least one of height, width and max side is required",
+        ));
+    }
+    if sizes.contains(&Some(0)) {
+        return Err(anyhow::Error::msg("Requested size must be positive"));
+    }
+    if resize.max_side.is_some() && (resize.height.is_some() || resize.width.is_some()) {
+        return Err(anyhow::Error::msg(
+            "Max side can't be combined with height or width",
+        ));
+    }
+
     // Sanity checks on I/O
     perform_io_sanity_check(input, output, false, true).with_context(|| "Sanity check failed")?;
 
     // Process files
-    process(input, height, width, output, overwrite).with_context(|| "Processing failed")?;
+    process(input, resize, output, overwrite).with_context(|| "Processing failed")?;
 
     Ok(())
 }
 
 // Process all the content (single file or directory of files)
-fn process(input: &Path, height: u32, width: u32, output: &Path, overwrite: bool) -> Result<()> {
+fn process(input: &Path, resize: Resize, output: &Path, overwrite: bool) -> Result<()> {
     // Case of single input file
     if input.is_file() {
         // Check if the file has the right extension and process it
         file_has_right_extension(input, &EXTENSIONS)?;
-        process_file(input, height, width, output, overwrite)
+        process_file(input, resize, output, overwrite)
             .with_context(|| format!("Failed to process file: {:?}", input))?;
     }
     // Case of input being a directory
@@ -68,7 +102,7 @@ fn process(input: &Path, height: u32, width: u32, output: &Path, overwrite: bool
             }
 
             // Process the file
-            process_file(file, height, width, &file_output, overwrite)
+            process_file(file, resize, &file_output, overwrite)
                 .with_context(|| format!("Failed to process file: {:?}", file))?;
 
             Ok(())
@@ -77,14 +111,116 @@ fn process(input: &Path, height: u32, width: u32, output: &Path, overwrite: bool
     Ok(())
 }
 
+// Filter of the image crate matching a choice of the CLI
+fn filter_type(filter: ResizeFilter) -> FilterType {
+    match filter {
+        ResizeFilter::Nearest => FilterType::Nearest,
+        ResizeFilter::Triangle => FilterType::Triangle,
+        ResizeFilter::CatmullRom => FilterType::CatmullRom,
+        ResizeFilter::Gaussian => FilterType::Gaussian,
+        ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+    }
+}
+
+// Scale a (width, height) size, rounding to the nearest pixel but never down to 0
+fn scale_size(width: u32, height: u32, scale: f64) -> (u32, u32) {
+    let scale_side = |side: u32| u32::max((side as f64 * scale).round() as u32, 1);
+    (scale_side(width), scale_side(height))
+}
+
+// Resize an image, cropping or padding it as requested
+fn resize_image(image: &DynamicImage, resize: Resize) -> DynamicImage {
+    let (width, height) = (image.width(), image.height());
+    let filter = filter_type(resize.filter);
+
+    match (resize.width, resize.height) {
+        // Both sides requested
+        (Some(target_width), Some(target_height)) => {
+            let scale_x = target_width as f64 / width as f64;
+            let scale_y = target_height as f64 / height as f64;
+
+            match resize.mode {
+                ResizeMode::Stretch => image.resize_exact(target_width, target_height, filter),
+                ResizeMode::Fit | ResizeMode::Pad => {
+                    let (fit_width, fit_height) =
+                        scale_size(width, height, f64::min(scale_x, scale_y));
+                    let (fit_width, fit_height) = (
+                        u32::min(fit_width, target_width),
+                        u32::min(fit_height, target_height),
+                    );
+                    let fitted = image.resize_exact(fit_width, fit_height, filter);
+                    if resize.mode == ResizeMode::Fit {
+                        return fitted;
+                    }
+
+                    // Center on a canvas of the pad color
+                    let mut canvas = RgbaImage::from_pixel(
+                        target_width,
+                        target_height,
+                        Rgba(resize.pad_color.0),
+                    );
+                    replace(
+                        &mut canvas,
+                        &fitted.to_rgba8(),
+                        ((target_width - fit_width) / 2) as i64,
+                        ((target_height - fit_height) / 2) as i64,
+                    );
+
+                    // Only keep an alpha channel if there is some transparency to store
+                    match image.color().has_alpha() || resize.pad_color.0[3] < u8::MAX {
+                        true => DynamicImage::ImageRgba8(canvas),
+                        false => {
+                            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8())
+                        }
+                    }
+                }
+                ResizeMode::Fill => {
+                    let (fill_width, fill_height) =
+                        scale_size(width, height, f64::max(scale_x, scale_y));
+                    let (fill_width, fill_height) = (
+                        u32::max(fill_width, target_width),
+                        u32::max(fill_height, target_height),
+                    );
+                    image
+                        .resize_exact(fill_width, fill_height, filter)
+                        .crop_imm(
+                            (fill_width - target_width) / 2,
+                            (fill_height - target_height) / 2,
+                            target_width,
+                            target_height,
+                        )
+                }
+            }
+        }
+        // A single side requested, the other following the aspect ratio
+        (Some(target_width), None) => {
+            let (new_width, new_height) =
+                scale_size(width, height, target_width as f64 / width as f64);
+            image.resize_exact(new_width, new_height, filter)
+        }
+        (None, Some(target_height)) => {
+            let (new_width, new_height) =
+                scale_size(width, height, target_height as f64 / height as f64);
+            image.resize_exact(new_width, new_height, filter)
+        }
+        // Longest side capped, smaller images are unchanged
+        (None, None) => {
+            let max_side = resize.max_side.unwrap_or(u32::MAX);
+            let longest = u32::max(width, height);
+            match longest > max_side {
+                true => {
+                    let (new_width, new_height) =
+                        scale_size(width, height, max_side as f64 / longest as f64);
+                    image.resize_exact(new_width, new_height, filter)
+                }
+                false => image.clone(),
+            }
+        }
+    }
+}
+
 // Process a single file
-fn process_file(
-    input: &Path,
-    height: u32,
-    width: u32,
-    output: &Path,
-    overwrite: bool,
-) -> Result<()> {
+fn process_file(input: &Path, resize: Resize, output: &Path, overwrite: bool) -> Result<()> {
     // Check that we can overwrite
     if input == output && !overwrite {
         return Err(anyhow::Error::msg("Can't overwrite files"));
@@ -97,11 +233,11 @@ fn process_file(
         .with_context(|| "Can't decode image")?;
 
     // Resize image
-    let output_img = resize(&input_img, width, height, FilterType::Lanczos3);
+    let output_img = resize_image(&input_img, resize);
 
     // Save image
     output_img
-        .save(&output)
+        .save(output)
         .with_context(|| format!("Couldn't save image to {:?}", output))?;
 
     Ok(())
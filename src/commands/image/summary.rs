@@ -1,11 +1,16 @@
 use anyhow::{Context, Result};
 use image::image_dimensions;
+use polars::prelude::*;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-use crate::utils::file_has_right_extension;
+use crate::utils::{
+    file_has_right_extension, partition_results, print_summary, report_failures, write_table,
+};
 
 use crate::ImageSummaryArgs;
 
@@ -46,27 +51,95 @@ pub fn execute(args: ImageSummaryArgs) -> Result<()> {
     }
 
     // Process files
-    let info: Vec<(u32, u32)> = files
+    let results: Vec<(PathBuf, Result<ImageInfo>)> = files
         .into_par_iter()
-        .filter_map(|file| process_image(&file).ok())
+        .map(|file| {
+            let result = process_image(&file);
+            (file, result)
+        })
         .collect();
 
-    // Calculate total number of files
-    let n_files = info.len();
+    // Separate readable files from failures and report the latter
+    let (info, failures) = partition_results(results);
+    report_failures(&failures, args.errors.as_deref().map(Path::new))?;
 
-    // Get unique values
-    let unique_shapes: HashSet<_> = info.into_iter().collect();
+    // Write the per-file manifest if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&info)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
+    }
+
+    // Build and print the summary
+    let summary = ImageSummary::from_info(&info, failures.len());
+    print_summary(&summary, args.format)?;
 
-    // Print results
-    println!("Total files: {}", n_files);
-    println!("Unique (height, width) pairs: {:?}", unique_shapes);
+    // In strict mode any unreadable file is an error
+    if args.strict && !failures.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} files could not be read",
+            failures.len()
+        )));
+    }
 
     Ok(())
 }
 
+// Properties of a single image file
+#[derive(Debug)]
+struct ImageInfo {
+    path: PathBuf,
+    height: u32,
+    width: u32,
+}
+
+// Aggregated properties of a collection of image files
+#[derive(Debug, Serialize)]
+pub struct ImageSummary {
+    pub total_files: usize,
+    pub failed_files: usize,
+    pub shapes: Vec<(u32, u32)>,
+}
+
+impl ImageSummary {
+    // Aggregate the probed properties of every file
+    fn from_info(info: &[ImageInfo], failed_files: usize) -> Self {
+        // Get unique values
+        let shapes: BTreeSet<_> = info.iter().map(|file| (file.height, file.width)).collect();
+
+        Self {
+            total_files: info.len(),
+            failed_files,
+            shapes: shapes.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for ImageSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total files: {}", self.total_files)?;
+        writeln!(f, "Failed files: {}", self.failed_files)?;
+        writeln!(f, "Unique (height, width) pairs: {:?}", self.shapes)
+    }
+}
+
+// Collect the properties of every file into a table, one row per file
+fn build_manifest(info: &[ImageInfo]) -> Result<DataFrame> {
+    df!(
+        "path" => info.iter().map(|file| file.path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "height" => info.iter().map(|file| file.height).collect::<Vec<_>>(),
+        "width" => info.iter().map(|file| file.width).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")
+}
+
 // Function for getting relevant info of an image file by just probing it
-fn process_image(path: &Path) -> Result<(u32, u32)> {
+fn process_image(path: &Path) -> Result<ImageInfo> {
     image_dimensions(path)
-        .map(|(width, height)| (height, width))
+        .map(|(width, height)| ImageInfo {
+            path: path.to_path_buf(),
+            height,
+            width,
+        })
         .with_context(|| "Error extracting image dimensions")
 }
@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
+use polars::prelude::*;
+use serde::Serialize;
+use std::fmt;
 use std::path::Path;
 
-use crate::utils::read_table;
+use crate::utils::{print_summary, read_table};
 
 use crate::TableSummaryArgs;
 
@@ -25,9 +28,45 @@ pub fn execute(args: TableSummaryArgs) -> Result<()> {
         .collect()
         .with_context(|| "Cannot collect Dataframe".to_string())?;
 
-    println!("{:?}", df.head(Some(5_usize)));
-    println!("------------");
-    println!("{:?}", df.tail(Some(5_usize)));
+    // Build and print the summary
+    let summary = TableSummary::from_df(&df);
+    print_summary(&summary, args.format)?;
 
     Ok(())
 }
+
+// Shape, schema and preview of a tabular file
+#[derive(Debug, Serialize)]
+pub struct TableSummary {
+    pub rows: usize,
+    pub columns: Vec<String>,
+    pub dtypes: Vec<String>,
+    #[serde(skip)]
+    head: DataFrame,
+    #[serde(skip)]
+    tail: DataFrame,
+}
+
+impl TableSummary {
+    fn from_df(df: &DataFrame) -> Self {
+        Self {
+            rows: df.height(),
+            columns: df
+                .get_column_names()
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            dtypes: df.dtypes().iter().map(|dtype| dtype.to_string()).collect(),
+            head: df.head(Some(5_usize)),
+            tail: df.tail(Some(5_usize)),
+        }
+    }
+}
+
+impl fmt::Display for TableSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?}", self.head)?;
+        writeln!(f, "------------")?;
+        writeln!(f, "{:?}", self.tail)
+    }
+}
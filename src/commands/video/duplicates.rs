@@ -1,13 +1,11 @@
+extern crate ffmpeg_next as ffmpeg;
+
 use anyhow::{Context, Result};
-use rayon::prelude::*;
-use sha2::{Digest, Sha256};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
-use crate::utils::file_has_right_extension;
+use crate::utils::duplicates::{
+    apply_action, check_action, exact_duplicates, find_files, keep_one,
+};
 
 use crate::VideoDuplicatesArgs;
 
@@ -16,85 +14,65 @@ const EXTENSIONS: [&str; 4] = ["ts", "mp4", "mkv", "mov"];
 
 pub fn execute(args: VideoDuplicatesArgs) -> Result<()> {
     // Parse the arguments
-    let target = Path::new(&args.target);
-
-    // Error if it does not exist at all
-    if !target.exists() {
-        return Err(anyhow::Error::msg("Target directory does not exist"));
-    }
+    let targets: Vec<PathBuf> = args.targets.iter().map(PathBuf::from).collect();
+    let move_to = args.move_to.as_deref().map(Path::new);
 
-    // Error if target is not a directory
-    if !target.is_dir() {
-        return Err(anyhow::Error::msg("Target must be a directory"));
-    }
+    check_action(args.action, move_to)?;
 
     // Find all admissible files
-    let video_files: Vec<PathBuf> = WalkDir::new(target)
-        .max_depth(1)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| file_has_right_extension(e.path(), &EXTENSIONS).is_ok())
-        .map(|e| e.path().to_path_buf())
-        .collect();
+    let video_files = find_files(&targets, args.recursive, &EXTENSIONS)?;
 
     // Raise error if impossible to find duplicates
     if video_files.len() < 2 {
-        return Err(anyhow::Error::msg("Directory contains less than 2 files"));
+        return Err(anyhow::Error::msg("Targets contain less than 2 files"));
     }
 
-    // Calculate hashes
-    let hashes: Vec<(String, PathBuf)> = video_files
-        .par_iter()
-        .filter_map(|file| process_video(file).ok().map(|hash| (hash, file.clone())))
-        .collect();
+    // Group files with the same bytes
+    let clusters = exact_duplicates(&video_files, args.method);
 
-    // Group files by hash to find duplicates
-    let mut hash_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
-    for (hash, path) in hashes {
-        hash_map.entry(hash).or_default().push(path);
-    }
+    // Choose the video kept out of every group
+    let mut groups = clusters
+        .into_iter()
+        .map(|files| keep_one(files, args.keep, video_pixels))
+        .collect::<Result<Vec<_>>>()?;
+    groups.sort_by(|a, b| a.kept.cmp(&b.kept));
 
     // Print duplicates
-    let mut found_duplicates = false;
-    for (hash, files) in hash_map.iter() {
-        if files.len() > 1 {
-            found_duplicates = true;
-            println!("Duplicate files with hash {}:", hash);
-            for file in files {
-                println!("  {}", file.display());
-            }
-            println!();
+    for group in &groups {
+        println!("Duplicate videos of {}:", group.kept.display());
+        for file in &group.duplicates {
+            println!("  {}", file.display());
         }
+        println!();
     }
 
-    if !found_duplicates {
+    if groups.is_empty() {
         println!("No duplicate files found.");
     }
 
-    Ok(())
+    // Get rid of the duplicates if requested
+    apply_action(&groups, args.action, &targets, move_to, args.apply)
 }
 
-fn process_video(path: &Path) -> Result<String> {
-    // Open file
-    let file = File::open(path).with_context(|| "Impossible to open file")?;
-
-    // Init buffer
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha256::new();
-    let mut buffer = vec![0; 1024 * 1024];
-
-    // Read bytes untile there are left
-    loop {
-        let bytes_read = reader.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        hasher.update(&buffer[..bytes_read]);
-    }
-
-    // Finalise the hash
-    let result = hasher.finalize();
-
-    // Return the hash as a result
-    Ok(format!("{:x}", result))
+// Number of pixels of the frames of a video, by just probing it
+fn video_pixels(path: &Path) -> Result<u64> {
+    // Read context
+    let context = ffmpeg::format::input(&path).with_context(|| "Couldn't read video")?;
+
+    // Only select the video stream
+    let video_stream = context
+        .streams()
+        .find(|s| s.parameters().medium() == ffmpeg::media::Type::Video)
+        .ok_or_else(|| anyhow::Error::msg("No video stream found in file"))?;
+
+    // Create decoder
+    let context_decoder =
+        ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())
+            .with_context(|| "Failed to create decoder context from video stream parameters")?;
+    let decoder = context_decoder
+        .decoder()
+        .video()
+        .with_context(|| "Failed to create video decoder from decoder context")?;
+
+    Ok(decoder.width() as u64 * decoder.height() as u64)
 }
@@ -1,12 +1,17 @@
 extern crate ffmpeg_next as ffmpeg;
 
 use anyhow::{Context, Result};
+use polars::prelude::*;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-use crate::utils::file_has_right_extension;
+use crate::utils::{
+    file_has_right_extension, partition_results, print_summary, report_failures, write_table,
+};
 
 use crate::VideoSummaryArgs;
 
@@ -47,40 +52,113 @@ pub fn execute(args: VideoSummaryArgs) -> Result<()> {
     }
 
     // Process files
-    let info: Vec<(f64, u32, u32, u32, u32)> = files
+    let results: Vec<(PathBuf, Result<VideoInfo>)> = files
         .into_par_iter()
-        .filter_map(|file| process_video(&file).ok())
+        .map(|file| {
+            let result = process_video(&file);
+            (file, result)
+        })
         .collect();
 
-    // Calculate total number of files
-    let n_files = info.len();
+    // Separate readable files from failures and report the latter
+    let (info, failures) = partition_results(results);
+    report_failures(&failures, args.errors.as_deref().map(Path::new))?;
 
-    // Sum all durations
-    let total_duration: f64 = info.iter().map(|(duration, _, _, _, _)| duration).sum();
+    // Write the per-file manifest if requested
+    if let Some(manifest) = &args.manifest {
+        let manifest = Path::new(manifest);
+        write_table(&mut build_manifest(&info)?, manifest)
+            .with_context(|| format!("Failed to write manifest: {:?}", manifest))?;
+    }
 
-    // Get unique values
-    let unique_durations: HashSet<_> = info
-        .iter()
-        .map(|(duration, _, _, _, _)| *duration as u64)
-        .collect();
-    let unique_fps: HashSet<_> = info
-        .iter()
-        .map(|(_, fps_num, fps_den, _, _)| (fps_num, fps_den))
-        .collect();
-    let unique_shapes: HashSet<_> = info.iter().map(|(_, _, _, h, w)| (h, w)).collect();
+    // Build and print the summary
+    let summary = VideoSummary::from_info(&info, failures.len());
+    print_summary(&summary, args.format)?;
 
-    // Print results
-    println!("Total files: {}", n_files);
-    println!("Total duration: {}", total_duration);
-    println!("Unique durations: {:?}", unique_durations);
-    println!("Unique (height, width) pairs: {:?}", unique_shapes);
-    println!("Unique  FPS: {:?}", unique_fps);
+    // In strict mode any unreadable file is an error
+    if args.strict && !failures.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} files could not be read",
+            failures.len()
+        )));
+    }
 
     Ok(())
 }
 
+// Properties of a single video file
+#[derive(Debug)]
+struct VideoInfo {
+    path: PathBuf,
+    duration: f64,
+    fps_numerator: u32,
+    fps_denominator: u32,
+    height: u32,
+    width: u32,
+}
+
+// Aggregated properties of a collection of video files
+#[derive(Debug, Serialize)]
+pub struct VideoSummary {
+    pub total_files: usize,
+    pub failed_files: usize,
+    pub total_duration_seconds: f64,
+    pub durations: Vec<u64>,
+    pub shapes: Vec<(u32, u32)>,
+    pub fps: Vec<(u32, u32)>,
+}
+
+impl VideoSummary {
+    // Aggregate the probed properties of every file
+    fn from_info(info: &[VideoInfo], failed_files: usize) -> Self {
+        // Sum all durations
+        let total_duration_seconds: f64 = info.iter().map(|file| file.duration).sum();
+
+        // Get unique values
+        let durations: BTreeSet<_> = info.iter().map(|file| file.duration as u64).collect();
+        let fps: BTreeSet<_> = info
+            .iter()
+            .map(|file| (file.fps_numerator, file.fps_denominator))
+            .collect();
+        let shapes: BTreeSet<_> = info.iter().map(|file| (file.height, file.width)).collect();
+
+        Self {
+            total_files: info.len(),
+            failed_files,
+            total_duration_seconds,
+            durations: durations.into_iter().collect(),
+            shapes: shapes.into_iter().collect(),
+            fps: fps.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for VideoSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total files: {}", self.total_files)?;
+        writeln!(f, "Failed files: {}", self.failed_files)?;
+        writeln!(f, "Total duration: {}", self.total_duration_seconds)?;
+        writeln!(f, "Unique durations: {:?}", self.durations)?;
+        writeln!(f, "Unique (height, width) pairs: {:?}", self.shapes)?;
+        writeln!(f, "Unique  FPS: {:?}", self.fps)
+    }
+}
+
+// Collect the properties of every file into a table, one row per file
+fn build_manifest(info: &[VideoInfo]) -> Result<DataFrame> {
+    df!(
+        "path" => info.iter().map(|file| file.path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+        "duration_seconds" => info.iter().map(|file| file.duration).collect::<Vec<_>>(),
+        "fps_numerator" => info.iter().map(|file| file.fps_numerator).collect::<Vec<_>>(),
+        "fps_denominator" => info.iter().map(|file| file.fps_denominator).collect::<Vec<_>>(),
+        "height" => info.iter().map(|file| file.height).collect::<Vec<_>>(),
+        "width" => info.iter().map(|file| file.width).collect::<Vec<_>>(),
+    )
+    .with_context(|| "Failed to build manifest")
+}
+
 // Function for getting relevant info of an image file by just probing it
-fn process_video(path: &Path) -> Result<(f64, u32, u32, u32, u32)> {
+fn process_video(path: &Path) -> Result<VideoInfo> {
     // Read context
     let context = ffmpeg::format::input(&path).with_context(|| "Couldn't read video")?;
 
@@ -109,7 +187,14 @@ fn process_video(path: &Path) -> Result<(f64, u32, u32, u32, u32)> {
         let width = decoder.width();
         let height = decoder.height();
 
-        Ok((duration, fps_numerator, fps_denominator, height, width))
+        Ok(VideoInfo {
+            path: path.to_path_buf(),
+            duration,
+            fps_numerator,
+            fps_denominator,
+            height,
+            width,
+        })
     } else {
         Err(anyhow::Error::msg("No video stream found in file"))
     }
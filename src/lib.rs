@@ -1,8 +1,431 @@
-use clap::{Args, Parser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::str::FromStr;
 
 pub mod commands;
 pub mod utils;
 
+// Output formats shared by the summary commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    Text,
+    /// JSON object
+    Json,
+    /// Single-row CSV with header
+    Csv,
+}
+
+// Ways of cutting audio files in audio split
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SplitMode {
+    /// Chunks of fixed duration, the last one padded with zeros
+    Fixed,
+    /// Segments separated by silent regions
+    Silence,
+}
+
+// What to do with the last chunk of audio split when it is shorter than the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SplitTail {
+    /// Pad it with zeros
+    Pad,
+    /// Discard it
+    Drop,
+    /// Keep it shorter
+    Keep,
+}
+
+// Level targeted by audio normalize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NormalizeMode {
+    /// Sample peak in dBFS
+    Peak,
+    /// EBU R128 integrated loudness in LUFS
+    Loudness,
+}
+
+// How image resize fits images to the requested height and width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResizeMode {
+    /// Exactly the requested size, distorting the image if needed
+    Stretch,
+    /// Largest size inside the requested one, keeping the aspect ratio
+    Fit,
+    /// Smallest size covering the requested one, center-cropped to it
+    Fill,
+    /// Fit, then centered on borders of the pad color
+    Pad,
+}
+
+// Resampling filter of image resize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResizeFilter {
+    /// Nearest neighbour
+    Nearest,
+    /// Bilinear
+    Triangle,
+    /// Bicubic
+    CatmullRom,
+    /// Gaussian
+    Gaussian,
+    /// Lanczos with a window of 3
+    Lanczos3,
+}
+
+// Output format of image convert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImageCodec {
+    /// Lossless PNG, keeping 16-bit channels
+    Png,
+    /// Lossy JPEG, without transparency
+    Jpeg,
+    /// Lossless WebP
+    Webp,
+    /// Uncompressed TIFF, keeping 16-bit channels
+    Tiff,
+    /// Uncompressed BMP
+    Bmp,
+}
+
+impl ImageCodec {
+    /// Extensions of the files encoded with this codec, the first one being used for new files
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Png => &["png"],
+            Self::Jpeg => &["jpg", "jpeg"],
+            Self::Webp => &["webp"],
+            Self::Tiff => &["tiff", "tif"],
+            Self::Bmp => &["bmp"],
+        }
+    }
+}
+
+// Hash comparing images in image duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImageHashMethod {
+    /// SHA-256 of the file bytes, for exact copies only
+    Sha256,
+    /// XXH3 of the file bytes, for exact copies only, faster but not cryptographic
+    Xxh3,
+    /// Pixels of an 8x8 thumbnail compared to their mean
+    Ahash,
+    /// Pixels of a 9x8 thumbnail compared to their right neighbour
+    Dhash,
+    /// Low frequencies of the DCT of a 32x32 thumbnail compared to their median
+    Phash,
+}
+
+// Hash of the bytes of files, finding exact copies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ContentHash {
+    /// Cryptographic SHA-256
+    Sha256,
+    /// Non-cryptographic 128-bit XXH3, much faster
+    Xxh3,
+}
+
+// File kept out of a group of duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeepPolicy {
+    /// Earliest modification time
+    Oldest,
+    /// Fewest characters in the path
+    ShortestPath,
+    /// Most pixels
+    LargestResolution,
+}
+
+// What happens to the duplicates that are not kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DuplicateAction {
+    /// Only list them
+    Report,
+    /// Delete them
+    Delete,
+    /// Replace them with hard links to the kept file
+    Hardlink,
+    /// Move them to another directory, mirroring their layout
+    Move,
+}
+
+// Trade-off between speed and fidelity of audio resample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResampleQuality {
+    /// Short sinc filter with linear interpolation
+    Fast,
+    /// FFT-based resampling
+    Balanced,
+    /// Long sinc filter with cubic interpolation
+    Best,
+}
+
+// Where audio pad adds the missing audio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PadPosition {
+    /// Before the audio
+    Start,
+    /// After the audio
+    End,
+    /// Half before and half after the audio
+    Both,
+}
+
+// What audio pad fills the missing audio with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PadMode {
+    /// Zeros
+    Silence,
+    /// The audio mirrored at its edges
+    Reflect,
+}
+
+// Shape of the gain of fades and crossfades
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FadeCurve {
+    /// Gain changing linearly
+    Linear,
+    /// Quarter of a sine, keeping the power constant in crossfades
+    Sine,
+    /// Level changing linearly in dB over 60 dB, slow at low levels
+    Exponential,
+    /// Mirror image of exponential, fast at low levels
+    Logarithmic,
+}
+
+// Output codec of audio convert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AudioCodec {
+    /// Uncompressed WAV
+    Wav,
+    /// Lossless FLAC
+    Flac,
+    /// Lossy MP3
+    Mp3,
+    /// Lossy Opus
+    Opus,
+}
+
+impl AudioCodec {
+    /// Extension of the files encoded with this codec
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+            Self::Mp3 => "mp3",
+            Self::Opus => "opus",
+        }
+    }
+}
+
+// Sample format of the files written by audio convert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AudioSampleFormat {
+    /// Signed integer samples
+    Int,
+    /// 32-bit floating point samples
+    Float,
+}
+
+// Output format of audio spectrogram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SpectrogramFormat {
+    /// Grayscale image, low frequencies at the bottom
+    Png,
+    /// NumPy array of shape (bins, frames)
+    Npy,
+    /// Table with one row per frame and one column per bin
+    Parquet,
+}
+
+impl SpectrogramFormat {
+    /// Extension of the files written in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Npy => "npy",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+// Window applied to every frame of audio spectrogram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SpectrogramWindow {
+    /// Raised cosine reaching zero at the edges, a good default
+    Hann,
+    /// Raised cosine not reaching zero, with lower nearest side lobes
+    Hamming,
+    /// Sum of cosines with low leakage but a wider main lobe
+    Blackman,
+    /// No weighting, the sharpest peaks but the most leakage
+    Rectangular,
+}
+
+// Target channel layout of audio channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelConversion {
+    /// Given number of channels, among those with a defined mapping from the input ones
+    Count(u16),
+    /// Average of all the channels into mono
+    Mix,
+    /// Single channel, counting from 0
+    Pick(u16),
+}
+
+impl FromStr for ChannelConversion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mix" => Ok(Self::Mix),
+            _ => {
+                if let Some(channel) = s.strip_prefix("pick:") {
+                    channel
+                        .parse()
+                        .map(Self::Pick)
+                        .map_err(|_| format!("Invalid channel to pick: {}", channel))
+                } else {
+                    match s.parse() {
+                        Ok(0) | Err(_) => Err(format!(
+                            "Expected a positive number of channels, mix or pick:K, got {}",
+                            s
+                        )),
+                        Ok(n) => Ok(Self::Count(n)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Tag assignment of audio tags set, as KEY=VALUE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagAssignment {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for TagAssignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Self {
+                key: key.trim().to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("Expected KEY=VALUE, got {}", s)),
+        }
+    }
+}
+
+// Time position in audio trim, as seconds or [HH:]MM:SS[.fff], negative ones counting from the end
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timestamp {
+    pub from_end: bool,
+    pub nanoseconds: u64,
+}
+
+impl Timestamp {
+    /// Timestamp of a number of seconds, rounded to the nearest nanosecond
+    pub fn from_seconds(seconds: f64) -> Result<Self, String> {
+        let nanoseconds = (seconds.abs() * 1e9).round();
+        if !nanoseconds.is_finite() || nanoseconds >= u64::MAX as f64 {
+            return Err(format!(
+                "Expected a finite number of seconds, got {}",
+                seconds
+            ));
+        }
+
+        Ok(Self {
+            from_end: seconds.is_sign_negative() && nanoseconds > 0.0,
+            nanoseconds: nanoseconds as u64,
+        })
+    }
+
+    /// Number of frames at a sample rate, rounded to the nearest one
+    pub fn frames(&self, sample_rate: u32) -> usize {
+        ((self.nanoseconds as u128 * sample_rate as u128 + 500_000_000) / 1_000_000_000) as usize
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected seconds or [HH:]MM:SS[.fff], got {}", s);
+
+        let (from_end, time) = match s.trim().strip_prefix('-') {
+            Some(time) => (true, time),
+            None => (false, s.trim()),
+        };
+
+        // Hours and minutes are whole numbers, only the seconds have a fractional part
+        let mut parts: Vec<&str> = time.split(':').collect();
+        let seconds = parts.pop().ok_or_else(invalid)?;
+        if parts.len() > 2 {
+            return Err(invalid());
+        }
+
+        let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+        if !digits(whole) || !(fraction.is_empty() || digits(fraction)) || fraction.len() > 9 {
+            return Err(invalid());
+        }
+
+        let mut total: u64 = 0;
+        for (i, part) in parts.iter().chain([&whole]).enumerate() {
+            if !digits(part) {
+                return Err(invalid());
+            }
+            let value: u64 = part.parse().map_err(|_| invalid())?;
+            // Only the leading field may exceed its usual range
+            if i > 0 && value >= 60 {
+                return Err(invalid());
+            }
+            total = total
+                .checked_mul(60)
+                .and_then(|total| total.checked_add(value))
+                .ok_or_else(invalid)?;
+        }
+
+        let fraction: u64 = format!("{:0<9}", fraction).parse().map_err(|_| invalid())?;
+        let nanoseconds = total
+            .checked_mul(1_000_000_000)
+            .and_then(|total| total.checked_add(fraction))
+            .ok_or_else(invalid)?;
+
+        Ok(Self {
+            from_end,
+            nanoseconds,
+        })
+    }
+}
+
+// RGBA color, as #RRGGBB or #RRGGBBAA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub [u8; 4]);
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected a color as #RRGGBB or #RRGGBBAA, got {}", s);
+
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut channels = [u8::MAX; 4];
+        for (i, channel) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+            *channel = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+
+        Ok(Self(channels))
+    }
+}
+
 // Export all the Args structs as they're needed by both the CLI and tests
 #[derive(Debug, Parser)]
 pub struct FileCountArgs {
@@ -21,26 +444,99 @@ pub struct ImageSummaryArgs {
     /// Target directory or file
     #[arg(required = true)]
     pub target: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Write one row per file to a manifest (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Write unreadable files and their errors to a table (CSV or parquet)
+    #[arg(long)]
+    pub errors: Option<String>,
+
+    /// Fail if any file cannot be read
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
 }
 
 #[derive(Debug, Parser)]
+#[command(allow_missing_positional = true)]
 pub struct ImageResizeArgs {
     /// Input file or directory
     #[arg(required = true)]
     pub input: String,
 
-    /// Requested height
+    /// Height and width of the former `<input> <height> <width> <output>` form
+    #[arg(
+        hide = true,
+        num_args = 2,
+        value_names = ["HEIGHT", "WIDTH"],
+        conflicts_with_all = ["height", "width", "max_side"]
+    )]
+    pub legacy_size: Vec<u32>,
+
+    /// Output file or directory
     #[arg(required = true)]
-    pub height: u32,
+    pub output: String,
+
+    /// Requested height, the width following the aspect ratio if not given
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// Requested width, the height following the aspect ratio if not given
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// Longest side of the output, smaller images being kept as they are
+    #[arg(long, conflicts_with_all = ["height", "width"])]
+    pub max_side: Option<u32>,
 
-    /// Requested width
+    /// How images fit the requested height and width when both are given
+    #[arg(long, value_enum, default_value_t = ResizeMode::Stretch)]
+    pub mode: ResizeMode,
+
+    /// Resampling filter
+    #[arg(long, value_enum, default_value_t = ResizeFilter::Lanczos3)]
+    pub filter: ResizeFilter,
+
+    /// Color of the borders in pad mode, as #RRGGBB or #RRGGBBAA
+    #[arg(long, default_value = "#000000")]
+    pub pad_color: Color,
+
+    /// Flag to enable overwriting of input file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImageConvertArgs {
+    /// Input file or directory
     #[arg(required = true)]
-    pub width: u32,
+    pub input: String,
 
     /// Output file or directory
     #[arg(required = true)]
     pub output: String,
 
+    /// Output format, which also sets the extension of the output files
+    #[arg(long, value_enum, required = true)]
+    pub to: ImageCodec,
+
+    /// Quality of JPEG output, from 1 to 100 [default: 90]
+    #[arg(long)]
+    pub quality: Option<u8>,
+
+    /// Color transparent pixels are blended over for JPEG output, as #RRGGBB
+    #[arg(long, default_value = "#FFFFFF")]
+    pub background: Color,
+
+    /// Drop the EXIF and ICC profile instead of carrying them to JPEG and PNG output
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strip_metadata: bool,
+
     /// Flag to enable overwriting of input file
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub overwrite: bool,
@@ -101,16 +597,107 @@ pub struct ImageToPortraitArgs {
 
 #[derive(Debug, Parser)]
 pub struct ImageDuplicatesArgs {
-    /// Target image directory
+    /// Target image directories, compared with each other
+    #[arg(required = true, num_args = 1..)]
+    pub targets: Vec<String>,
+
+    /// Also look into subdirectories
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub recursive: bool,
+
+    /// Hash used to compare images
+    #[arg(long, value_enum, default_value_t = ImageHashMethod::Sha256)]
+    pub method: ImageHashMethod,
+
+    /// Maximum Hamming distance (out of 64 bits) between perceptual hashes of duplicates [default: 10]
+    #[arg(long)]
+    pub threshold: Option<u32>,
+
+    /// Write one row per duplicate file to a table (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Image kept out of every group of duplicates
+    #[arg(long, value_enum, default_value_t = KeepPolicy::LargestResolution)]
+    pub keep: KeepPolicy,
+
+    /// What to do with the other images of every group
+    #[arg(long, value_enum, default_value_t = DuplicateAction::Report)]
+    pub action: DuplicateAction,
+
+    /// Directory the duplicates are moved to with the move action
+    #[arg(long, required_if_eq("action", "move"))]
+    pub move_to: Option<String>,
+
+    /// Perform the action, which is otherwise only printed
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub apply: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioSummaryArgs {
+    /// Target directory or file
     #[arg(required = true)]
     pub target: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Write one row per file to a manifest (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Write unreadable files and their errors to a table (CSV or parquet)
+    #[arg(long)]
+    pub errors: Option<String>,
+
+    /// Fail if any file cannot be read
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
+
+    /// Decode the samples and report level and quality statistics
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub analyze: bool,
 }
 
 #[derive(Debug, Parser)]
-pub struct AudioSummaryArgs {
+pub struct AudioVadArgs {
     /// Target directory or file
     #[arg(required = true)]
     pub target: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Write the speech duration and ratio of every file to a manifest (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Write one row per speech segment to a table (CSV or parquet)
+    #[arg(long)]
+    pub segments: Option<String>,
+
+    /// Level in dBFS above which a window is considered voiced
+    #[arg(long, default_value_t = -40.0, allow_hyphen_values = true)]
+    pub threshold: f64,
+
+    /// Minimum duration in seconds of a speech segment
+    #[arg(long, default_value_t = 0.1)]
+    pub min_speech: f64,
+
+    /// Minimum duration in seconds of a pause between speech segments
+    #[arg(long, default_value_t = 0.3)]
+    pub min_silence: f64,
+
+    /// Write unreadable files and their errors to a table (CSV or parquet)
+    #[arg(long)]
+    pub errors: Option<String>,
+
+    /// Fail if any file cannot be read
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -119,7 +706,7 @@ pub struct AudioSplitArgs {
     #[arg(required = true)]
     pub input: String,
 
-    /// Chunk duration in seconds
+    /// Chunk duration in seconds (maximum segment duration in silence mode)
     #[arg(required = true)]
     pub chunk_duration: f32,
 
@@ -130,6 +717,34 @@ pub struct AudioSplitArgs {
     /// Delete original file
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub delete_original: bool,
+
+    /// How to cut the files
+    #[arg(long, value_enum, default_value_t = SplitMode::Fixed)]
+    pub mode: SplitMode,
+
+    /// Seconds between the starts of consecutive chunks, defaults to the chunk duration (fixed mode)
+    #[arg(long)]
+    pub hop: Option<f32>,
+
+    /// What to do with chunks cut short by the end of the file (fixed mode)
+    #[arg(long, value_enum, default_value_t = SplitTail::Pad)]
+    pub tail: SplitTail,
+
+    /// RMS level in dBFS below which audio is silent (silence mode)
+    #[arg(long, default_value_t = -40.0, allow_hyphen_values = true)]
+    pub threshold: f32,
+
+    /// Minimum duration in seconds of a silence to cut at (silence mode)
+    #[arg(long, default_value_t = 0.5)]
+    pub min_silence: f32,
+
+    /// Minimum duration in seconds of a segment, shorter ones are discarded (silence mode)
+    #[arg(long, default_value_t = 0.0)]
+    pub min_segment: f32,
+
+    /// Write the boundaries of every segment to a table (CSV or parquet)
+    #[arg(long)]
+    pub boundaries: Option<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -146,17 +761,262 @@ pub struct AudioResampleArgs {
     #[arg(required = true)]
     pub output: String,
 
+    /// Resampler preset, trading speed for fidelity
+    #[arg(long, value_enum, default_value_t = ResampleQuality::Balanced)]
+    pub quality: ResampleQuality,
+
+    /// Integer bit depth of the output (8, 16, 24 or 32), same as the input by default
+    #[arg(long)]
+    pub bit_depth: Option<u16>,
+
+    /// Add triangular dither before quantizing to 16 bits or less
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub dither: bool,
+
     /// Flag to enable overwriting of input file
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub overwrite: bool,
 }
 
 #[derive(Debug, Parser)]
+pub struct AudioChannelsArgs {
+    /// Input file or directory
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Target channels: a number of channels, mix (average to mono) or pick:K (keep channel K, from 0)
+    #[arg(required = true)]
+    pub channels: ChannelConversion,
+
+    /// Output file or directory
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Flag to enable overwriting of input file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioConvertArgs {
+    /// Input file or directory
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Output file or directory
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Output codec, which also sets the extension of the output files
+    #[arg(long, value_enum, required = true)]
+    pub codec: AudioCodec,
+
+    /// Bits per sample of the output (8, 16, 24 or 32), same as the input by default
+    #[arg(long)]
+    pub bit_depth: Option<u16>,
+
+    /// Sample format of the output, same as the input by default
+    #[arg(long, value_enum)]
+    pub sample_format: Option<AudioSampleFormat>,
+
+    /// Flag to enable overwriting of input file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioSpectrogramArgs {
+    /// Input file or directory
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Output file or directory
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Output format, which also sets the extension of the output files
+    #[arg(long, value_enum, default_value_t = SpectrogramFormat::Png)]
+    pub format: SpectrogramFormat,
+
+    /// Number of samples per FFT frame
+    #[arg(long, default_value_t = 2048)]
+    pub n_fft: usize,
+
+    /// Number of samples between the starts of consecutive frames
+    #[arg(long, default_value_t = 512)]
+    pub hop: usize,
+
+    /// Window applied to every frame
+    #[arg(long, value_enum, default_value_t = SpectrogramWindow::Hann)]
+    pub window: SpectrogramWindow,
+
+    /// Number of mel bands, linear frequency bins if not given
+    #[arg(long)]
+    pub mels: Option<usize>,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioNormalizeArgs {
+    /// Input file or directory
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Output file or directory
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Level to normalize
+    #[arg(long, value_enum, default_value_t = NormalizeMode::Loudness)]
+    pub mode: NormalizeMode,
+
+    /// Target level, in LUFS for loudness (default -23) or dBFS for peak (default -1)
+    #[arg(long, allow_hyphen_values = true)]
+    pub target: Option<f64>,
+
+    /// Maximum true peak in dBTP after normalization
+    #[arg(long, default_value_t = -1.0, allow_hyphen_values = true)]
+    pub true_peak: f64,
+
+    /// Write the levels and the gain applied to every file to a table (CSV or parquet)
+    #[arg(long)]
+    pub report: Option<String>,
+
+    /// Flag to enable overwriting of input file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+#[command(allow_missing_positional = true)]
 pub struct AudioTrimArgs {
     /// Input file or directory
     #[arg(required = true)]
     pub input: String,
 
+    /// Length in seconds of the former `<input> <length> <output>` form (deprecated, use --length)
+    #[arg(
+        hide = true,
+        value_name = "LENGTH",
+        conflicts_with_all = ["start", "end", "duration"]
+    )]
+    pub legacy_length: Option<f32>,
+
+    /// Output file or directory
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Start offset in seconds of the former form (deprecated, use --start)
+    #[arg(
+        long = "offset",
+        hide = true,
+        value_name = "OFFSET",
+        requires = "legacy_length"
+    )]
+    pub legacy_offset: Option<f32>,
+
+    /// Start time, in seconds or [HH:]MM:SS[.fff], negative values counting from the end (0 by default)
+    #[arg(long, allow_hyphen_values = true)]
+    pub start: Option<Timestamp>,
+
+    /// End time, in the same format as the start (end of the file by default)
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "duration")]
+    pub end: Option<Timestamp>,
+
+    /// Length from the start, instead of an end time
+    #[arg(long = "length", value_name = "LENGTH")]
+    pub duration: Option<Timestamp>,
+
+    /// Trim to the available audio instead of failing when the range exceeds the file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub clamp: bool,
+
+    /// Flag to enable overwriting of input file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioCutArgs {
+    /// Root directory of the files listed in the segments table
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Output directory, with one subdirectory per label
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Table (CSV or parquet) with one row per segment and columns file, start, end and optionally label
+    #[arg(long, required = true)]
+    pub segments: String,
+
+    /// Cut to the available audio instead of failing when a segment exceeds its file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub clamp: bool,
+}
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct AudioTagsArgs {
+    #[command(subcommand)]
+    pub command: Option<AudioTagsCommand>,
+
+    /// Target directory or file whose tags are listed
+    #[arg(required = true)]
+    pub target: Option<String>,
+
+    /// Write one row per tag item to a table (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Write unreadable files and their errors to a table (CSV or parquet)
+    #[arg(long)]
+    pub errors: Option<String>,
+
+    /// Fail if any file cannot be read
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AudioTagsCommand {
+    /// Set tags of audio files in place
+    Set(AudioTagsSetArgs),
+    /// Remove tags from audio files in place
+    Strip(AudioTagsStripArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioTagsSetArgs {
+    /// Target directory or file
+    #[arg(required = true)]
+    pub target: String,
+
+    /// Tag to set as KEY=VALUE, an empty value removes the key (can be repeated)
+    #[arg(long = "tag", value_name = "KEY=VALUE", required = true)]
+    pub tags: Vec<TagAssignment>,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioTagsStripArgs {
+    /// Target directory or file
+    #[arg(required = true)]
+    pub target: String,
+
+    /// Only remove this key (can be repeated)
+    #[arg(long = "key", value_name = "KEY")]
+    pub keys: Vec<String>,
+
+    /// Only remove embedded pictures
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub pictures: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioPadArgs {
+    /// Input file or directory
+    #[arg(required = true)]
+    pub input: String,
+
     /// Target length in seconds
     #[arg(required = true)]
     pub length: f32,
@@ -165,20 +1025,117 @@ pub struct AudioTrimArgs {
     #[arg(required = true)]
     pub output: String,
 
-    /// Start offset in seconds
-    #[arg(default_value_t = 0.0)]
-    pub offset: f32,
+    /// Where to add the missing audio
+    #[arg(long, value_enum, default_value_t = PadPosition::End)]
+    pub position: PadPosition,
+
+    /// What to fill the missing audio with
+    #[arg(long, value_enum, default_value_t = PadMode::Silence)]
+    pub mode: PadMode,
+
+    /// Flag to enable overwriting of input file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioFadeArgs {
+    /// Input file or directory
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Output file or directory
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Duration in seconds of the fade in
+    #[arg(long, default_value_t = 0.0)]
+    pub fade_in: f32,
+
+    /// Duration in seconds of the fade out
+    #[arg(long, default_value_t = 0.0)]
+    pub fade_out: f32,
+
+    /// Shape of the fades
+    #[arg(long, value_enum, default_value_t = FadeCurve::Linear)]
+    pub curve: FadeCurve,
 
     /// Flag to enable overwriting of input file
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub overwrite: bool,
 }
 
+#[derive(Debug, Parser)]
+pub struct AudioConcatArgs {
+    /// Input directory, whose files are joined in natural sort order
+    #[arg(required = true)]
+    pub input: String,
+
+    /// Output file
+    #[arg(required = true)]
+    pub output: String,
+
+    /// Duration in seconds of the crossfade between consecutive files
+    #[arg(long, default_value_t = 0.0)]
+    pub crossfade: f32,
+
+    /// Shape of the crossfades
+    #[arg(long, value_enum, default_value_t = FadeCurve::Sine)]
+    pub curve: FadeCurve,
+
+    /// Flag to enable overwriting of an existing output file
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AudioDuplicatesArgs {
+    /// Target audio directory
+    #[arg(required = true)]
+    pub target: String,
+
+    /// Also look into subdirectories
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub recursive: bool,
+
+    /// Minimum similarity (between 0 and 1) of the fingerprints of duplicate recordings
+    #[arg(long, default_value_t = 0.8)]
+    pub threshold: f64,
+
+    /// Write one row per duplicate file to a table (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Write unreadable files and their errors to a table (CSV or parquet)
+    #[arg(long)]
+    pub errors: Option<String>,
+
+    /// Fail if any file cannot be read
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
+}
+
 #[derive(Debug, Parser)]
 pub struct VideoSummaryArgs {
     /// Target directory or file
     #[arg(required = true)]
     pub target: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Write one row per file to a manifest (CSV or parquet)
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Write unreadable files and their errors to a table (CSV or parquet)
+    #[arg(long)]
+    pub errors: Option<String>,
+
+    /// Fail if any file cannot be read
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -209,9 +1166,33 @@ pub struct VideoFromFramesArgs {
 
 #[derive(Debug, Parser)]
 pub struct VideoDuplicatesArgs {
-    /// Target video directory
-    #[arg(required = true)]
-    pub target: String,
+    /// Target video directories, compared with each other
+    #[arg(required = true, num_args = 1..)]
+    pub targets: Vec<String>,
+
+    /// Also look into subdirectories
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub recursive: bool,
+
+    /// Hash used to compare videos
+    #[arg(long, value_enum, default_value_t = ContentHash::Sha256)]
+    pub method: ContentHash,
+
+    /// Video kept out of every group of duplicates
+    #[arg(long, value_enum, default_value_t = KeepPolicy::Oldest)]
+    pub keep: KeepPolicy,
+
+    /// What to do with the other videos of every group
+    #[arg(long, value_enum, default_value_t = DuplicateAction::Report)]
+    pub action: DuplicateAction,
+
+    /// Directory the duplicates are moved to with the move action
+    #[arg(long, required_if_eq("action", "move"))]
+    pub move_to: Option<String>,
+
+    /// Perform the action, which is otherwise only printed
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub apply: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -259,6 +1240,10 @@ pub struct TableSummaryArgs {
     /// Target file
     #[arg(required = true)]
     pub target: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
 }
 
 // Error handling utility that can be used by both lib and binary
@@ -1,10 +1,12 @@
 use clap::{Args, Parser, Subcommand};
 use rush::{
-    AudioResampleArgs, AudioSplitArgs, AudioSummaryArgs, AudioTrimArgs, FileCountArgs,
-    FileExtensionArgs, ImageDuplicatesArgs, ImageResizeArgs, ImageSummaryArgs, ImageTessellateArgs,
-    ImageToLandscapeArgs, ImageToPortraitArgs, TableSchemaArgs, TableSummaryArgs, TableToCsvArgs,
-    TableToParquetArgs, VideoDuplicatesArgs, VideoFromFramesArgs, VideoSummaryArgs,
-    VideoThumbnailArgs, VideoToFramesArgs,
+    AudioChannelsArgs, AudioConcatArgs, AudioConvertArgs, AudioCutArgs, AudioDuplicatesArgs,
+    AudioFadeArgs, AudioNormalizeArgs, AudioPadArgs, AudioResampleArgs, AudioSpectrogramArgs,
+    AudioSplitArgs, AudioSummaryArgs, AudioTagsArgs, AudioTrimArgs, AudioVadArgs, FileCountArgs,
+    FileExtensionArgs, ImageConvertArgs, ImageDuplicatesArgs, ImageResizeArgs, ImageSummaryArgs,
+    ImageTessellateArgs, ImageToLandscapeArgs, ImageToPortraitArgs, TableSchemaArgs,
+    TableSummaryArgs, TableToCsvArgs, TableToParquetArgs, VideoDuplicatesArgs, VideoFromFramesArgs,
+    VideoSummaryArgs, VideoThumbnailArgs, VideoToFramesArgs,
 };
 
 /// Swiss-army knife for media inspection and manipulation
@@ -39,12 +41,34 @@ struct AudioCommand {
 enum AudioSubCommand {
     /// Summary of audio content of file or directory
     Summary(AudioSummaryArgs),
-    /// Split audio file in chunks of fixed length
+    /// Split audio file in chunks of fixed length or at silences
     Split(AudioSplitArgs),
     /// Resample audio file
     Resample(AudioResampleArgs),
-    /// Trim audio file to given length (possibly with initial offset)
+    /// Trim audio file to a time range
     Trim(AudioTrimArgs),
+    /// Cut the segments listed in a table out of audio files, sorted by label
+    Cut(AudioCutArgs),
+    /// Change the number of channels of audio file (downmix, upmix or extract a channel)
+    Channels(AudioChannelsArgs),
+    /// Normalize peak level or loudness of audio file
+    Normalize(AudioNormalizeArgs),
+    /// Convert audio file to another codec, bit depth or sample format
+    Convert(AudioConvertArgs),
+    /// Compute STFT or mel spectrogram of audio file
+    Spectrogram(AudioSpectrogramArgs),
+    /// Detect speech in audio file and measure how much of it there is
+    Vad(AudioVadArgs),
+    /// Pad audio file to a target length with silence or reflection
+    Pad(AudioPadArgs),
+    /// Fade audio file in and out
+    Fade(AudioFadeArgs),
+    /// Join audio files of a directory into a single one
+    Concat(AudioConcatArgs),
+    /// List, set or strip tags of audio file
+    Tags(AudioTagsArgs),
+    /// Find near-duplicate recordings in directory from their acoustic fingerprints
+    Duplicates(AudioDuplicatesArgs),
 }
 
 #[derive(Debug, Args)]
@@ -57,15 +81,17 @@ struct ImageCommand {
 enum ImageSubCommand {
     /// Summary of image content of file or directory
     Summary(ImageSummaryArgs),
-    /// Resize image to a fixed height and width
+    /// Resize image to a height and/or width, or to a maximum side
     Resize(ImageResizeArgs),
+    /// Convert images to another format
+    Convert(ImageConvertArgs),
     /// Divide image into tiles
     Tessellate(ImageTessellateArgs),
     /// Rotate to landscape
     ToLandscape(ImageToLandscapeArgs),
     /// Rotate to portrait
     ToPortrait(ImageToPortraitArgs),
-    /// Find duplicated images
+    /// Find duplicated images across directories, optionally removing them
     Duplicates(ImageDuplicatesArgs),
 }
 
@@ -83,7 +109,7 @@ enum VideoSubCommand {
     ToFrames(VideoToFramesArgs),
     /// Collect frames into a video
     FromFrames(VideoFromFramesArgs),
-    /// Find duplicated video files
+    /// Find duplicated video files across directories, optionally removing them
     Duplicates(VideoDuplicatesArgs),
     /// Create video thumbnail
     Thumbnail(VideoThumbnailArgs),
@@ -130,10 +156,22 @@ fn main() {
             AudioSubCommand::Split(args) => rush::commands::audio::split::execute(args),
             AudioSubCommand::Resample(args) => rush::commands::audio::resample::execute(args),
             AudioSubCommand::Trim(args) => rush::commands::audio::trim::execute(args),
+            AudioSubCommand::Cut(args) => rush::commands::audio::cut::execute(args),
+            AudioSubCommand::Channels(args) => rush::commands::audio::channels::execute(args),
+            AudioSubCommand::Normalize(args) => rush::commands::audio::normalize::execute(args),
+            AudioSubCommand::Convert(args) => rush::commands::audio::convert::execute(args),
+            AudioSubCommand::Spectrogram(args) => rush::commands::audio::spectrogram::execute(args),
+            AudioSubCommand::Vad(args) => rush::commands::audio::vad::execute(args),
+            AudioSubCommand::Pad(args) => rush::commands::audio::pad::execute(args),
+            AudioSubCommand::Fade(args) => rush::commands::audio::fade::execute(args),
+            AudioSubCommand::Concat(args) => rush::commands::audio::concat::execute(args),
+            AudioSubCommand::Tags(args) => rush::commands::audio::tags::execute(args),
+            AudioSubCommand::Duplicates(args) => rush::commands::audio::duplicates::execute(args),
         },
         Command::Image(image_command) => match image_command.command {
             ImageSubCommand::Summary(args) => rush::commands::image::summary::execute(args),
             ImageSubCommand::Resize(args) => rush::commands::image::resize::execute(args),
+            ImageSubCommand::Convert(args) => rush::commands::image::convert::execute(args),
             ImageSubCommand::Tessellate(args) => rush::commands::image::tessellate::execute(args),
             ImageSubCommand::ToLandscape(args) => {
                 rush::commands::image::to_landscape::execute(args)
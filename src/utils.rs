@@ -1,7 +1,17 @@
 use anyhow::{Context, Result};
 use polars::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::OutputFormat;
+
+pub mod audio;
+pub mod duplicates;
+pub mod image_metadata;
 
 // Check if file with given path has one of the desired extensions
 pub fn file_has_right_extension(path: &Path, extensions: &[&str]) -> Result<(), io::Error> {
@@ -56,6 +66,38 @@ pub fn perform_io_sanity_check(
     Ok(())
 }
 
+// Output path of every file of an input directory, mirrored in the output directory and renamed
+//
+// Renaming can send several files to the same output (e.g. `a.mp3` and `a.m4a` both written as
+// `a.wav`), where the last one written would silently win, so that is an error.
+pub fn nested_outputs(
+    files: &[PathBuf],
+    input: &Path,
+    output: &Path,
+    rename: impl Fn(&Path) -> PathBuf,
+) -> Result<Vec<PathBuf>> {
+    let mut sources: HashMap<PathBuf, &Path> = HashMap::new();
+    let mut outputs = Vec::with_capacity(files.len());
+
+    for file in files {
+        // Relative path wrt input directory
+        let relative_path = file
+            .strip_prefix(input)
+            .with_context(|| format!("Failed to strip prefix from path: {:?}", file))?;
+
+        let file_output = rename(&output.join(relative_path));
+        if let Some(other) = sources.insert(file_output.clone(), file) {
+            return Err(anyhow::Error::msg(format!(
+                "{:?} and {:?} would both be written to {:?}",
+                other, file, file_output
+            )));
+        }
+        outputs.push(file_output);
+    }
+
+    Ok(outputs)
+}
+
 // Read table
 pub fn read_table(path: &Path) -> Result<LazyFrame> {
     // Extract extension
@@ -74,3 +116,142 @@ pub fn read_table(path: &Path) -> Result<LazyFrame> {
         _ => Err(anyhow::Error::msg("Unsupported file format")),
     }
 }
+
+// Write table, with the format chosen by the extension
+pub fn write_table(df: &mut DataFrame, path: &Path) -> Result<()> {
+    // Extract extension
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .ok_or_else(|| anyhow::Error::msg("Failed to extract file extension"))?;
+
+    // Ensure the output directory exists
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory: {:?}", parent))?;
+    }
+
+    match extension.as_str() {
+        "parquet" => {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("Failed to create file: {:?}", path))?;
+            ParquetWriter::new(file)
+                .finish(df)
+                .with_context(|| format!("Failed to write parquet file: {:?}", path))?;
+        }
+        "csv" => {
+            let mut file = std::fs::File::create(path)
+                .with_context(|| format!("Failed to create file: {:?}", path))?;
+            CsvWriter::new(&mut file)
+                .finish(df)
+                .with_context(|| format!("Failed to write CSV file: {:?}", path))?;
+        }
+        _ => return Err(anyhow::Error::msg("Unsupported file format")),
+    }
+
+    Ok(())
+}
+
+// Split per-file results into successes and failures
+pub fn partition_results<T>(
+    results: Vec<(PathBuf, Result<T>)>,
+) -> (Vec<T>, Vec<(PathBuf, anyhow::Error)>) {
+    let mut successes = Vec::new();
+    let mut failures = Vec::new();
+
+    for (path, result) in results {
+        match result {
+            Ok(value) => successes.push(value),
+            Err(e) => failures.push((path, e)),
+        }
+    }
+
+    (successes, failures)
+}
+
+// Report files that could not be processed, optionally writing them to a table
+pub fn report_failures(failures: &[(PathBuf, anyhow::Error)], errors: Option<&Path>) -> Result<()> {
+    // Write the failures to file if requested (even if there are none)
+    if let Some(errors) = errors {
+        let mut df = df!(
+            "path" => failures.iter().map(|(path, _)| path.to_string_lossy().to_string()).collect::<Vec<_>>(),
+            "error" => failures.iter().map(|(_, e)| format!("{:#}", e)).collect::<Vec<_>>(),
+        )
+        .with_context(|| "Failed to build error table")?;
+        write_table(&mut df, errors)
+            .with_context(|| format!("Failed to write errors file: {:?}", errors))?;
+    }
+
+    // Print to stderr so that stdout stays machine-readable
+    if !failures.is_empty() {
+        eprintln!("Failed to read {} files:", failures.len());
+        for (path, e) in failures {
+            eprintln!("  {}: {:#}", path.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+// Print a summary in the requested format
+pub fn print_summary<T: Serialize + Display>(summary: &T, format: OutputFormat) -> Result<()> {
+    println!("{}", render_summary(summary, format)?);
+    Ok(())
+}
+
+// Render a summary to a string in the requested format
+pub fn render_summary<T: Serialize + Display>(summary: &T, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Text => Ok(summary.to_string().trim_end().to_string()),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(summary).with_context(|| "Failed to serialize summary")
+        }
+        OutputFormat::Csv => {
+            // Flatten the top-level fields into a single row
+            let value =
+                serde_json::to_value(summary).with_context(|| "Failed to serialize summary")?;
+            let fields = value
+                .as_object()
+                .ok_or_else(|| anyhow::Error::msg("Summary is not a structure"))?;
+
+            let header: Vec<String> = fields.keys().map(|key| csv_escape(key)).collect();
+            let row: Vec<String> = fields
+                .values()
+                .map(|value| csv_escape(&csv_cell(value)))
+                .collect();
+
+            Ok(format!("{}\n{}", header.join(","), row.join(",")))
+        }
+    }
+}
+
+// Turn a JSON value into the content of a CSV cell
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(|v| match v {
+                Value::Null => String::new(),
+                Value::String(s) => s.clone(),
+                _ => v.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(";"),
+        _ => value.to_string(),
+    }
+}
+
+// Quote a CSV cell if needed
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,301 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+use crate::FadeCurve;
+
+mod ffmpeg;
+pub mod loudness;
+
+use self::ffmpeg::{FfmpegReader, FfmpegWriter};
+
+// Number of frames read or written at a time when streaming audio
+pub const BLOCK_FRAMES: usize = 65536;
+
+// Extensions that can be decoded
+pub const READABLE_EXTENSIONS: [&str; 7] = ["wav", "flac", "mp3", "ogg", "m4a", "aac", "opus"];
+
+// Extensions that can be encoded while keeping every sample format
+pub const WRITABLE_EXTENSIONS: [&str; 2] = ["wav", "flac"];
+
+// Extensions that can be encoded, including lossy formats
+pub const ENCODABLE_EXTENSIONS: [&str; 4] = ["wav", "flac", "mp3", "opus"];
+
+// Lowercase extension of a path
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+// Keep the extension of a path if it can be written, fall back to WAV otherwise
+pub fn with_writable_extension(path: &Path) -> PathBuf {
+    match extension(path) {
+        Some(ext) if WRITABLE_EXTENSIONS.contains(&ext.as_str()) => path.to_path_buf(),
+        _ => path.with_extension("wav"),
+    }
+}
+
+// Temporary file next to a path with the same extension, to be persisted once fully written
+pub fn temp_output(path: &Path) -> Result<NamedTempFile> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let suffix = match path.extension() {
+        Some(ext) => format!(".{}", ext.to_string_lossy()),
+        None => String::new(),
+    };
+
+    tempfile::Builder::new()
+        .prefix(".rush-")
+        .suffix(&suffix)
+        .tempfile_in(directory)
+        .with_context(|| format!("Failed to create temporary file in {:?}", directory))
+}
+
+// Magnitude of the most negative integer sample for a bit depth
+fn int_max_value(bits_per_sample: u16) -> f64 {
+    2_f64.powi(bits_per_sample as i32 - 1)
+}
+
+// Scale a normalised sample to an integer one, clipping it to the range of the bit depth
+fn quantize(sample: f64, max_value: f64) -> f64 {
+    (sample * max_value)
+        .round()
+        .clamp(-max_value, max_value - 1.0)
+}
+
+// Range in dB covered by exponential and logarithmic fades
+const FADE_RANGE_DB: f64 = 60.0;
+
+// Gain of a fade in at a given progress in [0, 1], fades out use 1 - progress
+pub fn fade_gain(curve: FadeCurve, progress: f64) -> f64 {
+    let x = progress.clamp(0.0, 1.0);
+    let floor = 10_f64.powf(-FADE_RANGE_DB / 20.0);
+
+    match curve {
+        FadeCurve::Linear => x,
+        FadeCurve::Sine => (x * std::f64::consts::FRAC_PI_2).sin(),
+        FadeCurve::Exponential => match x > 0.0 {
+            true => floor.powf(1.0 - x),
+            false => 0.0,
+        },
+        FadeCurve::Logarithmic => (1.0 - floor.powf(x)) / (1.0 - floor),
+    }
+}
+
+// Triangular dither of one least significant bit, added to samples before quantizing them
+//
+// The noise comes from a fixed-seed generator, so that the same input always gives the same output.
+pub struct Dither {
+    state: u64,
+    lsb: f64,
+}
+
+impl Dither {
+    pub fn new(bits_per_sample: u16) -> Self {
+        Self {
+            state: 0x9E37_79B9_7F4A_7C15,
+            lsb: 1.0 / int_max_value(bits_per_sample),
+        }
+    }
+
+    // Uniform number in [0, 1), from a xorshift64* generator
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let value = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (value >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    pub fn apply(&mut self, samples: &mut [f64]) {
+        for sample in samples {
+            // The difference of two uniform numbers has a triangular distribution
+            *sample += (self.uniform() - self.uniform()) * self.lsb;
+        }
+    }
+}
+
+/// Audio decoder yielding interleaved samples normalised to [-1, 1]
+pub enum AudioReader {
+    Wav(WavReader<BufReader<File>>),
+    Ffmpeg(Box<FfmpegReader>),
+}
+
+impl AudioReader {
+    /// Open a file, picking the decoder from its extension
+    pub fn open(path: &Path) -> Result<Self> {
+        match extension(path).as_deref() {
+            Some("wav") => Ok(Self::Wav(
+                WavReader::open(path).with_context(|| "Failed to open WavReader")?,
+            )),
+            Some(ext) if READABLE_EXTENSIONS.contains(&ext) => Ok(Self::Ffmpeg(Box::new(
+                FfmpegReader::open(path).with_context(|| "Failed to open FFmpeg decoder")?,
+            ))),
+            _ => Err(anyhow::Error::msg("Unsupported audio format")),
+        }
+    }
+
+    /// Specs of the decoded stream
+    pub fn spec(&self) -> WavSpec {
+        match self {
+            Self::Wav(reader) => reader.spec(),
+            Self::Ffmpeg(reader) => reader.spec(),
+        }
+    }
+
+    /// Read up to `n` interleaved samples, returning an empty vec at the end of the stream
+    pub fn read_samples(&mut self, n: usize) -> Result<Vec<f64>> {
+        match self {
+            Self::Wav(reader) => {
+                let spec = reader.spec();
+                match spec.sample_format {
+                    // Float samples are already normalised
+                    SampleFormat::Float => reader
+                        .samples::<f32>()
+                        .take(n)
+                        .map(|s| s.map(|s| s as f64).with_context(|| "Couldn't read samples"))
+                        .collect(),
+                    SampleFormat::Int => {
+                        let max_value = int_max_value(spec.bits_per_sample);
+                        reader
+                            .samples::<i32>()
+                            .take(n)
+                            .map(|s| {
+                                s.map(|s| s as f64 / max_value)
+                                    .with_context(|| "Couldn't read samples")
+                            })
+                            .collect()
+                    }
+                }
+            }
+            Self::Ffmpeg(reader) => reader.read_samples(n),
+        }
+    }
+
+    /// Skip up to `n` frames, returning how many were actually skipped
+    pub fn skip_frames(&mut self, n: usize) -> Result<usize> {
+        let channels = self.spec().channels as usize;
+
+        match self {
+            Self::Wav(reader) => {
+                // Seek directly, without going past the end of the file
+                let remaining = reader.samples::<i32>().len() / channels;
+                let position = reader.duration() as usize - remaining;
+                let skipped = usize::min(n, remaining);
+                reader
+                    .seek((position + skipped) as u32)
+                    .with_context(|| "Failed to seek")?;
+                Ok(skipped)
+            }
+            Self::Ffmpeg(reader) => {
+                // Compressed streams can't be seeked exactly, decode and discard instead
+                let mut skipped = 0;
+                while skipped < n {
+                    let block =
+                        reader.read_samples(usize::min(n - skipped, BLOCK_FRAMES) * channels)?;
+                    if block.is_empty() {
+                        break;
+                    }
+                    skipped += block.len() / channels;
+                }
+                Ok(skipped)
+            }
+        }
+    }
+
+    /// Read all the remaining interleaved samples
+    pub fn read_all(&mut self) -> Result<Vec<f64>> {
+        self.read_samples(usize::MAX)
+    }
+}
+
+/// Audio encoder accepting interleaved samples normalised to [-1, 1]
+pub enum AudioWriter {
+    Wav(WavWriter<BufWriter<File>>),
+    Ffmpeg(Box<FfmpegWriter>),
+}
+
+impl AudioWriter {
+    /// Create a file, picking the encoder from its extension
+    pub fn create(path: &Path, spec: WavSpec) -> Result<Self> {
+        // FLAC only stores integers, and FFmpeg only encodes them on 16 or 24 bits
+        if extension(path).as_deref() == Some("flac")
+            && (spec.sample_format == SampleFormat::Float
+                || ![16, 24].contains(&spec.bits_per_sample))
+        {
+            return Err(anyhow::Error::msg(format!(
+                "FLAC only supports 16 or 24-bit integer samples, got {} bits {}",
+                spec.bits_per_sample,
+                match spec.sample_format {
+                    SampleFormat::Float => "float",
+                    SampleFormat::Int => "integer",
+                }
+            )));
+        }
+
+        match extension(path).as_deref() {
+            Some("wav") => Ok(Self::Wav(
+                WavWriter::create(path, spec)
+                    .with_context(|| format!("Couldn't write to {:?}", path))?,
+            )),
+            Some(ext) if ENCODABLE_EXTENSIONS.contains(&ext) => Ok(Self::Ffmpeg(Box::new(
+                FfmpegWriter::create(path, spec)
+                    .with_context(|| format!("Couldn't write to {:?}", path))?,
+            ))),
+            _ => Err(anyhow::Error::msg(format!(
+                "Unsupported output audio format: {:?}",
+                path
+            ))),
+        }
+    }
+
+    /// Write interleaved samples
+    pub fn write_samples(&mut self, samples: &[f64]) -> Result<()> {
+        match self {
+            Self::Wav(writer) => {
+                let spec = writer.spec();
+                let max_value = int_max_value(spec.bits_per_sample);
+
+                for &sample in samples {
+                    match (spec.sample_format, spec.bits_per_sample) {
+                        (SampleFormat::Float, 32) => writer.write_sample(sample as f32)?,
+                        (SampleFormat::Int, 8) => {
+                            writer.write_sample(quantize(sample, max_value) as i8)?
+                        }
+                        (SampleFormat::Int, 16) => {
+                            writer.write_sample(quantize(sample, max_value) as i16)?
+                        }
+                        (SampleFormat::Int, 24 | 32) => {
+                            writer.write_sample(quantize(sample, max_value) as i32)?
+                        }
+                        _ => {
+                            return Err(anyhow::Error::msg(format!(
+                                "Unsupported sample format: {:?} {} bits",
+                                spec.sample_format, spec.bits_per_sample
+                            )))
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            Self::Ffmpeg(writer) => writer.write_samples(samples),
+        }
+    }
+
+    /// Flush and close the file
+    pub fn finalize(self) -> Result<()> {
+        match self {
+            Self::Wav(writer) => writer
+                .finalize()
+                .with_context(|| "Failed to finalize WAV file"),
+            Self::Ffmpeg(writer) => writer.finalize(),
+        }
+    }
+}
@@ -0,0 +1,531 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use anyhow::{Context, Result};
+use ffmpeg::format::sample::Type as SampleType;
+use ffmpeg::format::Sample;
+use ffmpeg::software::resampling;
+use ffmpeg::{codec, frame, media, ChannelLayout, Packet, Rational};
+use hound::{SampleFormat, WavSpec};
+use std::collections::VecDeque;
+use std::path::Path;
+
+// Number of samples per channel sent to encoders that accept any frame size
+const DEFAULT_FRAME_SIZE: usize = 4096;
+
+// Codecs whose encoders may be given a sample format other than the requested one
+const LOSSY_CODECS: [codec::Id; 3] = [codec::Id::MP3, codec::Id::OPUS, codec::Id::AAC];
+
+// Channel layout of a stream, falling back to the default one for its number of channels
+fn layout_or_default(layout: ChannelLayout, channels: u16) -> ChannelLayout {
+    if layout.is_empty() {
+        ChannelLayout::default(channels as i32)
+    } else {
+        layout
+    }
+}
+
+/// Decoder for any format FFmpeg can demux, converting frames to planar f64
+pub struct FfmpegReader {
+    input: ffmpeg::format::context::Input,
+    decoder: codec::decoder::Audio,
+    resampler: resampling::Context,
+    stream_index: usize,
+    layout: ChannelLayout,
+    spec: WavSpec,
+    pending: VecDeque<f64>,
+    finished: bool,
+}
+
+impl FfmpegReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        ffmpeg::init().with_context(|| "Failed to init FFmpeg")?;
+
+        // Read context
+        let input = ffmpeg::format::input(&path).with_context(|| "Couldn't read audio")?;
+
+        // Only select the best audio stream, throw away video, subtitles etc
+        let stream = input
+            .streams()
+            .best(media::Type::Audio)
+            .with_context(|| "No audio stream found in file")?;
+        let stream_index = stream.index();
+
+        // Create decoder
+        let context = codec::context::Context::from_parameters(stream.parameters())
+            .with_context(|| "Failed to create decoder context from audio stream parameters")?;
+        let decoder = context
+            .decoder()
+            .audio()
+            .with_context(|| "Failed to create audio decoder from decoder context")?;
+
+        let layout = layout_or_default(decoder.channel_layout(), decoder.channels());
+
+        // Lossless codecs report the actual bit depth, lossy ones decode to float
+        //
+        // SAFETY: the decoder owns a valid codec context for as long as it lives, and the
+        // field is a plain integer that FFmpeg sets when opening it.
+        let bits_per_raw_sample = unsafe { (*decoder.as_ptr()).bits_per_raw_sample } as u16;
+        let (bits_per_sample, sample_format) = match decoder.format() {
+            Sample::U8(_) => (8, SampleFormat::Int),
+            Sample::I16(_) => (16, SampleFormat::Int),
+            Sample::I32(_) | Sample::I64(_) => match bits_per_raw_sample {
+                24 => (24, SampleFormat::Int),
+                _ => (32, SampleFormat::Int),
+            },
+            _ => (32, SampleFormat::Float),
+        };
+
+        let spec = WavSpec {
+            channels: decoder.channels(),
+            sample_rate: decoder.rate(),
+            bits_per_sample,
+            sample_format,
+        };
+
+        // Convert whatever the decoder outputs to planar f64
+        let resampler = resampling::Context::get(
+            decoder.format(),
+            layout,
+            decoder.rate(),
+            Sample::F64(SampleType::Planar),
+            layout,
+            decoder.rate(),
+        )
+        .with_context(|| "Failed to create sample format converter")?;
+
+        Ok(Self {
+            input,
+            decoder,
+            resampler,
+            stream_index,
+            layout,
+            spec,
+            pending: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    pub fn read_samples(&mut self, n: usize) -> Result<Vec<f64>> {
+        // Decode until enough samples are available or the stream is over
+        while self.pending.len() < n && self.decode_frame()? {}
+
+        let available = usize::min(n, self.pending.len());
+        Ok(self.pending.drain(..available).collect())
+    }
+
+    // Decode one more frame into the pending samples, returning false at the end of the stream
+    fn decode_frame(&mut self) -> Result<bool> {
+        loop {
+            // Drain the decoder first
+            let mut decoded = frame::Audio::empty();
+            if self.decoder.receive_frame(&mut decoded).is_ok() {
+                self.push_frame(&mut decoded)?;
+                return Ok(true);
+            }
+
+            if self.finished {
+                return Ok(false);
+            }
+
+            // Feed the decoder with the next packet of the audio stream
+            let mut packet = Packet::empty();
+            match packet.read(&mut self.input) {
+                Ok(()) => {
+                    if packet.stream() == self.stream_index {
+                        self.decoder
+                            .send_packet(&packet)
+                            .with_context(|| "Failed to decode packet")?;
+                    }
+                }
+                Err(ffmpeg::Error::Eof) => {
+                    self.decoder
+                        .send_eof()
+                        .with_context(|| "Failed to flush decoder")?;
+                    self.finished = true;
+                }
+                Err(e) => return Err(e).with_context(|| "Failed to read packet"),
+            }
+        }
+    }
+
+    // Convert a decoded frame and append its samples interleaved
+    fn push_frame(&mut self, decoded: &mut frame::Audio) -> Result<()> {
+        if decoded.channel_layout().is_empty() {
+            decoded.set_channel_layout(self.layout);
+        }
+
+        let mut converted = frame::Audio::empty();
+        self.resampler
+            .run(decoded, &mut converted)
+            .with_context(|| "Failed to convert decoded samples")?;
+
+        let planes: Vec<&[f64]> = (0..self.spec.channels as usize)
+            .map(|channel| converted.plane::<f64>(channel))
+            .collect();
+
+        for i in 0..converted.samples() {
+            for plane in &planes {
+                self.pending.push_back(plane[i]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Encoder for any format FFmpeg can mux, chosen from the output extension
+pub struct FfmpegWriter {
+    output: ffmpeg::format::context::Output,
+    encoder: codec::encoder::audio::Encoder,
+    resampler: resampling::Context,
+    rate_converter: Option<resampling::Context>,
+    layout: ChannelLayout,
+    spec: WavSpec,
+    rate: u32,
+    frame_size: usize,
+    time_base: Rational,
+    stream_time_base: Rational,
+    pending: Vec<f64>,
+    pts: i64,
+}
+
+impl FfmpegWriter {
+    pub fn create(path: &Path, spec: WavSpec) -> Result<Self> {
+        ffmpeg::init().with_context(|| "Failed to init FFmpeg")?;
+
+        // Output context, with the container guessed from the extension
+        let mut output =
+            ffmpeg::format::output(&path).with_context(|| "Failed to create output context")?;
+
+        // Find the default encoder of the container
+        let codec = ffmpeg::encoder::find(output.format().codec(&path, media::Type::Audio))
+            .with_context(|| "No encoder found for output format")?
+            .audio()
+            .with_context(|| "Encoder is not an audio encoder")?;
+
+        // Pick the supported sample format closest to the requested specs
+        let candidates = match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Float, _) => [
+                Sample::F32(SampleType::Packed),
+                Sample::F32(SampleType::Planar),
+            ],
+            (SampleFormat::Int, 8) => [
+                Sample::U8(SampleType::Packed),
+                Sample::U8(SampleType::Planar),
+            ],
+            (SampleFormat::Int, 16) => [
+                Sample::I16(SampleType::Packed),
+                Sample::I16(SampleType::Planar),
+            ],
+            (SampleFormat::Int, _) => [
+                Sample::I32(SampleType::Packed),
+                Sample::I32(SampleType::Planar),
+            ],
+        };
+        let supported: Vec<Sample> = codec
+            .formats()
+            .map(|formats| formats.collect())
+            .unwrap_or_default();
+        let sample_format = match candidates
+            .into_iter()
+            .find(|candidate| supported.contains(candidate))
+        {
+            Some(sample_format) => sample_format,
+            // Lossy encoders discard precision anyway, so any format they take will do
+            None if LOSSY_CODECS.contains(&codec.id()) => supported
+                .first()
+                .copied()
+                .with_context(|| "Encoder does not support any sample format")?,
+            // Lossless ones must not quietly lose bit depth or precision
+            None => {
+                return Err(anyhow::Error::msg(format!(
+                    "The {} encoder does not support {}-bit {} samples",
+                    codec.name(),
+                    spec.bits_per_sample,
+                    match spec.sample_format {
+                        SampleFormat::Float => "float",
+                        SampleFormat::Int => "integer",
+                    }
+                )))
+            }
+        };
+
+        // Some encoders only accept a few sample rates, pick the closest one above if needed
+        let rates: Vec<u32> = codec
+            .rates()
+            .map(|rates| rates.map(|rate| rate as u32).collect())
+            .unwrap_or_default();
+        let rate = match rates.contains(&spec.sample_rate) || rates.is_empty() {
+            true => spec.sample_rate,
+            false => rates
+                .iter()
+                .copied()
+                .filter(|&rate| rate >= spec.sample_rate)
+                .min()
+                .or_else(|| rates.iter().copied().max())
+                .with_context(|| "Encoder does not support any sample rate")?,
+        };
+
+        let layout = ChannelLayout::default(spec.channels as i32);
+        let time_base = Rational::new(1, rate as i32);
+        let global_header = output
+            .format()
+            .flags()
+            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
+
+        // Configure and open the encoder
+        let encoder = {
+            let mut stream = output
+                .add_stream(codec)
+                .with_context(|| "Failed to add audio stream")?;
+            let context = codec::context::Context::from_parameters(stream.parameters())
+                .with_context(|| "Failed to create encoder context")?;
+            let mut encoder = context
+                .encoder()
+                .audio()
+                .with_context(|| "Failed to create audio encoder")?;
+
+            if global_header {
+                encoder.set_flags(codec::flag::Flags::GLOBAL_HEADER);
+            }
+            encoder.set_rate(rate as i32);
+            encoder.set_channel_layout(layout);
+            encoder.set_channels(spec.channels as i32);
+            encoder.set_format(sample_format);
+            encoder.set_time_base(time_base);
+
+            // The native Opus encoder, used without libopus, is flagged as experimental
+            if codec.id() == codec::Id::OPUS {
+                encoder.compliance(codec::Compliance::Experimental);
+            }
+
+            // Let lossless encoders know the actual bit depth
+            if spec.sample_format == SampleFormat::Int {
+                // SAFETY: the encoder owns a valid codec context for as long as it lives, and
+                // the field is a plain integer read by FFmpeg only once the encoder is opened.
+                unsafe {
+                    (*encoder.as_mut_ptr()).bits_per_raw_sample = spec.bits_per_sample as i32;
+                }
+            }
+
+            stream.set_time_base(time_base);
+
+            let encoder = encoder
+                .open_as(codec)
+                .with_context(|| "Failed to open audio encoder")?;
+            stream.set_parameters(&encoder);
+
+            encoder
+        };
+
+        output
+            .write_header()
+            .with_context(|| "Failed to write header")?;
+
+        // The muxer is free to change the time base when writing the header
+        let stream_time_base = output
+            .stream(0)
+            .with_context(|| "Missing output stream")?
+            .time_base();
+
+        // Convert planar f64 to whatever the encoder accepts
+        let resampler = resampling::Context::get(
+            Sample::F64(SampleType::Planar),
+            layout,
+            rate,
+            sample_format,
+            layout,
+            rate,
+        )
+        .with_context(|| "Failed to create sample format converter")?;
+
+        // Convert the sample rate beforehand if the encoder does not support the original one
+        let rate_converter = match rate == spec.sample_rate {
+            true => None,
+            false => Some(
+                resampling::Context::get(
+                    Sample::F64(SampleType::Planar),
+                    layout,
+                    spec.sample_rate,
+                    Sample::F64(SampleType::Planar),
+                    layout,
+                    rate,
+                )
+                .with_context(|| "Failed to create sample rate converter")?,
+            ),
+        };
+
+        let frame_size = match encoder.frame_size() {
+            0 => DEFAULT_FRAME_SIZE,
+            n => n as usize,
+        };
+
+        Ok(Self {
+            output,
+            encoder,
+            resampler,
+            rate_converter,
+            layout,
+            spec,
+            rate,
+            frame_size,
+            time_base,
+            stream_time_base,
+            pending: Vec::new(),
+            pts: 0,
+        })
+    }
+
+    pub fn write_samples(&mut self, samples: &[f64]) -> Result<()> {
+        match self.rate_converter.is_some() {
+            true => {
+                let converted = self.convert_rate(Some(samples))?;
+                self.pending.extend_from_slice(&converted);
+            }
+            false => self.pending.extend_from_slice(samples),
+        }
+
+        // Encode as many full frames as possible
+        let frame_samples = self.frame_size * self.spec.channels as usize;
+        while self.pending.len() >= frame_samples {
+            let samples: Vec<f64> = self.pending.drain(..frame_samples).collect();
+            self.encode(&samples)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finalize(mut self) -> Result<()> {
+        // Flush the samples held by the sample rate converter
+        if self.rate_converter.is_some() {
+            loop {
+                let converted = self.convert_rate(None)?;
+                if converted.is_empty() {
+                    break;
+                }
+                self.pending.extend_from_slice(&converted);
+            }
+        }
+
+        // Encode the last, possibly shorter, frame
+        if !self.pending.is_empty() {
+            let samples = std::mem::take(&mut self.pending);
+            self.encode(&samples)?;
+        }
+
+        // Flush the encoder
+        self.encoder
+            .send_eof()
+            .with_context(|| "Failed to flush encoder")?;
+        self.write_packets()?;
+
+        self.output
+            .write_trailer()
+            .with_context(|| "Failed to write trailer")?;
+
+        Ok(())
+    }
+
+    // Convert interleaved samples to the encoder sample rate, flushing the converter if there are none
+    fn convert_rate(&mut self, samples: Option<&[f64]>) -> Result<Vec<f64>> {
+        let channels = self.spec.channels as usize;
+        let converter = self
+            .rate_converter
+            .as_mut()
+            .with_context(|| "Missing sample rate converter")?;
+
+        // Leave room for all the converted samples, plus what the converter was holding
+        let n = samples.map_or(0, |samples| samples.len() / channels);
+        let capacity = n as u64 * self.rate as u64 / self.spec.sample_rate as u64 + 1024;
+        let mut converted = frame::Audio::new(
+            Sample::F64(SampleType::Planar),
+            capacity as usize,
+            self.layout,
+        );
+        converted.set_channels(self.spec.channels);
+
+        match samples {
+            Some(samples) => {
+                let mut frame = frame::Audio::new(Sample::F64(SampleType::Planar), n, self.layout);
+                frame.set_channels(self.spec.channels);
+                frame.set_rate(self.spec.sample_rate);
+                for channel in 0..channels {
+                    let plane = frame.plane_mut::<f64>(channel);
+                    for (i, sample) in plane.iter_mut().enumerate() {
+                        *sample = samples[i * channels + channel];
+                    }
+                }
+
+                converter
+                    .run(&frame, &mut converted)
+                    .with_context(|| "Failed to convert sample rate")?;
+            }
+            None => {
+                converter
+                    .flush(&mut converted)
+                    .with_context(|| "Failed to flush sample rate converter")?;
+            }
+        }
+
+        // Interleave the converted samples
+        let planes: Vec<&[f64]> = (0..channels)
+            .map(|channel| converted.plane::<f64>(channel))
+            .collect();
+        let mut interleaved = Vec::with_capacity(converted.samples() * channels);
+        for i in 0..converted.samples() {
+            for plane in &planes {
+                interleaved.push(plane[i]);
+            }
+        }
+
+        Ok(interleaved)
+    }
+
+    // Encode interleaved samples as a single frame
+    fn encode(&mut self, samples: &[f64]) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        let n = samples.len() / channels;
+
+        // Fill a planar f64 frame
+        let mut frame = frame::Audio::new(Sample::F64(SampleType::Planar), n, self.layout);
+        frame.set_channels(self.spec.channels);
+        frame.set_rate(self.rate);
+        for channel in 0..channels {
+            let plane = frame.plane_mut::<f64>(channel);
+            for (i, sample) in plane.iter_mut().enumerate() {
+                *sample = samples[i * channels + channel];
+            }
+        }
+
+        // Convert to the encoder sample format
+        let mut converted = frame::Audio::empty();
+        self.resampler
+            .run(&frame, &mut converted)
+            .with_context(|| "Failed to convert samples for encoding")?;
+        converted.set_pts(Some(self.pts));
+        self.pts += n as i64;
+
+        self.encoder
+            .send_frame(&converted)
+            .with_context(|| "Failed to encode frame")?;
+        self.write_packets()
+    }
+
+    // Write all the packets the encoder has ready
+    fn write_packets(&mut self) -> Result<()> {
+        let mut packet = Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts(self.time_base, self.stream_time_base);
+            packet
+                .write_interleaved(&mut self.output)
+                .with_context(|| "Failed to write packet")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use hound::WavSpec;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// Duration in seconds of the steps between overlapping gating blocks
+const STEP_SECONDS: f64 = 0.1;
+
+// Number of steps making up a 400 ms gating block
+const STEPS_PER_BLOCK: usize = 4;
+
+// Gates of the integrated loudness, in LUFS and in LU below the ungated loudness
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+// Oversampling factor and taps per phase of the true peak interpolator
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+// Second order IIR filter in direct form I
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[1] * self.y[0]
+            - self.a[2] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+// K-weighting of ITU-R BS.1770 at any sample rate: a high shelf followed by a high pass
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    // High shelf modelling the acoustic effect of the head
+    let f0 = 1681.974450955533;
+    let gain = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10_f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    // High pass removing the lowest frequencies
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, high_pass]
+}
+
+// Windowed sinc interpolator, split into one filter per oversampling phase
+fn interpolator() -> Vec<[f64; TAPS_PER_PHASE]> {
+    let length = OVERSAMPLING * TAPS_PER_PHASE;
+    let center = (length - 1) as f64 / 2.0;
+
+    (0..OVERSAMPLING)
+        .map(|phase| {
+            let mut taps = [0.0; TAPS_PER_PHASE];
+            for (k, tap) in taps.iter_mut().enumerate() {
+                let n = (k * OVERSAMPLING + phase) as f64;
+                let t = (n - center) / OVERSAMPLING as f64;
+                let sinc = if t == 0.0 {
+                    1.0
+                } else {
+                    (PI * t).sin() / (PI * t)
+                };
+                let window = 0.5 - 0.5 * (2.0 * PI * (n + 0.5) / length as f64).cos();
+                *tap = sinc * window;
+            }
+
+            // Unit gain at DC for every phase
+            let sum: f64 = taps.iter().sum();
+            taps.iter_mut().for_each(|tap| *tap /= sum);
+            taps
+        })
+        .collect()
+}
+
+// Weight of each channel in the loudness sum, with the LFE of 5.1 ignored and surrounds boosted
+fn channel_weights(channels: usize) -> Vec<f64> {
+    match channels {
+        6 => vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41],
+        n => vec![1.0; n],
+    }
+}
+
+// Convert a linear amplitude to decibels
+fn amplitude_to_db(amplitude: f64) -> f64 {
+    20.0 * amplitude.log10()
+}
+
+// Convert a mean square to LUFS
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Level meter following ITU-R BS.1770 and EBU R128, fed with interleaved samples
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    weights: Vec<f64>,
+    interpolator: Vec<[f64; TAPS_PER_PHASE]>,
+    history: Vec<VecDeque<f64>>,
+    step_frames: usize,
+    step_energy: f64,
+    step_count: usize,
+    recent_steps: VecDeque<f64>,
+    blocks: Vec<f64>,
+    frames: u64,
+    sum_squares: f64,
+    sample_peak: f64,
+    true_peak: f64,
+}
+
+impl LoudnessMeter {
+    pub fn new(spec: WavSpec) -> Self {
+        let channels = spec.channels as usize;
+        let sample_rate = spec.sample_rate as f64;
+
+        Self {
+            channels,
+            filters: vec![k_weighting(sample_rate); channels],
+            weights: channel_weights(channels),
+            interpolator: interpolator(),
+            history: vec![VecDeque::from(vec![0.0; TAPS_PER_PHASE]); channels],
+            step_frames: usize::max((sample_rate * STEP_SECONDS).round() as usize, 1),
+            step_energy: 0.0,
+            step_count: 0,
+            recent_steps: VecDeque::with_capacity(STEPS_PER_BLOCK),
+            blocks: Vec::new(),
+            frames: 0,
+            sum_squares: 0.0,
+            sample_peak: 0.0,
+            true_peak: 0.0,
+        }
+    }
+
+    /// Measure interleaved samples
+    pub fn push(&mut self, samples: &[f64]) {
+        for frame in samples.chunks_exact(self.channels) {
+            let mut energy = 0.0;
+
+            for (channel, &sample) in frame.iter().enumerate() {
+                // Sample peak and plain RMS
+                self.sample_peak = f64::max(self.sample_peak, sample.abs());
+                self.sum_squares += sample * sample;
+
+                // True peak, on the oversampled signal
+                let history = &mut self.history[channel];
+                history.pop_back();
+                history.push_front(sample);
+                for taps in &self.interpolator {
+                    let interpolated: f64 =
+                        taps.iter().zip(history.iter()).map(|(t, x)| t * x).sum();
+                    self.true_peak = f64::max(self.true_peak, interpolated.abs());
+                }
+
+                // Loudness, on the K-weighted signal
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let weighted = high_pass.process(shelf.process(sample));
+                energy += self.weights[channel] * weighted * weighted;
+            }
+
+            self.frames += 1;
+            self.step_energy += energy;
+            self.step_count += 1;
+
+            // Every completed step closes a new overlapping gating block
+            if self.step_count == self.step_frames {
+                if self.recent_steps.len() == STEPS_PER_BLOCK {
+                    self.recent_steps.pop_front();
+                }
+                self.recent_steps.push_back(self.step_energy);
+
+                if self.recent_steps.len() == STEPS_PER_BLOCK {
+                    let block_frames = (STEPS_PER_BLOCK * self.step_frames) as f64;
+                    self.blocks
+                        .push(self.recent_steps.iter().sum::<f64>() / block_frames);
+                }
+
+                self.step_energy = 0.0;
+                self.step_count = 0;
+            }
+        }
+    }
+
+    /// Number of frames measured
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Highest absolute sample in dBFS
+    pub fn sample_peak(&self) -> f64 {
+        amplitude_to_db(self.sample_peak)
+    }
+
+    /// Highest absolute value of the 4x oversampled signal in dBTP
+    pub fn true_peak(&self) -> f64 {
+        amplitude_to_db(f64::max(self.true_peak, self.sample_peak))
+    }
+
+    /// Root mean square of all the samples in dBFS
+    pub fn rms(&self) -> f64 {
+        let samples = self.frames as f64 * self.channels as f64;
+        10.0 * (self.sum_squares / samples).log10()
+    }
+
+    /// Gated integrated loudness in LUFS, negative infinity if nothing passes the gates
+    pub fn integrated_loudness(&self) -> f64 {
+        // Absolute gate
+        let loud: Vec<f64> = self
+            .blocks
+            .iter()
+            .copied()
+            .filter(|&energy| energy_to_lufs(energy) > ABSOLUTE_GATE)
+            .collect();
+        if loud.is_empty() {
+            return f64::NEG_INFINITY;
+        }
+
+        // Relative gate, below the loudness of the blocks passing the absolute gate
+        let relative_gate =
+            energy_to_lufs(loud.iter().sum::<f64>() / loud.len() as f64) + RELATIVE_GATE;
+        let gated: Vec<f64> = loud
+            .into_iter()
+            .filter(|&energy| energy_to_lufs(energy) > relative_gate)
+            .collect();
+
+        energy_to_lufs(gated.iter().sum::<f64>() / gated.len() as f64)
+    }
+}
@@ -0,0 +1,388 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::utils::file_has_right_extension;
+use crate::{ContentHash, DuplicateAction, KeepPolicy};
+
+// Bytes hashed at the start and at the end of files before hashing them entirely
+const PARTIAL_BYTES: u64 = 64 * 1024;
+
+// Group of duplicate files, split into the one kept and the others
+#[derive(Debug)]
+pub struct DuplicateGroup {
+    pub kept: PathBuf,
+    pub duplicates: Vec<PathBuf>,
+}
+
+// Admissible files of all the targets, sorted and each listed once
+pub fn find_files(
+    targets: &[PathBuf],
+    recursive: bool,
+    extensions: &[&str],
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for target in targets {
+        // Error if it does not exist at all
+        if !target.exists() {
+            return Err(anyhow::Error::msg(format!(
+                "Target directory does not exist: {:?}",
+                target
+            )));
+        }
+
+        // Error if target is not a directory
+        if !target.is_dir() {
+            return Err(anyhow::Error::msg(format!(
+                "Target must be a directory: {:?}",
+                target
+            )));
+        }
+
+        files.extend(
+            WalkDir::new(target)
+                .max_depth(if recursive { usize::MAX } else { 1 })
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter(|e| file_has_right_extension(e.path(), extensions).is_ok())
+                .map(|e| e.path().to_path_buf()),
+        );
+    }
+    files.sort();
+
+    // Overlapping targets can reach a file through several paths, which must not be
+    // taken for copies of each other
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(fs::canonicalize(file).unwrap_or_else(|_| file.clone())));
+
+    Ok(files)
+}
+
+// Incremental hash of the bytes of a file
+enum Hasher {
+    Sha256(Sha256),
+    Xxh3(Box<Xxh3>),
+}
+
+impl Hasher {
+    fn new(hash: ContentHash) -> Self {
+        match hash {
+            ContentHash::Sha256 => Self::Sha256(Sha256::new()),
+            ContentHash::Xxh3 => Self::Xxh3(Box::new(Xxh3::new())),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Xxh3(hasher) => hasher.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Xxh3(hasher) => hasher.digest128().to_le_bytes().to_vec(),
+        }
+    }
+}
+
+// Group files with the same bytes, leaving out those that can't be read
+//
+// Only files of the same size can be copies, and most of those already differ in their
+// first or last bytes, so that files are only read entirely when these match.
+pub fn exact_duplicates(files: &[PathBuf], hash: ContentHash) -> Vec<Vec<PathBuf>> {
+    let by_size = split_groups(vec![files.to_vec()], |file| Ok(fs::metadata(file)?.len()));
+    let by_ends = split_groups(by_size, |file| partial_hash(file, hash));
+
+    // Small files were read entirely already
+    let (mut groups, large): (Vec<_>, Vec<_>) = by_ends.into_iter().partition(|group| {
+        fs::metadata(&group[0]).is_ok_and(|metadata| metadata.len() <= 2 * PARTIAL_BYTES)
+    });
+    groups.extend(split_groups(large, |file| full_hash(file, hash)));
+
+    groups
+}
+
+// Split groups of files by a key computed in parallel, keeping the groups of several files
+fn split_groups<K: Hash + Eq + Send>(
+    groups: Vec<Vec<PathBuf>>,
+    key: impl Fn(&Path) -> Result<K> + Sync,
+) -> Vec<Vec<PathBuf>> {
+    groups
+        .into_par_iter()
+        .flat_map(|group| {
+            let keys: Vec<(K, PathBuf)> = group
+                .into_par_iter()
+                .filter_map(|file| key(&file).ok().map(|key| (key, file)))
+                .collect();
+
+            let mut split: HashMap<K, Vec<PathBuf>> = HashMap::new();
+            for (key, file) in keys {
+                split.entry(key).or_default().push(file);
+            }
+            split
+                .into_values()
+                .filter(|files| files.len() > 1)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// Hash of the first and last bytes of a file, or of all of them if it is small
+fn partial_hash(path: &Path, hash: ContentHash) -> Result<Vec<u8>> {
+    let mut file = File::open(path).with_context(|| "Impossible to open file")?;
+    if file.metadata()?.len() <= 2 * PARTIAL_BYTES {
+        return full_hash(path, hash);
+    }
+
+    let mut hasher = Hasher::new(hash);
+    let mut buffer = vec![0; PARTIAL_BYTES as usize];
+    file.read_exact(&mut buffer)?;
+    hasher.update(&buffer);
+    file.seek(SeekFrom::End(-(PARTIAL_BYTES as i64)))?;
+    file.read_exact(&mut buffer)?;
+    hasher.update(&buffer);
+
+    Ok(hasher.finalize())
+}
+
+// Hash of all the bytes of a file
+fn full_hash(path: &Path, hash: ContentHash) -> Result<Vec<u8>> {
+    // Open file
+    let file = File::open(path).with_context(|| "Impossible to open file")?;
+
+    // Init buffer
+    let mut reader = BufReader::new(file);
+    let mut hasher = Hasher::new(hash);
+    let mut buffer = vec![0; 1024 * 1024];
+
+    // Read bytes until there are none left
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+// Check that an action has what it needs
+pub fn check_action(action: DuplicateAction, move_to: Option<&Path>) -> Result<()> {
+    match (action, move_to) {
+        (DuplicateAction::Move, None) => Err(anyhow::Error::msg(
+            "Moving duplicates requires a directory to move them to",
+        )),
+        (DuplicateAction::Move, Some(_)) | (_, None) => Ok(()),
+        (_, Some(_)) => Err(anyhow::Error::msg(
+            "A directory to move duplicates to only applies to the move action",
+        )),
+    }
+}
+
+// Choose the file kept out of a group of duplicates, ties going to the first path
+pub fn keep_one(
+    mut files: Vec<PathBuf>,
+    policy: KeepPolicy,
+    resolution: impl Fn(&Path) -> Result<u64>,
+) -> Result<DuplicateGroup> {
+    files.sort();
+
+    let kept = match policy {
+        KeepPolicy::Oldest => first_min(
+            &files
+                .iter()
+                .map(|file| {
+                    fs::metadata(file)
+                        .and_then(|metadata| metadata.modified())
+                        .with_context(|| format!("Failed to read modification time of {:?}", file))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        KeepPolicy::ShortestPath => first_min(
+            &files
+                .iter()
+                .map(|file| file.as_os_str().len())
+                .collect::<Vec<_>>(),
+        ),
+        KeepPolicy::LargestResolution => first_min(
+            &files
+                .iter()
+                .map(|file| {
+                    resolution(file)
+                        .map(Reverse)
+                        .with_context(|| format!("Failed to read resolution of {:?}", file))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
+    };
+
+    let kept = files.remove(kept);
+    Ok(DuplicateGroup {
+        kept,
+        duplicates: files,
+    })
+}
+
+// Index of the first smallest key
+fn first_min<T: Ord>(keys: &[T]) -> usize {
+    keys.iter()
+        .enumerate()
+        .min_by_key(|(_, key)| *key)
+        .map_or(0, |(i, _)| i)
+}
+
+// Delete, link or move the duplicates of every group, or only print what would be done
+pub fn apply_action(
+    groups: &[DuplicateGroup],
+    action: DuplicateAction,
+    targets: &[PathBuf],
+    move_to: Option<&Path>,
+    apply: bool,
+) -> Result<()> {
+    let (verb, past) = match action {
+        DuplicateAction::Report => return Ok(()),
+        DuplicateAction::Delete => ("delete", "Deleted"),
+        DuplicateAction::Hardlink => ("hardlink", "Hardlinked"),
+        DuplicateAction::Move => ("move", "Moved"),
+    };
+
+    // Moved files keep their path relative to the deepest directory holding all the targets
+    let root = match action {
+        DuplicateAction::Move => Some(common_ancestor(targets)?),
+        _ => None,
+    };
+
+    let mut count = 0;
+    for group in groups {
+        for duplicate in &group.duplicates {
+            match (action, &root, move_to) {
+                (DuplicateAction::Delete, _, _) => match apply {
+                    true => fs::remove_file(duplicate)
+                        .with_context(|| format!("Failed to delete {:?}", duplicate))?,
+                    false => println!("Would delete {}", duplicate.display()),
+                },
+                (DuplicateAction::Hardlink, _, _) => match apply {
+                    true => hard_link(&group.kept, duplicate).with_context(|| {
+                        format!("Failed to link {:?} to {:?}", duplicate, group.kept)
+                    })?,
+                    false => println!(
+                        "Would hardlink {} to {}",
+                        duplicate.display(),
+                        group.kept.display()
+                    ),
+                },
+                (DuplicateAction::Move, Some(root), Some(move_to)) => {
+                    let destination = destination(duplicate, root, move_to)?;
+                    match apply {
+                        true => move_file(duplicate, &destination).with_context(|| {
+                            format!("Failed to move {:?} to {:?}", duplicate, destination)
+                        })?,
+                        false => println!(
+                            "Would move {} to {}",
+                            duplicate.display(),
+                            destination.display()
+                        ),
+                    }
+                }
+                _ => {
+                    return Err(anyhow::Error::msg(
+                        "Missing directory to move duplicates to",
+                    ))
+                }
+            }
+            count += 1;
+        }
+    }
+
+    match apply {
+        true => println!("{} {} duplicates", past, count),
+        false => println!("Dry run, pass --apply to {} {} duplicates", verb, count),
+    }
+
+    Ok(())
+}
+
+// Deepest directory containing all the targets
+fn common_ancestor(targets: &[PathBuf]) -> Result<PathBuf> {
+    let roots = targets
+        .iter()
+        .map(|target| {
+            fs::canonicalize(target).with_context(|| format!("Failed to resolve {:?}", target))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut ancestor = roots
+        .first()
+        .cloned()
+        .ok_or_else(|| anyhow::Error::msg("No target directory"))?;
+    for root in &roots[1..] {
+        while !root.starts_with(&ancestor) && ancestor.pop() {}
+    }
+
+    Ok(ancestor)
+}
+
+// Path a duplicate is moved to, mirroring its location below the root
+fn destination(duplicate: &Path, root: &Path, move_to: &Path) -> Result<PathBuf> {
+    // Only the directory is resolved, so that a symbolic link is moved rather than its target
+    let parent = duplicate
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let name = duplicate
+        .file_name()
+        .with_context(|| format!("Failed to extract file name from: {:?}", duplicate))?;
+    let parent =
+        fs::canonicalize(parent).with_context(|| format!("Failed to resolve {:?}", parent))?;
+    let relative = parent
+        .strip_prefix(root)
+        .with_context(|| format!("File is not inside the targets: {:?}", duplicate))?;
+
+    Ok(move_to.join(relative).join(name))
+}
+
+// Replace a file with a hard link, through a temporary link so that it is never missing
+fn hard_link(kept: &Path, duplicate: &Path) -> Result<()> {
+    let name = duplicate
+        .file_name()
+        .with_context(|| format!("Failed to extract file name from: {:?}", duplicate))?;
+    let temporary = duplicate.with_file_name(format!(".{}.rush-link", name.to_string_lossy()));
+
+    fs::hard_link(kept, &temporary)?;
+    if let Err(e) = fs::rename(&temporary, duplicate) {
+        let _ = fs::remove_file(&temporary);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+// Move a file, copying it when renaming is impossible, e.g. across file systems
+fn move_file(source: &Path, destination: &Path) -> Result<()> {
+    if destination.exists() {
+        return Err(anyhow::Error::msg("Destination already exists"));
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory: {:?}", parent))?;
+    }
+
+    if fs::rename(source, destination).is_err() {
+        fs::copy(source, destination)?;
+        fs::remove_file(source)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+// Signatures telling the container of encoded images
+const JPEG_SIGNATURE: [u8; 2] = [0xFF, 0xD8];
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+// JPEG markers of the segments holding metadata, and of the start of the image data
+const JPEG_APP0: u8 = 0xE0;
+const JPEG_APP1: u8 = 0xE1;
+const JPEG_APP2: u8 = 0xE2;
+const JPEG_SOS: u8 = 0xDA;
+const JPEG_EOI: u8 = 0xD9;
+
+// Headers of the JPEG segments holding EXIF and ICC profiles
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+
+// Largest payload of a JPEG segment, whose length is stored on 16 bits including itself
+const JPEG_SEGMENT_BYTES: usize = u16::MAX as usize - 2;
+
+// Metadata of an image that is lost when decoding it, as stored in JPEG and PNG files
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImageMetadata {
+    pub exif: Option<Vec<u8>>,
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+impl ImageMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none() && self.icc_profile.is_none()
+    }
+
+    // Read the metadata of an encoded image, of which there is none in other formats
+    //
+    // Reading stops quietly at malformed structures, keeping what was found before, as
+    // decoders are the judges of whether a file can be used.
+    pub fn read(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&JPEG_SIGNATURE) {
+            read_jpeg(bytes)
+        } else if bytes.starts_with(&PNG_SIGNATURE) {
+            read_png(bytes)
+        } else {
+            Self::default()
+        }
+    }
+
+    // Add the metadata to an encoded image without any, leaving other formats unchanged
+    pub fn embed(&self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        if self.is_empty() {
+            Ok(bytes)
+        } else if bytes.starts_with(&JPEG_SIGNATURE) {
+            self.embed_jpeg(bytes)
+        } else if bytes.starts_with(&PNG_SIGNATURE) {
+            self.embed_png(bytes)
+        } else {
+            Ok(bytes)
+        }
+    }
+
+    // Insert APP1 and APP2 segments right after the JFIF header, which must come first
+    fn embed_jpeg(&self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let mut position = JPEG_SIGNATURE.len();
+        if bytes.get(position..position + 2) == Some(&[0xFF, JPEG_APP0]) {
+            position += 2 + segment_length(&bytes, position)?;
+        }
+
+        let mut segments = Vec::new();
+        if let Some(exif) = &self.exif {
+            if EXIF_HEADER.len() + exif.len() > JPEG_SEGMENT_BYTES {
+                return Err(anyhow::Error::msg("EXIF too large for a JPEG segment"));
+            }
+            write_jpeg_segment(&mut segments, JPEG_APP1, &[EXIF_HEADER, exif]);
+        }
+        if let Some(profile) = &self.icc_profile {
+            // Profiles are split into numbered chunks, counted on a byte
+            let chunk_bytes = JPEG_SEGMENT_BYTES - ICC_HEADER.len() - 2;
+            let chunks: Vec<&[u8]> = profile.chunks(chunk_bytes).collect();
+            if chunks.len() > u8::MAX as usize {
+                return Err(anyhow::Error::msg("ICC profile too large for a JPEG file"));
+            }
+            for (i, chunk) in chunks.iter().enumerate() {
+                let numbers = [i as u8 + 1, chunks.len() as u8];
+                write_jpeg_segment(&mut segments, JPEG_APP2, &[ICC_HEADER, &numbers, chunk]);
+            }
+        }
+
+        Ok([&bytes[..position], &segments, &bytes[position..]].concat())
+    }
+
+    // Insert iCCP and eXIf chunks right after the header chunk
+    fn embed_png(&self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let position = PNG_SIGNATURE.len();
+        let header_length = bytes
+            .get(position..position + 4)
+            .map(|length| u32::from_be_bytes([length[0], length[1], length[2], length[3]]))
+            .with_context(|| "Truncated PNG header")? as usize;
+        let position = position + 12 + header_length;
+
+        let mut chunks = Vec::new();
+        if let Some(profile) = &self.icc_profile {
+            // Name, compression method and zlib stream of the profile
+            let mut encoder = ZlibEncoder::new(b"ICC Profile\0\0".to_vec(), Compression::default());
+            encoder.write_all(profile)?;
+            write_png_chunk(&mut chunks, b"iCCP", &encoder.finish()?)?;
+        }
+        if let Some(exif) = &self.exif {
+            write_png_chunk(&mut chunks, b"eXIf", exif)?;
+        }
+
+        Ok([&bytes[..position], &chunks, &bytes[position..]].concat())
+    }
+}
+
+// Length of the payload of the JPEG segment whose marker is at the given position
+fn segment_length(bytes: &[u8], position: usize) -> Result<usize> {
+    let length = bytes
+        .get(position + 2..position + 4)
+        .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+        .with_context(|| "Truncated JPEG segment")?;
+    if length < 2 || position + 2 + length > bytes.len() {
+        return Err(anyhow::Error::msg("Truncated JPEG segment"));
+    }
+    Ok(length)
+}
+
+fn write_jpeg_segment(output: &mut Vec<u8>, marker: u8, parts: &[&[u8]]) {
+    let length: usize = 2 + parts.iter().map(|part| part.len()).sum::<usize>();
+    output.extend_from_slice(&[0xFF, marker]);
+    output.extend_from_slice(&(length as u16).to_be_bytes());
+    for part in parts {
+        output.extend_from_slice(part);
+    }
+}
+
+fn write_png_chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    let length = u32::try_from(data.len()).with_context(|| "Metadata too large for a PNG chunk")?;
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+
+    output.extend_from_slice(&length.to_be_bytes());
+    output.extend_from_slice(kind);
+    output.extend_from_slice(data);
+    output.extend_from_slice(&crc.finalize().to_be_bytes());
+    Ok(())
+}
+
+// Walk the segments before the image data
+fn read_jpeg(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+    let mut icc_chunks: Vec<(u8, &[u8])> = Vec::new();
+
+    let mut position = JPEG_SIGNATURE.len();
+    while let Some(&[0xFF, marker]) = bytes.get(position..position + 2) {
+        match marker {
+            // Padding
+            0xFF => {
+                position += 1;
+                continue;
+            }
+            // Markers without a payload
+            0x01 | 0xD0..=0xD7 => {
+                position += 2;
+                continue;
+            }
+            JPEG_SOS | JPEG_EOI => break,
+            _ => {}
+        }
+
+        let Ok(length) = segment_length(bytes, position) else {
+            break;
+        };
+        let payload = &bytes[position + 4..position + 2 + length];
+        match marker {
+            JPEG_APP1 if metadata.exif.is_none() => {
+                if let Some(exif) = payload.strip_prefix(EXIF_HEADER) {
+                    metadata.exif = Some(exif.to_vec());
+                }
+            }
+            JPEG_APP2 => {
+                if let Some(&[number, _, ref chunk @ ..]) = payload.strip_prefix(ICC_HEADER) {
+                    icc_chunks.push((number, chunk));
+                }
+            }
+            _ => {}
+        }
+        position += 2 + length;
+    }
+
+    if !icc_chunks.is_empty() {
+        icc_chunks.sort_by_key(|(number, _)| *number);
+        metadata.icc_profile = Some(
+            icc_chunks
+                .iter()
+                .flat_map(|(_, chunk)| *chunk)
+                .copied()
+                .collect(),
+        );
+    }
+
+    metadata
+}
+
+// Walk all the chunks, as EXIF may come after the image data
+fn read_png(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+
+    let mut position = PNG_SIGNATURE.len();
+    while let Some(header) = bytes.get(position..position + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let Some(data) = bytes.get(position + 8..position + 8 + length) else {
+            break;
+        };
+        match &header[4..] {
+            b"eXIf" => metadata.exif = Some(data.to_vec()),
+            b"iCCP" => {
+                // Name, then compression method and zlib stream
+                let profile = data
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .and_then(|end| data.get(end + 2..))
+                    .and_then(|stream| {
+                        let mut profile = Vec::new();
+                        ZlibDecoder::new(stream).read_to_end(&mut profile).ok()?;
+                        Some(profile)
+                    });
+                if profile.is_some() {
+                    metadata.icc_profile = profile;
+                }
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+        position += 12 + length;
+    }
+
+    metadata
+}
@@ -0,0 +1,340 @@
+use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use anyhow::Result;
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use rush::commands::audio;
+use rush::{AudioChannelsArgs, ChannelConversion};
+use std::fs;
+use std::path::Path;
+
+// Stereo file with a tone on the left channel and silence on the right one
+fn create_left_only_wav(path: &Path) -> Result<()> {
+    let spec = WavSpec {
+        channels: 2,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for t in 0..16000 {
+        let sample = (t as f32 * 440.0 * 2.0 * std::f32::consts::PI / 16000.0).sin();
+        writer.write_sample((sample * 16000.0) as i16)?;
+        writer.write_sample(0_i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
+
+// Float file holding a constant value on every channel
+fn create_constant_channels_wav(path: &Path, values: &[f32]) -> Result<()> {
+    let spec = WavSpec {
+        channels: values.len() as u16,
+        sample_rate: 16000,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for _ in 0..1600 {
+        for &value in values {
+            writer.write_sample(value)?;
+        }
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_mix_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_left_only_wav(&input_path)?;
+
+    // Define args
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Mix,
+        output: output_path.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::channels::execute(args)?;
+
+    // Verify output is mono with the average of the channels
+    let mut input = WavReader::open(&input_path)?;
+    let mut output = WavReader::open(&output_path)?;
+    assert_eq!(output.spec().channels, 1);
+    assert_eq!(output.duration(), 16000);
+
+    let left: Vec<i16> = input
+        .samples::<i16>()
+        .step_by(2)
+        .collect::<Result<_, _>>()?;
+    let mixed: Vec<i16> = output.samples::<i16>().collect::<Result<_, _>>()?;
+    for (left, mixed) in left.iter().zip(mixed.iter()) {
+        assert!((*left as i32 / 2 - *mixed as i32).abs() <= 1);
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_pick_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_left_only_wav(&input_path)?;
+
+    // Define args picking the silent channel
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Pick(1),
+        output: output_path.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::channels::execute(args)?;
+
+    // Verify output is the right channel alone
+    let mut output = WavReader::open(&output_path)?;
+    assert_eq!(output.spec().channels, 1);
+    assert!(output.samples::<i16>().all(|s| s.is_ok_and(|s| s == 0)));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_pick_out_of_range_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 1.0, 16000, 2, 16)?;
+
+    // Define args picking a channel that does not exist
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Pick(2),
+        output: output_path.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command and expect error
+    let result = audio::channels::execute(args);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_upmix_directory_success() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir_all(input_dir.join("nested"))?;
+
+    // Create test files in nested structure
+    create_test_wav(&input_dir.join("test1.wav"), 1.0, 16000, 1, 16)?;
+    create_test_wav(&input_dir.join("nested/test2.wav"), 1.0, 16000, 2, 24)?;
+
+    // Define args
+    let args = AudioChannelsArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        channels: ChannelConversion::Count(2),
+        output: output_dir.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::channels::execute(args)?;
+
+    // Verify every output is stereo, with the mono channel duplicated
+    let mut upmixed = WavReader::open(output_dir.join("test1.wav"))?;
+    assert_eq!(upmixed.spec().channels, 2);
+    let samples: Vec<i16> = upmixed.samples::<i16>().collect::<Result<_, _>>()?;
+    assert!(samples.chunks(2).all(|frame| frame[0] == frame[1]));
+
+    let copied = WavReader::open(output_dir.join("nested/test2.wav"))?;
+    assert_eq!(copied.spec().channels, 2);
+    assert_eq!(copied.spec().bits_per_sample, 24);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_overwrite_protection_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 1.0, 16000, 2, 16)?;
+
+    // Try to overwrite input file without overwrite flag
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Count(1),
+        output: input_path.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command and expect error
+    let result = audio::channels::execute(args);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_overwrite_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 1.0, 16000, 2, 16)?;
+
+    // Overwrite input file with overwrite flag
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Count(1),
+        output: input_path.to_string_lossy().to_string(),
+        overwrite: true,
+    };
+
+    // Execute command
+    audio::channels::execute(args)?;
+
+    // Verify the file has been replaced
+    let reader = WavReader::open(&input_path)?;
+    assert_eq!(reader.spec().channels, 1);
+    assert_eq!(reader.duration(), 16000);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_parse() -> Result<()> {
+    // Valid values
+    assert_eq!(
+        "mix".parse::<ChannelConversion>(),
+        Ok(ChannelConversion::Mix)
+    );
+    assert_eq!(
+        "2".parse::<ChannelConversion>(),
+        Ok(ChannelConversion::Count(2))
+    );
+    assert_eq!(
+        "pick:1".parse::<ChannelConversion>(),
+        Ok(ChannelConversion::Pick(1))
+    );
+
+    // Invalid values
+    assert!("0".parse::<ChannelConversion>().is_err());
+    assert!("pick:left".parse::<ChannelConversion>().is_err());
+    assert!("stereo".parse::<ChannelConversion>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_downmix_surround_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a 5.1 file with a different level on every channel (L R C LFE Ls Rs)
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_constant_channels_wav(&input_path, &[0.1, 0.2, 0.3, 0.9, 0.4, 0.5])?;
+
+    // Define args
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Count(2),
+        output: output_path.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::channels::execute(args)?;
+
+    // Verify the ITU-R BS.775 coefficients, without the LFE and scaled so that it can't clip
+    let mut output = WavReader::open(&output_path)?;
+    assert_eq!(output.spec().channels, 2);
+    let g = std::f64::consts::FRAC_1_SQRT_2;
+    let left = (0.1 + g * 0.3 + g * 0.4) / (1.0 + 2.0 * g);
+    let right = (0.2 + g * 0.3 + g * 0.5) / (1.0 + 2.0 * g);
+
+    let samples: Vec<f32> = output.samples::<f32>().collect::<Result<_, _>>()?;
+    assert_eq!(samples.len(), 3200);
+    for frame in samples.chunks(2) {
+        assert!((frame[0] as f64 - left).abs() < 1e-6);
+        assert!((frame[1] as f64 - right).abs() < 1e-6);
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_channels_undefined_mapping_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 1.0, 16000, 2, 16)?;
+
+    // Define args asking for a layout stereo has no mapping to
+    let args = AudioChannelsArgs {
+        input: input_path.to_string_lossy().to_string(),
+        channels: ChannelConversion::Count(6),
+        output: output_path.to_string_lossy().to_string(),
+        overwrite: false,
+    };
+
+    // Execute command and expect error
+    let result = audio::channels::execute(args);
+    assert!(result.is_err());
+    assert!(!output_path.exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use crate::utils::{cleanup_test_dir, create_test_wav_constant, setup_test_dir};
+use anyhow::Result;
+use hound::WavReader;
+use rush::commands::audio;
+use rush::{AudioConcatArgs, FadeCurve};
+use std::fs;
+
+#[test]
+fn test_audio_concat_natural_order_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files whose lexicographic order differs from the natural one
+    let input_dir = test_dir.join("input");
+    fs::create_dir(&input_dir)?;
+    create_test_wav_constant(&input_dir.join("take1.wav"), 0.1, 100, 1000)?;
+    create_test_wav_constant(&input_dir.join("take2.wav"), 0.2, 100, 1000)?;
+    create_test_wav_constant(&input_dir.join("take10.wav"), 0.3, 100, 1000)?;
+
+    // Define args
+    let output_path = test_dir.join("output.wav");
+    let args = AudioConcatArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        crossfade: 0.0,
+        curve: FadeCurve::Sine,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::concat::execute(args)?;
+
+    // Verify the files follow each other in natural order
+    let samples: Vec<f32> = WavReader::open(&output_path)?
+        .samples::<f32>()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(samples.len(), 300);
+    assert_eq!(samples[0], 0.1);
+    assert_eq!(samples[150], 0.2);
+    assert_eq!(samples[299], 0.3);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_concat_crossfade_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, the last one shorter than the crossfade
+    let input_dir = test_dir.join("input");
+    fs::create_dir(&input_dir)?;
+    create_test_wav_constant(&input_dir.join("a.wav"), 0.2, 100, 1000)?;
+    create_test_wav_constant(&input_dir.join("b.wav"), 0.4, 100, 1000)?;
+    create_test_wav_constant(&input_dir.join("c.wav"), 0.6, 30, 1000)?;
+
+    // Define args
+    let output_path = test_dir.join("output.wav");
+    let args = AudioConcatArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        crossfade: 0.05,
+        curve: FadeCurve::Linear,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::concat::execute(args)?;
+
+    // Overlaps are limited by the shorter file
+    let samples: Vec<f32> = WavReader::open(&output_path)?
+        .samples::<f32>()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(samples.len(), 100 + 100 + 30 - 50 - 30);
+    assert_eq!(samples[49], 0.2);
+    assert_eq!(samples[100], 0.4);
+
+    // Linear crossfades keep constant signals between the two levels
+    let progress = 34.5 / 50.0;
+    let expected = 0.2 * (1.0 - progress) + 0.4 * progress;
+    assert!((samples[84] as f64 - expected).abs() < 1e-6);
+    let progress = 29.5 / 30.0;
+    let expected = 0.4 * (1.0 - progress) + 0.6 * progress;
+    assert!((samples[149] as f64 - expected).abs() < 1e-6);
+    assert!(samples.windows(2).all(|pair| pair[1] >= pair[0] - 1e-6));
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_concat_mismatched_specs() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files with different sample rates
+    let input_dir = test_dir.join("input");
+    fs::create_dir(&input_dir)?;
+    create_test_wav_constant(&input_dir.join("a.wav"), 0.2, 100, 1000)?;
+    create_test_wav_constant(&input_dir.join("b.wav"), 0.2, 100, 2000)?;
+
+    // Define args
+    let output_path = test_dir.join("output.wav");
+    let args = AudioConcatArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        crossfade: 0.0,
+        curve: FadeCurve::Sine,
+        overwrite: false,
+    };
+
+    // Execute command
+    assert!(audio::concat::execute(args).is_err());
+    assert!(!output_path.exists());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_concat_existing_output() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, and an output left by a previous run
+    let input_dir = test_dir.join("input");
+    fs::create_dir(&input_dir)?;
+    create_test_wav_constant(&input_dir.join("a.wav"), 0.1, 100, 1000)?;
+    create_test_wav_constant(&input_dir.join("b.wav"), 0.2, 100, 1000)?;
+    let output_path = test_dir.join("output.wav");
+    fs::write(&output_path, b"previous")?;
+
+    // Define args
+    let args = |overwrite| AudioConcatArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        crossfade: 0.0,
+        curve: FadeCurve::Sine,
+        overwrite,
+    };
+
+    // Execute command and expect error, leaving the output untouched
+    let error =
+        audio::concat::execute(args(false)).expect_err("existing output should not be replaced");
+    assert!(error.to_string().contains("already exists"));
+    assert_eq!(fs::read(&output_path)?, b"previous");
+
+    // Replace it when requested
+    audio::concat::execute(args(true))?;
+    assert_eq!(WavReader::open(&output_path)?.duration(), 200);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
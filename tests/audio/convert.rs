@@ -0,0 +1,220 @@
+use crate::utils::{cleanup_test_dir, create_test_wav, create_test_wav_float, setup_test_dir};
+use anyhow::Result;
+use hound::{SampleFormat, WavReader};
+use rush::commands::audio;
+use rush::{AudioCodec, AudioConvertArgs, AudioSampleFormat};
+use std::fs;
+
+#[test]
+fn test_audio_convert_int_to_float_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 1.0, 16000, 2, 16)?;
+
+    // Define args
+    let args = AudioConvertArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        codec: AudioCodec::Wav,
+        bit_depth: None,
+        sample_format: Some(AudioSampleFormat::Float),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::convert::execute(args)?;
+
+    // Verify output is float with the same samples
+    let mut input = WavReader::open(&input_path)?;
+    let mut output = WavReader::open(&output_path)?;
+    assert_eq!(output.spec().sample_format, SampleFormat::Float);
+    assert_eq!(output.spec().bits_per_sample, 32);
+    assert_eq!(output.spec().channels, 2);
+    assert_eq!(output.duration(), input.duration());
+
+    let original: Vec<i16> = input.samples::<i16>().collect::<Result<_, _>>()?;
+    let converted: Vec<f32> = output.samples::<f32>().collect::<Result<_, _>>()?;
+    for (&a, &b) in original.iter().zip(converted.iter()) {
+        assert!((a as f32 / 32768.0 - b).abs() < 1e-6);
+    }
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_convert_bit_depth_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 1.0, 16000, 1, 16)?;
+
+    // Define args
+    let args = AudioConvertArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        codec: AudioCodec::Wav,
+        bit_depth: Some(24),
+        sample_format: None,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::convert::execute(args)?;
+
+    // Verify output is 24-bit with the samples shifted by 8 bits
+    let mut input = WavReader::open(&input_path)?;
+    let mut output = WavReader::open(&output_path)?;
+    assert_eq!(output.spec().sample_format, SampleFormat::Int);
+    assert_eq!(output.spec().bits_per_sample, 24);
+
+    let original: Vec<i32> = input.samples::<i32>().collect::<Result<_, _>>()?;
+    let converted: Vec<i32> = output.samples::<i32>().collect::<Result<_, _>>()?;
+    assert_eq!(original.len(), converted.len());
+    for (&a, &b) in original.iter().zip(converted.iter()) {
+        assert_eq!(a * 256, b);
+    }
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_convert_float_defaults_to_24_bits() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_float(&input_path, 0.5, 16000, 1)?;
+
+    // Define args
+    let args = AudioConvertArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        codec: AudioCodec::Wav,
+        bit_depth: None,
+        sample_format: Some(AudioSampleFormat::Int),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::convert::execute(args)?;
+
+    // Verify output
+    let output = WavReader::open(&output_path)?;
+    assert_eq!(output.spec().sample_format, SampleFormat::Int);
+    assert_eq!(output.spec().bits_per_sample, 24);
+    assert_eq!(output.duration(), 8000);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_convert_directory_maps_extensions() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir_all(input_dir.join("nested"))?;
+    create_test_wav(&input_dir.join("a.wav"), 0.5, 16000, 1, 16)?;
+    create_test_wav(&input_dir.join("nested").join("b.WAV"), 0.5, 16000, 1, 16)?;
+
+    // Define args
+    let args = AudioConvertArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        codec: AudioCodec::Wav,
+        bit_depth: Some(8),
+        sample_format: None,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::convert::execute(args)?;
+
+    // Verify the tree is mirrored with the extension of the codec
+    for path in [
+        output_dir.join("a.wav"),
+        output_dir.join("nested").join("b.wav"),
+    ] {
+        let output = WavReader::open(&path)?;
+        assert_eq!(output.spec().bits_per_sample, 8);
+        assert_eq!(output.duration(), 8000);
+    }
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_convert_invalid_requests() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 0.5, 16000, 1, 16)?;
+
+    let args = |output: &str, codec, bit_depth, sample_format| AudioConvertArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: test_dir.join(output).to_string_lossy().to_string(),
+        codec,
+        bit_depth,
+        sample_format,
+        overwrite: false,
+    };
+
+    // Output extension not matching the codec
+    assert!(audio::convert::execute(args("output.wav", AudioCodec::Flac, None, None)).is_err());
+
+    // FLAC can't store float samples
+    assert!(audio::convert::execute(args(
+        "output.flac",
+        AudioCodec::Flac,
+        None,
+        Some(AudioSampleFormat::Float)
+    ))
+    .is_err());
+
+    // Lossy codecs choose their own sample format
+    assert!(audio::convert::execute(args("output.mp3", AudioCodec::Mp3, Some(16), None)).is_err());
+
+    // Unsupported bit depths
+    assert!(audio::convert::execute(args("output.wav", AudioCodec::Wav, Some(12), None)).is_err());
+    assert!(audio::convert::execute(args(
+        "output.wav",
+        AudioCodec::Wav,
+        Some(16),
+        Some(AudioSampleFormat::Float)
+    ))
+    .is_err());
+
+    // Nothing was written
+    assert!(!test_dir.join("output.wav").exists());
+    assert!(!test_dir.join("output.flac").exists());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use anyhow::Result;
+use hound::WavReader;
+use polars::prelude::*;
+use rush::commands::audio;
+use rush::utils::write_table;
+use rush::AudioCutArgs;
+use std::fs;
+use std::path::Path;
+
+fn cut_args(input: &Path, output: &Path, segments: &Path) -> AudioCutArgs {
+    AudioCutArgs {
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        segments: segments.to_string_lossy().to_string(),
+        clamp: false,
+    }
+}
+
+#[test]
+fn test_audio_cut_success() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir_all(input_dir.join("nested"))?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("a.wav"), 5.0, 8000, 1, 16)?;
+    create_test_wav(&input_dir.join("nested/b.wav"), 5.0, 16000, 2, 16)?;
+
+    // Segments with times as numbers, timestamps and negative offsets
+    let segments_path = test_dir.join("segments.csv");
+    let mut df = df!(
+        "file" => ["a.wav", "a.wav", "nested/b.wav"],
+        "start" => ["0.5", "00:00:02", "-1"],
+        "end" => ["1.5", "2.25", "-0.5"],
+        "label" => ["speech", "music", "speech"],
+    )?;
+    write_table(&mut df, &segments_path)?;
+
+    // Execute command
+    audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path))?;
+
+    // Verify segments are sorted by label with deterministic names
+    let first = output_dir.join("speech/a@500-1500.wav");
+    let second = output_dir.join("music/a@2000-2250.wav");
+    let third = output_dir.join("speech/nested/b@4000-4500.wav");
+    assert_eq!(WavReader::open(first)?.duration(), 8000);
+    assert_eq!(WavReader::open(second)?.duration(), 2000);
+    let reader = WavReader::open(third)?;
+    assert_eq!(reader.duration(), 8000);
+    assert_eq!(reader.spec().channels, 2);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_cut_numeric_times_without_label() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("a.wav"), 2.0, 8000, 1, 16)?;
+
+    // Segments table in parquet with float times, the last one exceeding the file
+    let segments_path = test_dir.join("segments.parquet");
+    let mut df = df!(
+        "file" => ["a.wav", "a.wav"],
+        "start" => [0.25, 1.5],
+        "end" => [0.75, 3.0],
+    )?;
+    write_table(&mut df, &segments_path)?;
+
+    // Execute command and expect error
+    assert!(audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path)).is_err());
+
+    // Execute command trimming to what is available
+    let mut args = cut_args(&input_dir, &output_dir, &segments_path);
+    args.clamp = true;
+    audio::cut::execute(args)?;
+    assert_eq!(
+        WavReader::open(output_dir.join("a@250-750.wav"))?.duration(),
+        4000
+    );
+
+    // Names follow the table rather than the available audio
+    assert_eq!(
+        WavReader::open(output_dir.join("a@1500-3000.wav"))?.duration(),
+        4000
+    );
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_cut_float_seconds_success() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("a.wav"), 20.0, 8000, 1, 16)?;
+
+    // Float times with more than 9 decimals or printed in exponent notation
+    let segments_path = test_dir.join("segments.parquet");
+    let mut df = df!(
+        "file" => ["a.wav", "a.wav"],
+        "start" => [1e-05, 12.3456789012],
+        "end" => [0.30000000000000004, 13.0],
+    )?;
+    write_table(&mut df, &segments_path)?;
+
+    // Execute command
+    audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path))?;
+
+    // Verify times are read as seconds
+    assert_eq!(
+        WavReader::open(output_dir.join("a@0-300.wav"))?.duration(),
+        2400
+    );
+    assert_eq!(
+        WavReader::open(output_dir.join("a@12346-13000.wav"))?.duration(),
+        5235
+    );
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_cut_duplicate_rows_error() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("a.wav"), 2.0, 8000, 1, 16)?;
+
+    // The same segment twice
+    let segments_path = test_dir.join("segments.csv");
+    let mut df = df!(
+        "file" => ["a.wav", "a.wav"],
+        "start" => ["0.5", "0.5"],
+        "end" => ["1", "1"],
+    )?;
+    write_table(&mut df, &segments_path)?;
+
+    // Execute command and expect error
+    assert!(audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path)).is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_cut_invalid_table_error() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("a.wav"), 2.0, 8000, 1, 16)?;
+
+    // Missing end column
+    let segments_path = test_dir.join("segments.csv");
+    let mut df = df!(
+        "file" => ["a.wav"],
+        "start" => ["0.5"],
+    )?;
+    write_table(&mut df, &segments_path)?;
+    assert!(audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path)).is_err());
+
+    // Label escaping the output directory
+    let mut df = df!(
+        "file" => ["a.wav"],
+        "start" => ["0.5"],
+        "end" => ["1"],
+        "label" => [".."],
+    )?;
+    write_table(&mut df, &segments_path)?;
+    assert!(audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path)).is_err());
+
+    // File outside of the input root
+    let mut df = df!(
+        "file" => ["../a.wav"],
+        "start" => ["0.5"],
+        "end" => ["1"],
+    )?;
+    write_table(&mut df, &segments_path)?;
+    assert!(audio::cut::execute(cut_args(&input_dir, &output_dir, &segments_path)).is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use crate::utils::{cleanup_test_dir, create_test_wav_melody, setup_test_dir};
+use anyhow::Result;
+use rush::commands::audio;
+use rush::utils::read_table;
+use rush::AudioDuplicatesArgs;
+use std::path::Path;
+
+fn duplicates_args(target: &Path, manifest: &Path) -> AudioDuplicatesArgs {
+    AudioDuplicatesArgs {
+        target: target.to_string_lossy().to_string(),
+        recursive: false,
+        threshold: 0.8,
+        manifest: Some(manifest.to_string_lossy().to_string()),
+        errors: None,
+        strict: false,
+    }
+}
+
+// Groups and file names listed in a manifest
+fn read_manifest(path: &Path) -> Result<Vec<(u32, String)>> {
+    let df = read_table(path)?.collect()?;
+    let groups = df.column("group")?.u32()?;
+    let paths = df.column("path")?.str()?;
+
+    Ok(groups
+        .into_iter()
+        .zip(paths)
+        .map(|(group, path)| {
+            let name = Path::new(path.unwrap_or_default())
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (group.unwrap_or_default(), name)
+        })
+        .collect())
+}
+
+#[test]
+fn test_audio_duplicates_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir(&input_dir)?;
+
+    // The same melody at different sample rates and levels, and another melody
+    create_test_wav_melody(&input_dir.join("a.wav"), 1, 3.0, 16000, 0.5)?;
+    create_test_wav_melody(&input_dir.join("b.wav"), 1, 3.0, 44100, 0.2)?;
+    create_test_wav_melody(&input_dir.join("c.wav"), 2, 3.0, 16000, 0.5)?;
+
+    // Execute command
+    let manifest_path = test_dir.join("duplicates.parquet");
+    audio::duplicates::execute(duplicates_args(&input_dir, &manifest_path))?;
+
+    // Verify only the two recordings of the same melody are grouped
+    assert_eq!(
+        read_manifest(&manifest_path)?,
+        vec![(0, "a.wav".to_string()), (0, "b.wav".to_string())]
+    );
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_duplicates_excerpt_not_duplicate() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir(&input_dir)?;
+
+    // The beginning of a recording is not a duplicate of it
+    create_test_wav_melody(&input_dir.join("full.wav"), 3, 6.0, 16000, 0.5)?;
+    create_test_wav_melody(&input_dir.join("excerpt.wav"), 3, 2.0, 16000, 0.5)?;
+
+    // Execute command
+    let manifest_path = test_dir.join("duplicates.parquet");
+    audio::duplicates::execute(duplicates_args(&input_dir, &manifest_path))?;
+    assert!(read_manifest(&manifest_path)?.is_empty());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_duplicates_invalid_threshold() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    create_test_wav_melody(&test_dir.join("a.wav"), 1, 1.0, 16000, 0.5)?;
+    create_test_wav_melody(&test_dir.join("b.wav"), 1, 1.0, 16000, 0.5)?;
+
+    // Execute command and expect error
+    let mut args = duplicates_args(&test_dir, &test_dir.join("duplicates.csv"));
+    args.threshold = 1.5;
+    assert!(audio::duplicates::execute(args).is_err());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_duplicates_not_a_directory() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let file_path = test_dir.join("a.wav");
+    create_test_wav_melody(&file_path, 1, 1.0, 16000, 0.5)?;
+
+    // Execute command and expect error
+    let result = audio::duplicates::execute(duplicates_args(
+        &file_path,
+        &test_dir.join("duplicates.csv"),
+    ));
+    assert!(result.is_err());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_duplicates_recursive() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir_all(input_dir.join("nested"))?;
+
+    // The same melody at the top level and in a subdirectory, with a shorter unrelated file
+    create_test_wav_melody(&input_dir.join("a.wav"), 1, 3.0, 16000, 0.5)?;
+    create_test_wav_melody(&input_dir.join("c.wav"), 2, 1.0, 16000, 0.5)?;
+    create_test_wav_melody(&input_dir.join("nested").join("b.wav"), 1, 3.0, 22050, 0.3)?;
+
+    // Subdirectories are ignored by default
+    let manifest_path = test_dir.join("duplicates.parquet");
+    audio::duplicates::execute(duplicates_args(&input_dir, &manifest_path))?;
+    assert!(read_manifest(&manifest_path)?.is_empty());
+
+    // Execute command
+    let mut args = duplicates_args(&input_dir, &manifest_path);
+    args.recursive = true;
+    audio::duplicates::execute(args)?;
+    assert_eq!(
+        read_manifest(&manifest_path)?,
+        vec![(0, "a.wav".to_string()), (0, "b.wav".to_string())]
+    );
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use crate::utils::{cleanup_test_dir, create_test_wav_constant, setup_test_dir};
+use anyhow::Result;
+use hound::WavReader;
+use rush::commands::audio;
+use rush::{AudioFadeArgs, FadeCurve};
+use std::path::Path;
+
+fn read_samples(path: &Path) -> Result<Vec<f32>> {
+    Ok(WavReader::open(path)?
+        .samples::<f32>()
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn test_audio_fade_linear_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_constant(&input_path, 0.5, 100_000, 1000)?;
+
+    // Define args, with a fade out spanning several read blocks
+    let args = AudioFadeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        fade_in: 0.1,
+        fade_out: 70.0,
+        curve: FadeCurve::Linear,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::fade::execute(args)?;
+
+    // Verify the gain ramps at both ends and is untouched in between
+    let samples = read_samples(&output_path)?;
+    assert_eq!(samples.len(), 100_000);
+    assert_eq!(samples[0], 0.0);
+    assert!((samples[50] - 0.25).abs() < 1e-6);
+    assert_eq!(samples[100], 0.5);
+    assert_eq!(samples[29_999], 0.5);
+    assert!((samples[65_000] - 0.5 * 34_999.0 / 70_000.0).abs() < 1e-6);
+    assert_eq!(samples[99_999], 0.0);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_fade_sine_overlapping_fades() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files shorter than the fades
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_constant(&input_path, 1.0, 500, 1000)?;
+
+    // Define args
+    let args = AudioFadeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        fade_in: 1.0,
+        fade_out: 1.0,
+        curve: FadeCurve::Sine,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::fade::execute(args)?;
+
+    // Both fades apply at once
+    let samples = read_samples(&output_path)?;
+    assert_eq!(samples.len(), 500);
+    let expected = (250.0 / 1000.0 * std::f64::consts::FRAC_PI_2).sin()
+        * (249.0 / 1000.0 * std::f64::consts::FRAC_PI_2).sin();
+    assert!((samples[250] as f64 - expected).abs() < 1e-6);
+    assert_eq!(samples[0], 0.0);
+    assert_eq!(samples[499], 0.0);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_fade_negative_duration() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_constant(&input_path, 1.0, 500, 1000)?;
+
+    // Define args
+    let args = AudioFadeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        fade_in: -1.0,
+        fade_out: 0.0,
+        curve: FadeCurve::Linear,
+        overwrite: false,
+    };
+
+    // Execute command
+    assert!(audio::fade::execute(args).is_err());
+    assert!(!output_path.exists());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -1,4 +1,15 @@
+mod channels;
+mod concat;
+mod convert;
+mod cut;
+mod duplicates;
+mod fade;
+mod normalize;
+mod pad;
 mod resample;
+mod spectrogram;
 mod split;
 mod summary;
+mod tags;
 mod trim;
+mod vad;
@@ -0,0 +1,229 @@
+use crate::utils::{
+    cleanup_test_dir, create_test_wav, create_test_wav_tone, create_test_wav_with_silence,
+    setup_test_dir,
+};
+use anyhow::Result;
+use rush::commands::audio;
+use rush::utils::audio::loudness::LoudnessMeter;
+use rush::utils::audio::AudioReader;
+use rush::utils::read_table;
+use rush::{AudioNormalizeArgs, NormalizeMode};
+use std::fs;
+use std::path::Path;
+
+// Measure the levels of a file
+fn measure(path: &Path) -> Result<LoudnessMeter> {
+    let mut reader = AudioReader::open(path)?;
+    let mut meter = LoudnessMeter::new(reader.spec());
+    meter.push(&reader.read_all()?);
+    Ok(meter)
+}
+
+#[test]
+fn test_audio_normalize_loudness_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 5.0, 44100, 2, 16)?;
+
+    // Define args
+    let args = AudioNormalizeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        mode: NormalizeMode::Loudness,
+        target: Some(-23.0),
+        true_peak: -1.0,
+        report: None,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::normalize::execute(args)?;
+
+    // Verify the output reaches the target loudness
+    let meter = measure(&output_path)?;
+    assert!((meter.integrated_loudness() + 23.0).abs() < 0.1);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_normalize_peak_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 2.0, 44100, 1, 24)?;
+
+    // Define args with a ceiling that does not interfere
+    let args = AudioNormalizeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        mode: NormalizeMode::Peak,
+        target: Some(-6.0),
+        true_peak: 0.0,
+        report: None,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::normalize::execute(args)?;
+
+    // Verify the output peak is the target
+    let meter = measure(&output_path)?;
+    assert!((meter.sample_peak() + 6.0).abs() < 0.01);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_normalize_true_peak_ceiling_success() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    let report_path = test_dir.join("report.csv");
+    fs::create_dir(&input_dir)?;
+
+    // Create a full scale file and a silent one
+    create_test_wav(&input_dir.join("loud.wav"), 2.0, 44100, 1, 16)?;
+    create_test_wav_with_silence(&input_dir.join("silent.wav"), &[(2.0, false)], 44100)?;
+
+    // Ask for a loudness that would need to go over the ceiling
+    let args = AudioNormalizeArgs {
+        input: input_dir.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        mode: NormalizeMode::Loudness,
+        target: Some(0.0),
+        true_peak: -1.0,
+        report: Some(report_path.to_string_lossy().to_string()),
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::normalize::execute(args)?;
+
+    // Verify the gain is limited by the true peak ceiling
+    let meter = measure(&output_dir.join("loud.wav"))?;
+    assert!(meter.true_peak() <= -0.99);
+
+    // Verify the report has one row per file, with no gain on the silent one
+    let df = read_table(&report_path)?.collect()?;
+    assert_eq!(df.height(), 2);
+    let gains: Vec<Option<f64>> = df.column("gain_db")?.f64()?.into_iter().collect();
+    assert!(gains[0].is_some_and(|gain| gain < 0.0));
+    assert_eq!(gains[1], Some(0.0));
+    assert!(output_dir.join("silent.wav").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_normalize_overwrite_protection_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 1.0, 44100, 2, 16)?;
+
+    // Try to overwrite input file without overwrite flag
+    let args = AudioNormalizeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: input_path.to_string_lossy().to_string(),
+        mode: NormalizeMode::Loudness,
+        target: None,
+        true_peak: -1.0,
+        report: None,
+        overwrite: false,
+    };
+
+    // Execute command and expect error
+    let result = audio::normalize::execute(args);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_normalize_shorter_than_block_untouched() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // A clip shorter than one 400 ms loudness block
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 0.2, 44100, 1, 16)?;
+
+    // Define args
+    let args = AudioNormalizeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        mode: NormalizeMode::Loudness,
+        target: Some(-23.0),
+        true_peak: -1.0,
+        report: None,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::normalize::execute(args)?;
+
+    // Verify the output equals the input
+    assert_eq!(fs::read(&output_path)?, fs::read(&input_path)?);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_normalize_below_gate_untouched() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // A tone quiet enough for every block to be gated out
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_tone(&input_path, -75.0, 2.0, 44100)?;
+
+    // Define args
+    let args = AudioNormalizeArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        mode: NormalizeMode::Loudness,
+        target: Some(-23.0),
+        true_peak: -1.0,
+        report: None,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::normalize::execute(args)?;
+
+    // Verify the output equals the input
+    assert_eq!(fs::read(&output_path)?, fs::read(&input_path)?);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use anyhow::Result;
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use rush::commands::audio;
+use rush::{AudioPadArgs, PadMode, PadPosition};
+use std::path::Path;
+
+fn args(input: &Path, length: f32, output: &Path) -> AudioPadArgs {
+    AudioPadArgs {
+        input: input.to_string_lossy().to_string(),
+        length,
+        output: output.to_string_lossy().to_string(),
+        position: PadPosition::End,
+        mode: PadMode::Silence,
+        overwrite: false,
+    }
+}
+
+#[test]
+fn test_audio_pad_silence_end_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 0.5, 16000, 2, 16)?;
+
+    // Execute command
+    audio::pad::execute(args(&input_path, 1.0, &output_path))?;
+
+    // Verify the audio is followed by zeros up to the target length
+    let original: Vec<i16> = WavReader::open(&input_path)?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    let mut output = WavReader::open(&output_path)?;
+    assert_eq!(output.duration(), 16000);
+    let padded: Vec<i16> = output.samples::<i16>().collect::<Result<_, _>>()?;
+    assert_eq!(&padded[..original.len()], &original[..]);
+    assert!(padded[original.len()..].iter().all(|&sample| sample == 0));
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_pad_silence_start_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 0.5, 16000, 1, 16)?;
+
+    // Execute command
+    let mut args = args(&input_path, 0.75, &output_path);
+    args.position = PadPosition::Start;
+    audio::pad::execute(args)?;
+
+    // Verify the zeros come first
+    let original: Vec<i16> = WavReader::open(&input_path)?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    let padded: Vec<i16> = WavReader::open(&output_path)?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(padded.len(), 12000);
+    assert!(padded[..4000].iter().all(|&sample| sample == 0));
+    assert_eq!(&padded[4000..], &original[..]);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_pad_reflect_both_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a short ramp, easy to follow once mirrored
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 10,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(&input_path, spec)?;
+    for value in 1..=5 {
+        writer.write_sample(value * 1000_i16)?;
+    }
+    writer.finalize()?;
+
+    // Execute command
+    let mut args = args(&input_path, 0.9, &output_path);
+    args.position = PadPosition::Both;
+    args.mode = PadMode::Reflect;
+    audio::pad::execute(args)?;
+
+    // Verify the ramp is mirrored at both ends without repeating the edges
+    let padded: Vec<i16> = WavReader::open(&output_path)?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        padded,
+        vec![3000, 2000, 1000, 2000, 3000, 4000, 5000, 4000, 3000]
+    );
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_pad_longer_file_unchanged() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 1.0, 16000, 1, 16)?;
+
+    // Execute command
+    audio::pad::execute(args(&input_path, 0.5, &output_path))?;
+
+    // Verify nothing was cut
+    let original: Vec<i16> = WavReader::open(&input_path)?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    let output: Vec<i16> = WavReader::open(&output_path)?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(output, original);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
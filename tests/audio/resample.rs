@@ -1,9 +1,25 @@
-use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use crate::utils::{
+    cleanup_test_dir, create_test_wav, create_test_wav_constant, create_test_wav_float,
+    setup_test_dir,
+};
 use anyhow::Result;
-use hound::WavReader;
+use hound::{SampleFormat, WavReader};
 use rush::commands::audio;
-use rush::AudioResampleArgs;
+use rush::{AudioResampleArgs, ResampleQuality};
 use std::fs;
+use std::path::Path;
+
+fn resample_args(input: &Path, output: &Path, sr: u32) -> AudioResampleArgs {
+    AudioResampleArgs {
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        sr,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
+        overwrite: false,
+    }
+}
 
 #[test]
 fn test_audio_resample_file_success() -> Result<()> {
@@ -20,6 +36,9 @@ fn test_audio_resample_file_success() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
         sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -59,6 +78,9 @@ fn test_audio_resample_directory_success() -> Result<()> {
         input: input_dir.to_string_lossy().to_string(),
         output: output_dir.to_string_lossy().to_string(),
         sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -98,6 +120,9 @@ fn test_audio_resample_same_rate_success() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
         sr: 44100,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -129,6 +154,9 @@ fn test_audio_resample_overwrite_protection_error() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: input_path.to_string_lossy().to_string(),
         sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -156,6 +184,9 @@ fn test_audio_resample_overwrite_success() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: input_path.to_string_lossy().to_string(),
         sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: true,
     };
 
@@ -188,6 +219,9 @@ fn test_audio_resample_very_high_rate_success() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
         sr: 44100,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -220,6 +254,9 @@ fn test_audio_resample_higher_rate() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
         sr: 44100,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -252,6 +289,9 @@ fn test_audio_resample_mono_8bit() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
         sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -286,6 +326,9 @@ fn test_audio_resample_stereo_32bit() -> Result<()> {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
         sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
         overwrite: false,
     };
 
@@ -304,3 +347,280 @@ fn test_audio_resample_stereo_32bit() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_audio_resample_stereo_24bit() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 5.0, 44100, 2, 24)?;
+
+    // Define args with new sample rate
+    let args = AudioResampleArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::resample::execute(args)?;
+
+    // Verify output file keeps the input format
+    let mut reader = WavReader::open(output_path)?;
+    assert_eq!(reader.spec().sample_rate, 22050);
+    assert_eq!(reader.spec().bits_per_sample, 24);
+    assert_eq!(reader.spec().sample_format, SampleFormat::Int);
+
+    // Verify integer samples are clipped to the bit depth range
+    let peak = reader
+        .samples::<i32>()
+        .map(|s| s.map(|s| s.unsigned_abs() as f32 / 8_388_608.0))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .fold(0.0_f32, f32::max);
+    assert!(peak > 0.5 && peak <= 1.0);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_stereo_float() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_float(&input_path, 5.0, 44100, 2)?;
+
+    // Define args with new sample rate
+    let args = AudioResampleArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        sr: 22050,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::resample::execute(args)?;
+
+    // Verify output file keeps the input format
+    let mut reader = WavReader::open(output_path)?;
+    assert_eq!(reader.spec().sample_rate, 22050);
+    assert_eq!(reader.spec().bits_per_sample, 32);
+    assert_eq!(reader.spec().sample_format, SampleFormat::Float);
+
+    // Verify samples are preserved as floats, which may slightly overshoot after filtering
+    let peak = reader
+        .samples::<f32>()
+        .map(|s| s.map(|s| s.abs()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .fold(0.0_f32, f32::max);
+    assert!(peak > 0.5 && peak < 1.1);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_output_length() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files longer than a single resampler chunk
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 2.5, 44100, 2, 16)?;
+
+    // Define args with new sample rate
+    let args = AudioResampleArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        sr: 16000,
+        quality: ResampleQuality::Balanced,
+        bit_depth: None,
+        dither: false,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::resample::execute(args)?;
+
+    // Verify the duration is preserved exactly
+    let reader = WavReader::open(output_path)?;
+    assert_eq!(reader.duration(), 40000);
+
+    // Verify no temporary file is left behind
+    assert_eq!(fs::read_dir(&test_dir)?.count(), 2);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_quality_presets() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files longer than a single resampler chunk
+    let input_path = test_dir.join("input.wav");
+    create_test_wav_float(&input_path, 2.5, 44100, 1)?;
+
+    for quality in [
+        ResampleQuality::Fast,
+        ResampleQuality::Balanced,
+        ResampleQuality::Best,
+    ] {
+        // Execute command
+        let output_path = test_dir.join(format!("{:?}.wav", quality));
+        let mut args = resample_args(&input_path, &output_path, 16000);
+        args.quality = quality;
+        audio::resample::execute(args)?;
+
+        // Verify the duration is preserved exactly
+        let mut reader = WavReader::open(output_path)?;
+        assert_eq!(reader.duration(), 40000);
+
+        // Verify the full-scale sine keeps its level away from the edges
+        let samples: Vec<f32> = reader.samples::<f32>().collect::<Result<_, _>>()?;
+        let middle = &samples[8000..32000];
+        let rms = (middle.iter().map(|s| s * s).sum::<f32>() / middle.len() as f32).sqrt();
+        assert!(
+            (rms - 0.5_f32.sqrt()).abs() < 0.01,
+            "{:?}: {}",
+            quality,
+            rms
+        );
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_bit_depth_dither() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a silent float file
+    let input_path = test_dir.join("input.wav");
+    create_test_wav_constant(&input_path, 0.0, 48000, 48000)?;
+
+    // Reduce to 16 bits without dither
+    let plain_path = test_dir.join("plain.wav");
+    let mut args = resample_args(&input_path, &plain_path, 44100);
+    args.bit_depth = Some(16);
+    audio::resample::execute(args)?;
+
+    let mut reader = WavReader::open(plain_path)?;
+    assert_eq!(reader.spec().bits_per_sample, 16);
+    assert_eq!(reader.spec().sample_format, SampleFormat::Int);
+    assert!(reader.samples::<i32>().all(|s| s.is_ok_and(|s| s == 0)));
+
+    // With dither, silence becomes noise of at most one step
+    let dithered_path = test_dir.join("dithered.wav");
+    let mut args = resample_args(&input_path, &dithered_path, 44100);
+    args.bit_depth = Some(16);
+    args.dither = true;
+    audio::resample::execute(args)?;
+
+    let mut reader = WavReader::open(dithered_path)?;
+    assert_eq!(reader.duration(), 44100);
+    let samples: Vec<i32> = reader.samples::<i32>().collect::<Result<_, _>>()?;
+    assert!(samples.iter().all(|s| s.abs() <= 1));
+    assert!(samples.iter().any(|&s| s != 0));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_invalid_bit_depth_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 1.0, 44100, 1, 16)?;
+
+    // Execute command and expect error
+    let mut args = resample_args(&input_path, &test_dir.join("output.wav"), 16000);
+    args.bit_depth = Some(12);
+    assert!(audio::resample::execute(args).is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_colliding_outputs_error() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // Create two files that would both be written as take.wav
+    create_test_wav(&input_dir.join("take.wav"), 1.0, 44100, 1, 16)?;
+    fs::write(input_dir.join("take.mp3"), "not decoded")?;
+
+    // Execute command and expect error before anything is written
+    let result = audio::resample::execute(resample_args(&input_dir, &output_dir, 22050));
+    let error = format!(
+        "{:#}",
+        result.expect_err("colliding outputs must be rejected")
+    );
+    assert!(error.contains("would both be written"));
+    assert!(!output_dir.join("take.wav").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_resample_float_to_flac_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a float file, which FLAC can't store
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.flac");
+    create_test_wav_float(&input_path, 1.0, 44100, 1)?;
+
+    // Execute command and expect error rather than a silently converted file
+    let result = audio::resample::execute(resample_args(&input_path, &output_path, 22050));
+    let error = format!("{:#}", result.expect_err("float FLAC must be rejected"));
+    assert!(error.contains("FLAC"));
+    assert!(!output_path.exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use anyhow::Result;
+use polars::prelude::*;
+use rush::commands::audio;
+use rush::{AudioSpectrogramArgs, SpectrogramFormat, SpectrogramWindow};
+use std::fs;
+use std::path::Path;
+
+// Read a float32 NumPy array written by the command, returning its shape and data in row-major order
+fn read_npy(path: &Path) -> Result<((usize, usize), Vec<f32>)> {
+    let bytes = fs::read(path)?;
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&bytes[10..10 + header_len])?;
+    assert!(header.contains("'descr': '<f4'"));
+
+    // Parse the shape tuple
+    let shape = header
+        .split("'shape': (")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .ok_or_else(|| anyhow::anyhow!("Missing shape"))?;
+    let dims: Vec<usize> = shape
+        .split(',')
+        .map(|dim| dim.trim().parse())
+        .collect::<Result<_, _>>()?;
+
+    let data: Vec<f32> = bytes[10 + header_len..]
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    assert_eq!(data.len(), dims[0] * dims[1]);
+
+    // Transpose arrays stored in column-major order
+    let data = match header.contains("'fortran_order': True") {
+        true => (0..dims[0] * dims[1])
+            .map(|i| data[(i % dims[1]) * dims[0] + i / dims[1]])
+            .collect(),
+        false => data,
+    };
+
+    Ok(((dims[0], dims[1]), data))
+}
+
+fn args(input: &Path, output: &Path, format: SpectrogramFormat) -> AudioSpectrogramArgs {
+    AudioSpectrogramArgs {
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        format,
+        n_fft: 512,
+        hop: 256,
+        window: SpectrogramWindow::Hann,
+        mels: None,
+    }
+}
+
+#[test]
+fn test_audio_spectrogram_png_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.png");
+    create_test_wav(&input_path, 1.0, 16000, 2, 16)?;
+
+    // Execute command
+    audio::spectrogram::execute(args(&input_path, &output_path, SpectrogramFormat::Png))?;
+
+    // One column per frame (the last one padded) and one row per linear bin
+    let image = image::open(&output_path)?.to_luma8();
+    assert_eq!(image.width(), 62);
+    assert_eq!(image.height(), 257);
+
+    // The 440 Hz tone is the brightest row, counting from the bottom
+    let row = image.height() - 1 - 14;
+    assert_eq!(image.get_pixel(30, row)[0], 255);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_spectrogram_npy_linear_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.npy");
+    create_test_wav(&input_path, 1.0, 16000, 1, 16)?;
+
+    // Execute command
+    audio::spectrogram::execute(args(&input_path, &output_path, SpectrogramFormat::Npy))?;
+
+    // Verify shape and content
+    let ((bins, frames), data) = read_npy(&output_path)?;
+    assert_eq!((bins, frames), (257, 62));
+    assert_eq!(data.len(), bins * frames);
+
+    // The loudest bin of a frame is the one of the tone, at about -6 dB
+    let frame = 30;
+    let column: Vec<f32> = (0..bins).map(|bin| data[bin * frames + frame]).collect();
+    let (peak_bin, &peak) = column
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .unwrap();
+    assert_eq!(peak_bin, 14);
+    assert!(peak > -10.0 && peak < -6.0);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_spectrogram_parquet_mels_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.parquet");
+    create_test_wav(&input_path, 0.5, 16000, 1, 16)?;
+
+    // Execute command
+    let mut args = args(&input_path, &output_path, SpectrogramFormat::Parquet);
+    args.mels = Some(40);
+    audio::spectrogram::execute(args)?;
+
+    // One row per frame, with the time and one column per mel band
+    let df = ParquetReader::new(fs::File::open(&output_path)?).finish()?;
+    assert_eq!(df.height(), 31);
+    assert_eq!(df.width(), 41);
+    assert_eq!(df.get_column_names()[1].as_str(), "mel_00");
+    assert_eq!(df.get_column_names()[40].as_str(), "mel_39");
+
+    let times = df.column("time_seconds")?.f64()?;
+    assert_eq!(times.get(0), Some(0.0));
+    assert_eq!(times.get(2), Some(512.0 / 16000.0));
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_spectrogram_parquet_several_row_groups() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, long enough to need several batches of rows
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.parquet");
+    create_test_wav(&input_path, 3.0, 16000, 1, 16)?;
+
+    // Execute command
+    let mut args = args(&input_path, &output_path, SpectrogramFormat::Parquet);
+    args.n_fft = 16;
+    args.hop = 4;
+    audio::spectrogram::execute(args)?;
+
+    // Every frame is written once, in order
+    let df = ParquetReader::new(fs::File::open(&output_path)?).finish()?;
+    assert_eq!(df.height(), 11997);
+    assert_eq!(df.width(), 10);
+
+    let times = df.column("time_seconds")?.f64()?;
+    assert_eq!(times.get(4096), Some(4096.0 * 4.0 / 16000.0));
+    assert_eq!(times.get(11996), Some(11996.0 * 4.0 / 16000.0));
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_spectrogram_png_too_long() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, with more frames than an image can hold
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.png");
+    create_test_wav(&input_path, 5.0, 16000, 1, 16)?;
+
+    // Execute command
+    let mut args = args(&input_path, &output_path, SpectrogramFormat::Png);
+    args.n_fft = 2;
+    args.hop = 1;
+    assert!(audio::spectrogram::execute(args).is_err());
+
+    // Nothing is left behind
+    assert_eq!(fs::read_dir(&test_dir)?.count(), 1);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_spectrogram_directory_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir_all(input_dir.join("nested"))?;
+    create_test_wav(&input_dir.join("a.wav"), 0.5, 16000, 1, 16)?;
+    create_test_wav(&input_dir.join("nested").join("b.wav"), 0.1, 16000, 1, 16)?;
+
+    // Execute command
+    audio::spectrogram::execute(args(&input_dir, &output_dir, SpectrogramFormat::Npy))?;
+
+    // Verify the tree is mirrored with .npy files
+    let ((_, frames), _) = read_npy(&output_dir.join("a.npy"))?;
+    assert_eq!(frames, 31);
+    let ((_, frames), _) = read_npy(&output_dir.join("nested").join("b.npy"))?;
+    assert_eq!(frames, 6);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_spectrogram_invalid_settings() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 0.5, 16000, 1, 16)?;
+
+    // Output extension not matching the format
+    let output_path = test_dir.join("output.png");
+    assert!(
+        audio::spectrogram::execute(args(&input_path, &output_path, SpectrogramFormat::Npy))
+            .is_err()
+    );
+
+    // Hop larger than the frames
+    let mut invalid = args(&input_path, &output_path, SpectrogramFormat::Png);
+    invalid.hop = 1024;
+    assert!(audio::spectrogram::execute(invalid).is_err());
+
+    // No mel bands
+    let mut invalid = args(&input_path, &output_path, SpectrogramFormat::Png);
+    invalid.mels = Some(0);
+    assert!(audio::spectrogram::execute(invalid).is_err());
+
+    assert!(!output_path.exists());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
-use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use crate::utils::{
+    cleanup_test_dir, create_test_wav, create_test_wav_float, create_test_wav_with_silence,
+    setup_test_dir,
+};
 use anyhow::Result;
-use hound::WavReader;
+use hound::{SampleFormat, WavReader};
 use rush::commands::audio;
-use rush::AudioSplitArgs;
+use rush::utils::read_table;
+use rush::{AudioSplitArgs, SplitMode, SplitTail};
 use std::fs;
 
 #[test]
@@ -21,6 +25,13 @@ fn test_audio_split_file_stereo_16_success() -> Result<()> {
         output: output_dir.to_string_lossy().to_string(),
         chunk_duration: 1.0,
         delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
     };
 
     // Execute command
@@ -64,6 +75,13 @@ fn test_audio_split_file_mono_8_success() -> Result<()> {
         output: output_dir.to_string_lossy().to_string(),
         chunk_duration: 1.0,
         delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
     };
 
     // Execute command
@@ -107,6 +125,13 @@ fn test_audio_split_file_mono_32_success() -> Result<()> {
         output: output_dir.to_string_lossy().to_string(),
         chunk_duration: 1.0,
         delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
     };
 
     // Execute command
@@ -134,6 +159,145 @@ fn test_audio_split_file_mono_32_success() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_audio_split_file_mono_24_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    create_test_wav(&input_path, 5.0, 44100, 1, 24)?;
+
+    // Define args
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 1.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify output files exist and keep the input format
+    let output_files: Vec<_> = fs::read_dir(&output_dir)?
+        .filter_map(|entry| entry.ok())
+        .collect();
+
+    assert_eq!(output_files.len(), 5);
+
+    for file in output_files {
+        let reader = WavReader::open(file.path())?;
+        assert_eq!(reader.spec().bits_per_sample, 24);
+        assert_eq!(reader.spec().sample_format, SampleFormat::Int);
+        assert_eq!(reader.duration(), 44100);
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_file_stereo_float_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    create_test_wav_float(&input_path, 5.0, 44100, 2)?;
+
+    // Define args
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 1.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify output files exist and keep the input format
+    let output_files: Vec<_> = fs::read_dir(&output_dir)?
+        .filter_map(|entry| entry.ok())
+        .collect();
+
+    assert_eq!(output_files.len(), 5);
+
+    for file in output_files {
+        let reader = WavReader::open(file.path())?;
+        assert_eq!(reader.spec().bits_per_sample, 32);
+        assert_eq!(reader.spec().sample_format, SampleFormat::Float);
+        assert_eq!(reader.duration(), 44100);
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_file_partial_chunk_padded_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    create_test_wav(&input_path, 2.5, 44100, 2, 16)?;
+
+    // Define args
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 1.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify the last chunk is padded to the full duration
+    for i in 0..3 {
+        let reader = WavReader::open(output_dir.join(format!("input@{}.wav", i)))?;
+        assert_eq!(reader.duration(), 44100);
+    }
+
+    // Verify no temporary file is left behind
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 3);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
 #[test]
 fn test_audio_split_directory_success() -> Result<()> {
     // Set up the directories for testing
@@ -157,6 +321,13 @@ fn test_audio_split_directory_success() -> Result<()> {
         output: output_dir.to_string_lossy().to_string(),
         chunk_duration: 1.0,
         delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
     };
 
     // Execute command
@@ -199,6 +370,13 @@ fn test_audio_split_file_with_delete_success() -> Result<()> {
         output: output_dir.to_string_lossy().to_string(),
         chunk_duration: 1.0,
         delete_original: true,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
     };
 
     // Execute command
@@ -249,6 +427,13 @@ fn test_audio_split_directory_with_delete_success() -> Result<()> {
         output: output_dir.to_string_lossy().to_string(),
         chunk_duration: 1.0,
         delete_original: true,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
     };
 
     // Execute command
@@ -274,3 +459,275 @@ fn test_audio_split_directory_with_delete_success() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_audio_split_silence_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a file with a long pause, a short pause and trailing silence
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    let boundaries_path = test_dir.join("boundaries.csv");
+    create_test_wav_with_silence(
+        &input_path,
+        &[
+            (1.0, true),
+            (1.0, false),
+            (2.0, true),
+            (0.2, false),
+            (1.0, true),
+            (0.5, false),
+        ],
+        16000,
+    )?;
+
+    // Define args
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 10.0,
+        delete_original: false,
+        mode: SplitMode::Silence,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: Some(boundaries_path.to_string_lossy().to_string()),
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify only the long pause and the trailing silence are cut out
+    let first = WavReader::open(output_dir.join("input@0.wav"))?;
+    let second = WavReader::open(output_dir.join("input@1.wav"))?;
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 2);
+    assert_eq!(first.duration(), 16000);
+    assert_eq!(second.duration(), 51200);
+
+    // Verify the boundaries are recorded
+    let df = read_table(&boundaries_path)?.collect()?;
+    let starts: Vec<Option<f64>> = df.column("start_seconds")?.f64()?.into_iter().collect();
+    let ends: Vec<Option<f64>> = df.column("end_seconds")?.f64()?.into_iter().collect();
+    assert_eq!(starts, vec![Some(0.0), Some(2.0)]);
+    assert_eq!(ends, vec![Some(1.0), Some(5.2)]);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_silence_segment_limits_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a file with a short and a long segment
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    create_test_wav_with_silence(
+        &input_path,
+        &[(0.5, true), (1.0, false), (3.0, true)],
+        16000,
+    )?;
+
+    // Drop segments under 1 second and cut those over 2 seconds
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 2.0,
+        delete_original: false,
+        mode: SplitMode::Silence,
+        hop: None,
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 1.0,
+        boundaries: None,
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify the short segment is dropped and the long one is cut at the maximum length
+    let first = WavReader::open(output_dir.join("input@0.wav"))?;
+    let second = WavReader::open(output_dir.join("input@1.wav"))?;
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 2);
+    assert_eq!(first.duration(), 32000);
+    assert_eq!(second.duration(), 16000);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_hop_drop_tail_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    let boundaries_path = test_dir.join("boundaries.parquet");
+    create_test_wav(&input_path, 2.5, 44100, 1, 16)?;
+
+    // Define args with overlapping chunks
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 1.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: Some(0.5),
+        tail: SplitTail::Drop,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: Some(boundaries_path.to_string_lossy().to_string()),
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify the incomplete chunk is dropped
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 4);
+    let df = read_table(&boundaries_path)?.collect()?;
+    let starts: Vec<Option<f64>> = df.column("start_seconds")?.f64()?.into_iter().collect();
+    assert_eq!(starts, vec![Some(0.0), Some(0.5), Some(1.0), Some(1.5)]);
+
+    // Verify consecutive chunks overlap
+    let first: Vec<i16> = WavReader::open(output_dir.join("input@0.wav"))?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    let second: Vec<i16> = WavReader::open(output_dir.join("input@1.wav"))?
+        .samples::<i16>()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(first[22050..], second[..22050]);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_keep_tail_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    create_test_wav(&input_path, 2.5, 44100, 2, 16)?;
+
+    // Define args keeping the short tail
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 1.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: None,
+        tail: SplitTail::Keep,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify the last chunk is not padded
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 3);
+    let reader = WavReader::open(output_dir.join("input@2.wav"))?;
+    assert_eq!(reader.duration(), 22050);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_hop_pad_tail_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    let boundaries_path = test_dir.join("boundaries.parquet");
+    create_test_wav(&input_path, 2.0, 44100, 1, 16)?;
+
+    // Define args with chunks overlapping four times
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 1.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: Some(0.25),
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: Some(boundaries_path.to_string_lossy().to_string()),
+    };
+
+    // Execute command
+    audio::split::execute(args)?;
+
+    // Verify only the first chunk past the end is padded, the later ones are dropped
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 6);
+    let df = read_table(&boundaries_path)?.collect()?;
+    let starts: Vec<Option<f64>> = df.column("start_seconds")?.f64()?.into_iter().collect();
+    assert_eq!(starts.last(), Some(&Some(1.25)));
+    let reader = WavReader::open(output_dir.join("input@5.wav"))?;
+    assert_eq!(reader.duration(), 44100);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_split_hop_too_short_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_dir = test_dir.join("output");
+    create_test_wav(&input_path, 1.0, 44100, 1, 16)?;
+
+    // Define args that would keep a thousand chunks open at once
+    let args = AudioSplitArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_dir.to_string_lossy().to_string(),
+        chunk_duration: 10.0,
+        delete_original: false,
+        mode: SplitMode::Fixed,
+        hop: Some(0.01),
+        tail: SplitTail::Pad,
+        threshold: -40.0,
+        min_silence: 0.5,
+        min_segment: 0.0,
+        boundaries: None,
+    };
+
+    // Execute command and expect error
+    let result = audio::split::execute(args);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
-use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use crate::utils::{
+    cleanup_test_dir, create_test_mp3, create_test_wav, create_test_wav_with_silence,
+    setup_test_dir,
+};
 use anyhow::Result;
+use hound::{SampleFormat, WavSpec, WavWriter};
 use rush::commands::audio;
-use rush::AudioSummaryArgs;
+use rush::utils::read_table;
+use rush::{AudioSummaryArgs, OutputFormat};
 
 #[test]
 fn test_audio_summary_directory_success() -> Result<()> {
@@ -15,6 +20,11 @@ fn test_audio_summary_directory_success() -> Result<()> {
     // Define args
     let args = AudioSummaryArgs {
         target: test_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
+        analyze: false,
     };
 
     // Execute command
@@ -38,6 +48,11 @@ fn test_audio_summary_file_success() -> Result<()> {
     // Define args
     let args = AudioSummaryArgs {
         target: wav_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
+        analyze: false,
     };
 
     // Execute command
@@ -57,6 +72,11 @@ fn test_audio_summary_empty_directory_error() -> Result<()> {
     // Run summary command
     let args = AudioSummaryArgs {
         target: test_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
+        analyze: false,
     };
 
     // Execute and expect an error
@@ -81,6 +101,11 @@ fn test_audio_summary_non_existing_directory_error() -> Result<()> {
     // Run summary command
     let args = AudioSummaryArgs {
         target: test_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
+        analyze: false,
     };
 
     // Execute and expect an error
@@ -122,6 +147,11 @@ fn test_audio_summary_nested_directories_success() -> Result<()> {
     // Define args to scan the root directory
     let args = AudioSummaryArgs {
         target: test_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
+        analyze: false,
     };
 
     // Execute command
@@ -132,3 +162,218 @@ fn test_audio_summary_nested_directories_success() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_audio_summary_json_and_csv_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    create_test_wav(&test_dir.join("test1.wav"), 5.0, 44100, 1, 16)?;
+    create_test_wav(&test_dir.join("test2.wav"), 10.0, 48000, 2, 16)?;
+
+    // Execute command with every machine-readable format
+    for format in [OutputFormat::Json, OutputFormat::Csv] {
+        let args = AudioSummaryArgs {
+            target: test_dir.to_string_lossy().to_string(),
+            format,
+            manifest: None,
+            errors: None,
+            strict: false,
+            analyze: false,
+        };
+        audio::summary::execute(args)?;
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_summary_manifest_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("test1.wav"), 5.0, 44100, 1, 16)?;
+    create_test_wav(&input_dir.join("test2.wav"), 10.0, 48000, 2, 16)?;
+
+    // Write the manifest in both supported formats
+    for manifest in ["manifest.csv", "manifest.parquet"] {
+        let manifest_path = test_dir.join(manifest);
+        let args = AudioSummaryArgs {
+            target: input_dir.to_string_lossy().to_string(),
+            format: OutputFormat::Text,
+            manifest: Some(manifest_path.to_string_lossy().to_string()),
+            errors: None,
+            strict: false,
+            analyze: false,
+        };
+        audio::summary::execute(args)?;
+
+        // Verify there is one row per file
+        let df = read_table(&manifest_path)?.collect()?;
+        assert_eq!(df.height(), 2);
+        assert!(df.column("sample_rate").is_ok());
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_summary_lossy_without_bit_depth() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, the mp3 one having no bit depth
+    create_test_wav(&test_dir.join("test.wav"), 1.0, 44100, 2, 16)?;
+    create_test_mp3(&test_dir.join("test.mp3"), 100)?;
+
+    // Lossy files are readable, even in strict mode
+    let manifest_path = test_dir.join("manifest.parquet");
+    let args = AudioSummaryArgs {
+        target: test_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        errors: None,
+        strict: true,
+        analyze: false,
+    };
+    audio::summary::execute(args)?;
+
+    // Verify the bit depth is only missing for the mp3 file
+    let df = read_table(&manifest_path)?.collect()?;
+    assert_eq!(df.height(), 2);
+    let paths = df.column("path")?.str()?;
+    let bit_depths = df.column("bit_depth")?.u32()?;
+    for (path, bit_depth) in paths.into_iter().zip(bit_depths) {
+        match path.unwrap_or_default().ends_with(".mp3") {
+            true => assert_eq!(bit_depth, None),
+            false => assert_eq!(bit_depth, Some(16)),
+        }
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_summary_unreadable_files() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir(&input_dir)?;
+
+    // Create a valid and a corrupt file
+    create_test_wav(&input_dir.join("valid.wav"), 5.0, 44100, 1, 16)?;
+    std::fs::write(input_dir.join("corrupt.wav"), "not audio")?;
+
+    // Without strict mode the corrupt file is reported but not fatal
+    let errors_path = test_dir.join("errors.csv");
+    let args = AudioSummaryArgs {
+        target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: Some(errors_path.to_string_lossy().to_string()),
+        strict: false,
+        analyze: false,
+    };
+    audio::summary::execute(args)?;
+
+    // Verify the corrupt file has been recorded
+    let df = read_table(&errors_path)?.collect()?;
+    assert_eq!(df.height(), 1);
+
+    // In strict mode the corrupt file is an error
+    let args = AudioSummaryArgs {
+        target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: true,
+        analyze: false,
+    };
+    let result = audio::summary::execute(args);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_summary_analyze_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let manifest_path = test_dir.join("manifest.parquet");
+    std::fs::create_dir(&input_dir)?;
+
+    // Create a clean file, a mostly silent one and a clipped one
+    create_test_wav(&input_dir.join("a_clean.wav"), 2.0, 16000, 1, 16)?;
+    create_test_wav_with_silence(
+        &input_dir.join("b_silent.wav"),
+        &[(0.5, true), (1.5, false)],
+        16000,
+    )?;
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(input_dir.join("c_clipped.wav"), spec)?;
+    for t in 0..32000 {
+        let sample = match (t / 20) % 2 {
+            0 => i16::MAX,
+            _ => i16::MIN,
+        };
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+
+    // Execute command with the analysis
+    let args = AudioSummaryArgs {
+        target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Json,
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        errors: None,
+        strict: false,
+        analyze: true,
+    };
+    audio::summary::execute(args)?;
+
+    // Verify the statistics of every file
+    let df = read_table(&manifest_path)?
+        .sort(["path"], Default::default())
+        .collect()?;
+    let silence: Vec<Option<f64>> = df.column("silence_ratio")?.f64()?.into_iter().collect();
+    let clipping: Vec<Option<f64>> = df.column("clipping_ratio")?.f64()?.into_iter().collect();
+    let peaks: Vec<Option<f64>> = df.column("peak_dbfs")?.f64()?.into_iter().collect();
+    let flags: Vec<Option<&str>> = df.column("flags")?.str()?.into_iter().collect();
+
+    assert!((silence[1].unwrap() - 0.75).abs() < 0.01);
+    assert_eq!(clipping[0], Some(0.0));
+    assert_eq!(clipping[2], Some(1.0));
+    assert!(peaks[0].unwrap() < 0.0 && peaks[0].unwrap() > -0.01);
+
+    assert!(!flags[0].unwrap().contains("clipping"));
+    assert!(flags[1].unwrap().contains("mostly_silent"));
+    assert!(flags[2].unwrap().contains("clipping"));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
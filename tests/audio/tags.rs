@@ -0,0 +1,163 @@
+use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use anyhow::Result;
+use rush::commands::audio;
+use rush::utils::read_table;
+use rush::{AudioTagsArgs, AudioTagsCommand, AudioTagsSetArgs, AudioTagsStripArgs, TagAssignment};
+use std::path::Path;
+
+fn list_args(target: &Path, manifest: &Path) -> AudioTagsArgs {
+    AudioTagsArgs {
+        command: None,
+        target: Some(target.to_string_lossy().to_string()),
+        manifest: Some(manifest.to_string_lossy().to_string()),
+        errors: None,
+        strict: false,
+    }
+}
+
+fn set_args(target: &Path, tags: &[&str]) -> Result<AudioTagsArgs> {
+    Ok(AudioTagsArgs {
+        command: Some(AudioTagsCommand::Set(AudioTagsSetArgs {
+            target: target.to_string_lossy().to_string(),
+            tags: tags
+                .iter()
+                .map(|tag| tag.parse::<TagAssignment>())
+                .collect::<Result<_, _>>()
+                .map_err(anyhow::Error::msg)?,
+        })),
+        target: None,
+        manifest: None,
+        errors: None,
+        strict: false,
+    })
+}
+
+fn strip_args(target: &Path, keys: &[&str], pictures: bool) -> AudioTagsArgs {
+    AudioTagsArgs {
+        command: Some(AudioTagsCommand::Strip(AudioTagsStripArgs {
+            target: target.to_string_lossy().to_string(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            pictures,
+        })),
+        target: None,
+        manifest: None,
+        errors: None,
+        strict: false,
+    }
+}
+
+// Key and value pairs listed in a manifest
+fn read_manifest(path: &Path) -> Result<Vec<(String, String)>> {
+    let df = read_table(path)?.collect()?;
+    let keys = df.column("key")?.str()?;
+    let values = df.column("value")?.str()?;
+
+    Ok(keys
+        .into_iter()
+        .zip(values)
+        .map(|(key, value)| {
+            (
+                key.unwrap_or_default().to_string(),
+                value.unwrap_or_default().to_string(),
+            )
+        })
+        .collect())
+}
+
+#[test]
+fn test_audio_tags_set_and_list_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_wav(&input_dir.join("a.wav"), 0.5, 16000, 1, 16)?;
+    create_test_wav(&input_dir.join("b.wav"), 0.5, 16000, 1, 16)?;
+
+    // Untagged files have no entries
+    let manifest_path = test_dir.join("tags.csv");
+    audio::tags::execute(list_args(&input_dir, &manifest_path))?;
+    assert!(read_manifest(&manifest_path)?.is_empty());
+
+    // Tag the whole directory
+    audio::tags::execute(set_args(
+        &input_dir,
+        &["title=Interview", "artist=Jane Doe"],
+    )?)?;
+
+    // Verify the tags are listed for every file
+    audio::tags::execute(list_args(&input_dir, &manifest_path))?;
+    let entries = read_manifest(&manifest_path)?;
+    assert_eq!(entries.len(), 4);
+    assert!(entries.contains(&("title".to_string(), "Interview".to_string())));
+    assert!(entries.contains(&("artist".to_string(), "Jane Doe".to_string())));
+
+    // An empty value removes the key
+    audio::tags::execute(set_args(&input_dir.join("a.wav"), &["artist="])?)?;
+    audio::tags::execute(list_args(&input_dir.join("a.wav"), &manifest_path))?;
+    assert_eq!(
+        read_manifest(&manifest_path)?,
+        vec![("title".to_string(), "Interview".to_string())]
+    );
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_tags_strip_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    create_test_wav(&input_path, 0.5, 16000, 1, 16)?;
+    audio::tags::execute(set_args(
+        &input_path,
+        &[
+            "title=Interview",
+            "artist=Jane Doe",
+            "comment=Recorded at home",
+        ],
+    )?)?;
+
+    // Strip a single key
+    let manifest_path = test_dir.join("tags.parquet");
+    audio::tags::execute(strip_args(&input_path, &["artist"], false))?;
+    audio::tags::execute(list_args(&input_path, &manifest_path))?;
+    let entries = read_manifest(&manifest_path)?;
+    assert_eq!(entries.len(), 2);
+    assert!(!entries.iter().any(|(key, _)| key == "artist"));
+
+    // Strip everything
+    audio::tags::execute(strip_args(&input_path, &[], false))?;
+    audio::tags::execute(list_args(&input_path, &manifest_path))?;
+    assert!(read_manifest(&manifest_path)?.is_empty());
+
+    // The audio is untouched
+    let reader = hound::WavReader::open(&input_path)?;
+    assert_eq!(reader.duration(), 8000);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_tags_invalid_assignment() -> Result<()> {
+    assert!("title".parse::<TagAssignment>().is_err());
+    assert!("=value".parse::<TagAssignment>().is_err());
+    assert_eq!(
+        "comment=a=b".parse::<TagAssignment>(),
+        Ok(TagAssignment {
+            key: "comment".to_string(),
+            value: "a=b".to_string()
+        })
+    );
+
+    Ok(())
+}
@@ -1,8 +1,29 @@
-use crate::utils::{cleanup_test_dir, create_test_wav, setup_test_dir};
+use crate::utils::{cleanup_test_dir, create_test_wav, create_test_wav_float, setup_test_dir};
 use anyhow::Result;
+use clap::Parser;
+use hound::{SampleFormat, WavReader};
 use rush::commands::audio;
-use rush::AudioTrimArgs;
+use rush::{AudioTrimArgs, Timestamp};
 use std::fs;
+use std::path::Path;
+
+fn time(s: &str) -> Timestamp {
+    s.parse().unwrap()
+}
+
+fn trim_args(input: &Path, output: &Path, start: &str, end: Option<&str>) -> AudioTrimArgs {
+    AudioTrimArgs {
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        legacy_length: None,
+        legacy_offset: None,
+        start: Some(time(start)),
+        end: end.map(time),
+        duration: None,
+        clamp: false,
+        overwrite: false,
+    }
+}
 
 #[test]
 fn test_audio_trim_file_success() -> Result<()> {
@@ -18,8 +39,12 @@ fn test_audio_trim_file_success() -> Result<()> {
     let args = AudioTrimArgs {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
-        offset: 2.0,
-        length: 5.0,
+        legacy_offset: Some(2.0),
+        legacy_length: Some(5.0),
+        start: None,
+        end: None,
+        duration: None,
+        clamp: false,
         overwrite: false,
     };
 
@@ -56,8 +81,12 @@ fn test_audio_trim_directory_success() -> Result<()> {
     let args = AudioTrimArgs {
         input: input_dir.to_string_lossy().to_string(),
         output: output_dir.to_string_lossy().to_string(),
-        offset: 2.0,
-        length: 5.0,
+        legacy_offset: Some(2.0),
+        legacy_length: Some(5.0),
+        start: None,
+        end: None,
+        duration: None,
+        clamp: false,
         overwrite: false,
     };
 
@@ -88,8 +117,12 @@ fn test_audio_trim_invalid_offset_error() -> Result<()> {
     let args = AudioTrimArgs {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
-        offset: 6.0,
-        length: 2.0,
+        legacy_offset: Some(6.0),
+        legacy_length: Some(2.0),
+        start: None,
+        end: None,
+        duration: None,
+        clamp: false,
         overwrite: false,
     };
 
@@ -117,8 +150,12 @@ fn test_audio_trim_invalid_length_error() -> Result<()> {
     let args = AudioTrimArgs {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
-        offset: 2.0,
-        length: 4.0,
+        legacy_offset: Some(2.0),
+        legacy_length: Some(4.0),
+        start: None,
+        end: None,
+        duration: None,
+        clamp: false,
         overwrite: false,
     };
 
@@ -145,8 +182,12 @@ fn test_audio_trim_overwrite_protection_error() -> Result<()> {
     let args = AudioTrimArgs {
         input: input_path.to_string_lossy().to_string(),
         output: input_path.to_string_lossy().to_string(),
-        offset: 2.0,
-        length: 5.0,
+        legacy_offset: Some(2.0),
+        legacy_length: Some(5.0),
+        start: None,
+        end: None,
+        duration: None,
+        clamp: false,
         overwrite: false,
     };
 
@@ -173,8 +214,12 @@ fn test_audio_trim_overwrite_success() -> Result<()> {
     let args = AudioTrimArgs {
         input: input_path.to_string_lossy().to_string(),
         output: input_path.to_string_lossy().to_string(),
-        offset: 2.0,
-        length: 5.0,
+        legacy_offset: Some(2.0),
+        legacy_length: Some(5.0),
+        start: None,
+        end: None,
+        duration: None,
+        clamp: false,
         overwrite: true,
     };
 
@@ -189,3 +234,287 @@ fn test_audio_trim_overwrite_success() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_audio_trim_24bit_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 5.0, 44100, 2, 24)?;
+
+    // Define args
+    let args = AudioTrimArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        legacy_length: None,
+        legacy_offset: None,
+        start: Some(time("1.0")),
+        end: None,
+        duration: Some(time("2.0")),
+        clamp: false,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::trim::execute(args)?;
+
+    // Verify output file keeps the input format and has the right length
+    let reader = WavReader::open(output_path)?;
+    assert_eq!(reader.spec().bits_per_sample, 24);
+    assert_eq!(reader.spec().sample_format, SampleFormat::Int);
+    assert_eq!(reader.duration(), 88200);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_float_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav_float(&input_path, 5.0, 44100, 1)?;
+
+    // Define args
+    let args = AudioTrimArgs {
+        input: input_path.to_string_lossy().to_string(),
+        output: output_path.to_string_lossy().to_string(),
+        legacy_length: None,
+        legacy_offset: None,
+        start: Some(time("1.0")),
+        end: None,
+        duration: Some(time("2.0")),
+        clamp: false,
+        overwrite: false,
+    };
+
+    // Execute command
+    audio::trim::execute(args)?;
+
+    // Verify output file keeps the input format and has the right length
+    let reader = WavReader::open(output_path)?;
+    assert_eq!(reader.spec().bits_per_sample, 32);
+    assert_eq!(reader.spec().sample_format, SampleFormat::Float);
+    assert_eq!(reader.duration(), 88200);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_timestamps_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 90.0, 8000, 1, 16)?;
+
+    // Execute command with clock times
+    audio::trim::execute(trim_args(
+        &input_path,
+        &output_path,
+        "00:01:00.5",
+        Some("1:02.25"),
+    ))?;
+
+    // Verify the output has exactly 1.75 seconds
+    assert_eq!(WavReader::open(&output_path)?.duration(), 14000);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_from_end_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 5.0, 44100, 2, 16)?;
+
+    // Keep the last 1.5 seconds but the last 0.5
+    audio::trim::execute(trim_args(&input_path, &output_path, "-1.5", Some("-0.5")))?;
+    assert_eq!(WavReader::open(&output_path)?.duration(), 44100);
+
+    // Keep the last 2 seconds, up to the end of the file
+    audio::trim::execute(trim_args(&input_path, &output_path, "-2", None))?;
+    assert_eq!(WavReader::open(&output_path)?.duration(), 88200);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_sample_exact_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 1.0, 44100, 1, 16)?;
+
+    // 0.3 seconds is exactly 13230 frames
+    audio::trim::execute(trim_args(&input_path, &output_path, "0.1", Some("0.4")))?;
+    assert_eq!(WavReader::open(&output_path)?.duration(), 13230);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_clamp_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 2.0, 8000, 1, 16)?;
+
+    // The range exceeds the file on both sides
+    let mut args = trim_args(&input_path, &output_path, "-5", Some("10"));
+    assert!(audio::trim::execute(args).is_err());
+
+    // Execute command trimming to what is available
+    args = trim_args(&input_path, &output_path, "-5", Some("10"));
+    args.clamp = true;
+    audio::trim::execute(args)?;
+    assert_eq!(WavReader::open(&output_path)?.duration(), 16000);
+
+    // A start past the end gives an empty file
+    args = trim_args(&input_path, &output_path, "3", None);
+    args.clamp = true;
+    audio::trim::execute(args)?;
+    assert_eq!(WavReader::open(&output_path)?.duration(), 0);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_end_before_start_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 2.0, 8000, 1, 16)?;
+
+    // Execute command and expect error
+    let result = audio::trim::execute(trim_args(&input_path, &output_path, "1.5", Some("0.5")));
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_timestamp_parsing() -> Result<()> {
+    assert_eq!(time("83.5"), time("00:01:23.500"));
+    assert_eq!(time("1:23.5"), time("83.5"));
+    assert_eq!(
+        time("-0.25"),
+        Timestamp {
+            from_end: true,
+            nanoseconds: 250_000_000
+        }
+    );
+    assert_eq!(time("0.000001").frames(48000), 0);
+    assert_eq!(time("0.00002").frames(48000), 1);
+
+    // Invalid timestamps
+    for s in [
+        "",
+        "abc",
+        "1:60",
+        "1:2:3:4",
+        "1.2.3",
+        "-",
+        "0.1234567891",
+        "1:-2",
+    ] {
+        assert!(s.parse::<Timestamp>().is_err(), "{} should be invalid", s);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_legacy_arguments_parsing() -> Result<()> {
+    // Former form, with the length before the output
+    let args = AudioTrimArgs::try_parse_from(["trim", "in.wav", "5", "out.wav", "--offset", "2"])?;
+    assert_eq!(args.input, "in.wav");
+    assert_eq!(args.legacy_length, Some(5.0));
+    assert_eq!(args.output, "out.wav");
+    assert_eq!(args.legacy_offset, Some(2.0));
+
+    // Current form, with input and output only
+    let args = AudioTrimArgs::try_parse_from(["trim", "in.wav", "out.wav", "--length", "5"])?;
+    assert_eq!(args.output, "out.wav");
+    assert_eq!(args.legacy_length, None);
+    assert_eq!(args.duration, Some(time("5")));
+
+    // Both forms at once
+    for option in ["--start", "--end", "--length"] {
+        let result = AudioTrimArgs::try_parse_from(["trim", "in.wav", "5", "out.wav", option, "1"]);
+        assert!(result.is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_trim_legacy_arguments_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.wav");
+    let output_path = test_dir.join("output.wav");
+    create_test_wav(&input_path, 5.0, 44100, 2, 16)?;
+
+    // Execute command as `rush audio trim input.wav 2 output.wav --offset 1`
+    let args = AudioTrimArgs::try_parse_from([
+        "trim".to_string(),
+        input_path.to_string_lossy().to_string(),
+        "2".to_string(),
+        output_path.to_string_lossy().to_string(),
+        "--offset".to_string(),
+        "1".to_string(),
+    ])?;
+    audio::trim::execute(args)?;
+
+    // Verify the output has the requested length
+    assert_eq!(WavReader::open(&output_path)?.duration(), 88200);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use crate::utils::{cleanup_test_dir, create_test_wav_with_silence, setup_test_dir};
+use anyhow::Result;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rush::commands::audio;
+use rush::utils::read_table;
+use rush::{AudioVadArgs, OutputFormat};
+use std::path::Path;
+
+fn args(target: &Path) -> AudioVadArgs {
+    AudioVadArgs {
+        target: target.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        segments: None,
+        threshold: -40.0,
+        min_speech: 0.1,
+        min_silence: 0.3,
+        errors: None,
+        strict: false,
+    }
+}
+
+#[test]
+fn test_audio_vad_segments_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, with a short pause inside the first segment
+    let input_path = test_dir.join("input.wav");
+    create_test_wav_with_silence(
+        &input_path,
+        &[
+            (0.5, false),
+            (1.0, true),
+            (0.2, false),
+            (0.5, true),
+            (1.0, false),
+            (0.6, true),
+            (0.5, false),
+        ],
+        16000,
+    )?;
+
+    // Execute command, writing both tables
+    let manifest_path = test_dir.join("manifest.csv");
+    let segments_path = test_dir.join("segments.parquet");
+    let mut args = args(&input_path);
+    args.manifest = Some(manifest_path.to_string_lossy().to_string());
+    args.segments = Some(segments_path.to_string_lossy().to_string());
+    audio::vad::execute(args)?;
+
+    // Verify the segments
+    let segments = read_table(&segments_path)?.collect()?;
+    assert_eq!(segments.height(), 2);
+    let starts = segments.column("start_seconds")?.f64()?;
+    let ends = segments.column("end_seconds")?.f64()?;
+    let expected = [(0.5, 2.2), (3.2, 3.8)];
+    for (i, (start, end)) in expected.into_iter().enumerate() {
+        assert!((starts.get(i).unwrap() - start).abs() < 1e-6);
+        assert!((ends.get(i).unwrap() - end).abs() < 1e-6);
+    }
+
+    // Verify the per-file ratio
+    let manifest = read_table(&manifest_path)?.collect()?;
+    assert_eq!(manifest.height(), 1);
+    let ratio = manifest.column("speech_ratio")?.f64()?.get(0).unwrap();
+    assert!((ratio - 2.3 / 4.3).abs() < 1e-6);
+    assert_eq!(manifest.column("segments")?.i64()?.get(0), Some(2));
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_vad_min_speech() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files with a click too short to be speech
+    let input_path = test_dir.join("input.wav");
+    create_test_wav_with_silence(
+        &input_path,
+        &[(0.5, false), (0.06, true), (0.5, false), (0.3, true)],
+        16000,
+    )?;
+
+    // Execute command
+    let segments_path = test_dir.join("segments.csv");
+    let mut args = args(&input_path);
+    args.segments = Some(segments_path.to_string_lossy().to_string());
+    audio::vad::execute(args)?;
+
+    // Only the longer tone is kept
+    let segments = read_table(&segments_path)?.collect()?;
+    assert_eq!(segments.height(), 1);
+    let start = segments.column("start_seconds")?.f64()?.get(0).unwrap();
+    assert!((start - 1.06).abs() < 1e-6);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_vad_unvoiced_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Quiet tone followed by equally quiet noise-like signal, both below the threshold
+    let input_path = test_dir.join("input.wav");
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(&input_path, spec)?;
+    for t in 0..16000 {
+        let sample = (t as f32 * 440.0 * 2.0 * std::f32::consts::PI / 16000.0).sin() * 0.007;
+        writer.write_sample((sample * i16::MAX as f32) as i16)?;
+    }
+    for t in 0..16000 {
+        let sample = if t % 2 == 0 { 0.005 } else { -0.005 };
+        writer.write_sample((sample * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+
+    // Execute command
+    let segments_path = test_dir.join("segments.csv");
+    let mut args = args(&input_path);
+    args.segments = Some(segments_path.to_string_lossy().to_string());
+    audio::vad::execute(args)?;
+
+    // Only the frequently crossing part counts as (unvoiced) speech
+    let segments = read_table(&segments_path)?.collect()?;
+    assert_eq!(segments.height(), 1);
+    let start = segments.column("start_seconds")?.f64()?.get(0).unwrap();
+    let end = segments.column("end_seconds")?.f64()?.get(0).unwrap();
+    assert!((start - 1.0).abs() < 1e-6);
+    assert!((end - 2.0).abs() < 1e-6);
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audio_vad_unreadable_files() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    std::fs::create_dir(&input_dir)?;
+
+    // Create a valid and a corrupt file
+    create_test_wav_with_silence(&input_dir.join("valid.wav"), &[(0.5, true)], 16000)?;
+    std::fs::write(input_dir.join("corrupt.wav"), "not audio")?;
+
+    // Without strict mode the corrupt file is reported but not fatal
+    let manifest_path = test_dir.join("manifest.csv");
+    let mut lenient = args(&input_dir);
+    lenient.manifest = Some(manifest_path.to_string_lossy().to_string());
+    audio::vad::execute(lenient)?;
+    assert_eq!(read_table(&manifest_path)?.collect()?.height(), 1);
+
+    // In strict mode it is an error
+    let mut strict = args(&input_dir);
+    strict.strict = true;
+    assert!(audio::vad::execute(strict).is_err());
+
+    // Missing target
+    assert!(audio::vad::execute(args(&test_dir.join("missing"))).is_err());
+
+    // Clean up
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
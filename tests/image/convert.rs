@@ -0,0 +1,324 @@
+use crate::utils::{cleanup_test_dir, create_test_image, create_test_waves, setup_test_dir};
+use ::image::{Rgba, RgbaImage};
+use anyhow::Result;
+use rush::commands::image;
+use rush::utils::image_metadata::ImageMetadata;
+use rush::{Color, ImageCodec, ImageConvertArgs};
+use std::fs;
+use std::path::Path;
+
+fn convert_args(input: &Path, output: &Path, to: ImageCodec) -> ImageConvertArgs {
+    ImageConvertArgs {
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        to,
+        quality: None,
+        background: Color([255, 255, 255, 255]),
+        strip_metadata: false,
+        overwrite: false,
+    }
+}
+
+// Sample metadata, which only has to survive the conversion unchanged
+fn test_metadata() -> ImageMetadata {
+    ImageMetadata {
+        exif: Some(b"MM\0*\0\0\0\x08\0\0".to_vec()),
+        icc_profile: Some((0..=255).cycle().take(3000).collect()),
+    }
+}
+
+#[test]
+fn test_image_convert_file_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.jpg");
+    create_test_image(&input_path, 64, 48, 3)?;
+
+    // Execute command
+    image::convert::execute(convert_args(&input_path, &output_path, ImageCodec::Jpeg))?;
+
+    // Verify the output is a JPEG of the same size
+    let output_img = ::image::open(&output_path)?;
+    assert_eq!(
+        ::image::ImageFormat::from_path(&output_path)?,
+        ::image::ImageFormat::Jpeg
+    );
+    assert_eq!((output_img.width(), output_img.height()), (64, 48));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_directory_swaps_extensions() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    let nested_dir = input_dir.join("nested");
+    fs::create_dir_all(&nested_dir)?;
+
+    // Create test files of several formats in a nested structure
+    create_test_image(&input_dir.join("test1.png"), 32, 32, 1)?;
+    create_test_image(&nested_dir.join("test2.jpg"), 32, 32, 3)?;
+    create_test_image(&nested_dir.join("test3.bmp"), 32, 32, 3)?;
+
+    // Execute command
+    image::convert::execute(convert_args(&input_dir, &output_dir, ImageCodec::Webp))?;
+
+    // Verify the output tree mirrors the input with the new extension
+    for path in ["test1.webp", "nested/test2.webp", "nested/test3.webp"] {
+        let output_img = ::image::open(output_dir.join(path))?;
+        assert_eq!((output_img.width(), output_img.height()), (32, 32));
+    }
+    assert!(!output_dir.join("test1.png").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_flattens_alpha_for_jpeg() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create an image opaque red on the left and transparent on the right
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.jpg");
+    RgbaImage::from_fn(64, 64, |x, _| match x < 32 {
+        true => Rgba([255, 0, 0, 255]),
+        false => Rgba([0, 0, 0, 0]),
+    })
+    .save(&input_path)?;
+
+    // Flatten it over blue
+    let args = ImageConvertArgs {
+        background: Color([0, 0, 255, 255]),
+        ..convert_args(&input_path, &output_path, ImageCodec::Jpeg)
+    };
+    image::convert::execute(args)?;
+
+    // Verify the transparent half became the background, not black
+    let output_img = ::image::open(&output_path)?.to_rgb8();
+    let close = |a: [u8; 3], b: [u8; 3]| a.iter().zip(b).all(|(x, y)| x.abs_diff(y) <= 8);
+    assert!(close(output_img.get_pixel(8, 32).0, [255, 0, 0]));
+    assert!(close(output_img.get_pixel(56, 32).0, [0, 0, 255]));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_keeps_metadata() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a PNG with metadata
+    let input_path = test_dir.join("input.png");
+    let jpeg_path = test_dir.join("output.jpg");
+    let png_path = test_dir.join("output.png");
+    create_test_image(&input_path, 32, 32, 3)?;
+    let metadata = test_metadata();
+    fs::write(&input_path, metadata.embed(fs::read(&input_path)?)?)?;
+
+    // Convert it to JPEG, then back to PNG
+    image::convert::execute(convert_args(&input_path, &jpeg_path, ImageCodec::Jpeg))?;
+    image::convert::execute(convert_args(&jpeg_path, &png_path, ImageCodec::Png))?;
+
+    // Verify the metadata went through both conversions
+    assert_eq!(ImageMetadata::read(&fs::read(&jpeg_path)?), metadata);
+    assert_eq!(ImageMetadata::read(&fs::read(&png_path)?), metadata);
+    ::image::open(&jpeg_path)?;
+    ::image::open(&png_path)?;
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_strip_metadata() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a PNG with metadata
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.jpg");
+    create_test_image(&input_path, 32, 32, 3)?;
+    fs::write(&input_path, test_metadata().embed(fs::read(&input_path)?)?)?;
+
+    // Convert it without its metadata
+    let args = ImageConvertArgs {
+        strip_metadata: true,
+        ..convert_args(&input_path, &output_path, ImageCodec::Jpeg)
+    };
+    image::convert::execute(args)?;
+
+    // Verify nothing was carried over
+    assert!(ImageMetadata::read(&fs::read(&output_path)?).is_empty());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_quality_changes_size() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a detailed image
+    let input_path = test_dir.join("input.png");
+    let low_path = test_dir.join("low.jpg");
+    let high_path = test_dir.join("high.jpg");
+    create_test_waves(&input_path, 128, 128)?;
+
+    // Encode it at two qualities
+    for (path, quality) in [(&low_path, 10), (&high_path, 95)] {
+        let args = ImageConvertArgs {
+            quality: Some(quality),
+            ..convert_args(&input_path, path, ImageCodec::Jpeg)
+        };
+        image::convert::execute(args)?;
+    }
+
+    // Verify the lower quality gives a smaller file
+    assert!(fs::metadata(&low_path)?.len() < fs::metadata(&high_path)?.len());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_quality_without_jpeg_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.jpg");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 32, 32, 3)?;
+
+    // Ask for a quality with a lossless format
+    let args = ImageConvertArgs {
+        quality: Some(80),
+        ..convert_args(&input_path, &output_path, ImageCodec::Png)
+    };
+
+    // Execute command and expect error
+    assert!(image::convert::execute(args).is_err());
+    assert!(!output_path.exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_wrong_output_extension_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 32, 32, 3)?;
+
+    // Execute command and expect error, as the output isn't named like a TIFF
+    let result = image::convert::execute(convert_args(&input_path, &output_path, ImageCodec::Tiff));
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_overwrite_protection_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.png");
+    create_test_image(&input_path, 32, 32, 3)?;
+
+    // Try to overwrite input file without overwrite flag
+    let result = image::convert::execute(convert_args(&input_path, &input_path, ImageCodec::Png));
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_colliding_outputs_error() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // Two images only differing by their extension
+    create_test_image(&input_dir.join("a.png"), 32, 32, 3)?;
+    create_test_image(&input_dir.join("a.jpg"), 16, 16, 3)?;
+
+    // Execute command and expect error, as both would be written to a.webp
+    let result = image::convert::execute(convert_args(&input_dir, &output_dir, ImageCodec::Webp));
+    assert!(result.is_err());
+    assert!(!output_dir.join("a.webp").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_convert_in_place_directory() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files, one of them already in the output format
+    let png_path = test_dir.join("a.png");
+    let jpg_path = test_dir.join("b.jpg");
+    create_test_image(&png_path, 32, 32, 3)?;
+    create_test_image(&jpg_path, 32, 32, 3)?;
+    let original = fs::read(&png_path)?;
+
+    // Execute command and expect error, as a.png would be replaced
+    let result = image::convert::execute(convert_args(&test_dir, &test_dir, ImageCodec::Png));
+    assert!(result.is_err());
+    assert_eq!(fs::read(&png_path)?, original);
+    assert!(!test_dir.join("b.png").exists());
+
+    // Execute command allowing the inputs to be replaced
+    let args = ImageConvertArgs {
+        overwrite: true,
+        ..convert_args(&test_dir, &test_dir, ImageCodec::Png)
+    };
+    image::convert::execute(args)?;
+    assert!(test_dir.join("b.png").exists());
+    assert!(jpg_path.exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -1,7 +1,64 @@
-use crate::utils::{cleanup_test_dir, create_test_image, setup_test_dir};
+use crate::utils::{
+    cleanup_test_dir, create_test_checkerboard, create_test_image, create_test_waves,
+    setup_test_dir,
+};
 use anyhow::Result;
 use rush::commands::image;
-use rush::ImageDuplicatesArgs;
+use rush::utils::read_table;
+use rush::{DuplicateAction, ImageDuplicatesArgs, ImageHashMethod, KeepPolicy};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn duplicates_args(target: &Path) -> ImageDuplicatesArgs {
+    ImageDuplicatesArgs {
+        targets: vec![target.to_string_lossy().to_string()],
+        recursive: false,
+        method: ImageHashMethod::Sha256,
+        threshold: None,
+        manifest: None,
+        keep: KeepPolicy::LargestResolution,
+        action: DuplicateAction::Report,
+        move_to: None,
+        apply: false,
+    }
+}
+
+// Groups, file names and representative flags listed in a manifest
+fn read_manifest(path: &Path) -> Result<Vec<(u32, String, bool)>> {
+    let df = read_table(path)?.collect()?;
+    let groups = df.column("group")?.u32()?;
+    let paths = df.column("path")?.str()?;
+    let representatives = df.column("representative")?.bool()?;
+
+    Ok(groups
+        .into_iter()
+        .zip(paths)
+        .zip(representatives)
+        .map(|((group, path), representative)| {
+            let name = Path::new(path.unwrap_or_default())
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (
+                group.unwrap_or_default(),
+                name,
+                representative.unwrap_or_default(),
+            )
+        })
+        .collect())
+}
+
+// 8x8 grayscale image whose first pixels are white and the others black, so that its
+// average hash has exactly these bits set
+fn create_bright_pixels_image(path: &Path, bright: u32) -> Result<()> {
+    let image = ::image::GrayImage::from_fn(8, 8, |x, y| match y * 8 + x < bright {
+        true => ::image::Luma([255]),
+        false => ::image::Luma([0]),
+    });
+    image.save(path)?;
+    Ok(())
+}
 
 #[test]
 fn test_image_duplicates_success() -> Result<()> {
@@ -20,9 +77,7 @@ fn test_image_duplicates_success() -> Result<()> {
     create_test_image(&image_path3, 200, 200, 3)?;
 
     // Define args
-    let args = ImageDuplicatesArgs {
-        target: test_dir.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&test_dir);
 
     // Execute command
     image::duplicates::execute(args)?;
@@ -40,9 +95,7 @@ fn test_image_duplicates_nonexistent_path() -> Result<()> {
     let nonexistent_path = test_dir.join("nonexistent");
 
     // Define args with nonexistent path
-    let args = ImageDuplicatesArgs {
-        target: nonexistent_path.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&nonexistent_path);
 
     // Execute command and expect error
     let result = image::duplicates::execute(args);
@@ -64,9 +117,7 @@ fn test_image_duplicates_not_a_directory() -> Result<()> {
     create_test_image(&image_path, 100, 100, 3)?;
 
     // Define args with a file path instead of directory
-    let args = ImageDuplicatesArgs {
-        target: image_path.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&image_path);
 
     // Execute command and expect error
     let result = image::duplicates::execute(args);
@@ -88,9 +139,7 @@ fn test_image_duplicates_insufficient_files() -> Result<()> {
     create_test_image(&image_path, 100, 100, 3)?;
 
     // Define args
-    let args = ImageDuplicatesArgs {
-        target: test_dir.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&test_dir);
 
     // Execute command and expect error (needs at least 2 files)
     let result = image::duplicates::execute(args);
@@ -118,13 +167,431 @@ fn test_image_duplicates_multiple_formats() -> Result<()> {
     create_test_image(&image_path3, 100, 100, 3)?;
 
     // Define args
+    let args = duplicates_args(&test_dir);
+
+    // Execute command
+    image::duplicates::execute(args)?;
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_perceptual_methods_group_resized_copies() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // An image, a smaller recompressed copy of it and an unrelated image
+    create_test_waves(&test_dir.join("original.png"), 200, 200)?;
+    ::image::open(test_dir.join("original.png"))?
+        .resize_exact(120, 120, ::image::imageops::FilterType::Triangle)
+        .save(test_dir.join("copy.jpg"))?;
+    create_test_checkerboard(&test_dir.join("other.png"), 200, 200, 25)?;
+
+    for method in [
+        ImageHashMethod::Ahash,
+        ImageHashMethod::Dhash,
+        ImageHashMethod::Phash,
+    ] {
+        // Define args
+        let manifest_path = test_dir.join(format!("{:?}.parquet", method));
+        let args = ImageDuplicatesArgs {
+            method,
+            manifest: Some(manifest_path.to_string_lossy().to_string()),
+            ..duplicates_args(&test_dir)
+        };
+
+        // Execute command
+        image::duplicates::execute(args)?;
+
+        // The copy is grouped with the larger original, which represents the group
+        assert_eq!(
+            read_manifest(&manifest_path)?,
+            vec![
+                (0, "original.png".to_string(), true),
+                (0, "copy.jpg".to_string(), false),
+            ],
+            "{:?}",
+            method
+        );
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_sha256_ignores_resized_copies() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Two identical images and a smaller copy of them
+    create_test_image(&test_dir.join("image1.png"), 100, 100, 3)?;
+    create_test_image(&test_dir.join("image2.png"), 100, 100, 3)?;
+    create_test_image(&test_dir.join("small.png"), 50, 50, 3)?;
+
+    // Define args
+    let manifest_path = test_dir.join("duplicates.parquet");
     let args = ImageDuplicatesArgs {
-        target: test_dir.to_string_lossy().to_string(),
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        ..duplicates_args(&test_dir)
     };
 
     // Execute command
     image::duplicates::execute(args)?;
 
+    // Only the exact copies are grouped
+    assert_eq!(
+        read_manifest(&manifest_path)?,
+        vec![
+            (0, "image1.png".to_string(), true),
+            (0, "image2.png".to_string(), false),
+        ]
+    );
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_invalid_threshold() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    create_test_image(&test_dir.join("image1.png"), 100, 100, 3)?;
+    create_test_image(&test_dir.join("image2.png"), 100, 100, 3)?;
+
+    // A threshold doesn't apply to SHA-256
+    let args = ImageDuplicatesArgs {
+        threshold: Some(5),
+        ..duplicates_args(&test_dir)
+    };
+    assert!(image::duplicates::execute(args).is_err());
+
+    // Nor can it exceed the size of the hash
+    let args = ImageDuplicatesArgs {
+        method: ImageHashMethod::Phash,
+        threshold: Some(65),
+        ..duplicates_args(&test_dir)
+    };
+    assert!(image::duplicates::execute(args).is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_recursive_across_targets() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let train_dir = test_dir.join("train");
+    let test_split_dir = test_dir.join("test");
+    fs::create_dir_all(train_dir.join("nested"))?;
+    fs::create_dir_all(&test_split_dir)?;
+
+    // The same image in both splits, and in a subdirectory of one of them
+    create_test_image(&train_dir.join("a.png"), 100, 100, 3)?;
+    create_test_image(&train_dir.join("nested/b.png"), 100, 100, 3)?;
+    create_test_image(&test_split_dir.join("c.png"), 100, 100, 3)?;
+    let targets = vec![
+        train_dir.to_string_lossy().to_string(),
+        test_split_dir.to_string_lossy().to_string(),
+    ];
+    let manifest_path = test_dir.join("duplicates.parquet");
+
+    // Only the top of both targets
+    let args = ImageDuplicatesArgs {
+        targets: targets.clone(),
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        ..duplicates_args(&test_dir)
+    };
+    image::duplicates::execute(args)?;
+    assert_eq!(
+        read_manifest(&manifest_path)?,
+        vec![
+            (0, "c.png".to_string(), true),
+            (0, "a.png".to_string(), false),
+        ]
+    );
+
+    // Subdirectories included
+    let args = ImageDuplicatesArgs {
+        targets,
+        recursive: true,
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        ..duplicates_args(&test_dir)
+    };
+    image::duplicates::execute(args)?;
+    assert_eq!(
+        read_manifest(&manifest_path)?,
+        vec![
+            (0, "c.png".to_string(), true),
+            (0, "a.png".to_string(), false),
+            (0, "b.png".to_string(), false),
+        ]
+    );
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_delete_is_dry_run_by_default() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    fs::create_dir_all(input_dir.join("nested"))?;
+
+    // Two copies of an image
+    create_test_image(&input_dir.join("image.png"), 100, 100, 3)?;
+    create_test_image(&input_dir.join("nested/copy.png"), 100, 100, 3)?;
+
+    // Without applying, nothing is deleted
+    let args = ImageDuplicatesArgs {
+        recursive: true,
+        keep: KeepPolicy::ShortestPath,
+        action: DuplicateAction::Delete,
+        ..duplicates_args(&input_dir)
+    };
+    image::duplicates::execute(args)?;
+    assert!(input_dir.join("nested/copy.png").exists());
+
+    // Applying deletes all but the shortest path
+    let args = ImageDuplicatesArgs {
+        recursive: true,
+        keep: KeepPolicy::ShortestPath,
+        action: DuplicateAction::Delete,
+        apply: true,
+        ..duplicates_args(&input_dir)
+    };
+    image::duplicates::execute(args)?;
+    assert!(input_dir.join("image.png").exists());
+    assert!(!input_dir.join("nested/copy.png").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_hardlink_keeps_oldest() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Two copies of an image, the second one being older
+    let newer_path = test_dir.join("a.png");
+    let older_path = test_dir.join("b.png");
+    create_test_image(&newer_path, 100, 100, 3)?;
+    create_test_image(&older_path, 100, 100, 3)?;
+    fs::File::options()
+        .write(true)
+        .open(&older_path)?
+        .set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+
+    // Link the newer copy to the older one
+    let args = ImageDuplicatesArgs {
+        keep: KeepPolicy::Oldest,
+        action: DuplicateAction::Hardlink,
+        apply: true,
+        ..duplicates_args(&test_dir)
+    };
+    image::duplicates::execute(args)?;
+
+    // Both paths are left, and changing one changes the other
+    assert!(newer_path.exists());
+    fs::write(&older_path, b"changed")?;
+    assert_eq!(fs::read(&newer_path)?, b"changed");
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_hardlink_perceptual_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Two images close to each other, without being copies
+    create_bright_pixels_image(&test_dir.join("a.png"), 20)?;
+    create_bright_pixels_image(&test_dir.join("b.png"), 22)?;
+
+    // Execute command and expect error
+    let args = ImageDuplicatesArgs {
+        method: ImageHashMethod::Ahash,
+        action: DuplicateAction::Hardlink,
+        apply: true,
+        ..duplicates_args(&test_dir)
+    };
+    assert!(image::duplicates::execute(args).is_err());
+
+    // Verify the images were left as they are
+    assert_ne!(
+        fs::read(test_dir.join("a.png"))?,
+        fs::read(test_dir.join("b.png"))?
+    );
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_delete_only_within_threshold() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Images 6 bits apart from their neighbours, the last one 12 bits from the first
+    create_bright_pixels_image(&test_dir.join("a.png"), 20)?;
+    create_bright_pixels_image(&test_dir.join("b.png"), 26)?;
+    create_bright_pixels_image(&test_dir.join("c.png"), 32)?;
+
+    // Chained images are grouped, the first path being kept
+    let manifest_path = test_dir.join("manifest.parquet");
+    let args = ImageDuplicatesArgs {
+        method: ImageHashMethod::Ahash,
+        threshold: Some(10),
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        keep: KeepPolicy::ShortestPath,
+        action: DuplicateAction::Delete,
+        apply: true,
+        ..duplicates_args(&test_dir)
+    };
+    image::duplicates::execute(args)?;
+    assert_eq!(read_manifest(&manifest_path)?.len(), 3);
+
+    // Only the image within the threshold of the kept one is deleted
+    assert!(test_dir.join("a.png").exists());
+    assert!(!test_dir.join("b.png").exists());
+    assert!(test_dir.join("c.png").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_move_mirrors_layout() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let train_dir = test_dir.join("splits/train");
+    let test_split_dir = test_dir.join("splits/test");
+    let move_dir = test_dir.join("moved");
+    fs::create_dir_all(&train_dir)?;
+    fs::create_dir_all(&test_split_dir)?;
+
+    // A large image in the train split leaked into the test split at a smaller size
+    create_test_waves(&train_dir.join("large.png"), 200, 200)?;
+    ::image::open(train_dir.join("large.png"))?
+        .resize_exact(100, 100, ::image::imageops::FilterType::Triangle)
+        .save(test_split_dir.join("small.png"))?;
+
+    // Move the smaller copy away
+    let args = ImageDuplicatesArgs {
+        targets: vec![
+            train_dir.to_string_lossy().to_string(),
+            test_split_dir.to_string_lossy().to_string(),
+        ],
+        method: ImageHashMethod::Dhash,
+        action: DuplicateAction::Move,
+        move_to: Some(move_dir.to_string_lossy().to_string()),
+        apply: true,
+        ..duplicates_args(&test_dir)
+    };
+    image::duplicates::execute(args)?;
+
+    // It keeps its path relative to the directory holding both splits
+    assert!(train_dir.join("large.png").exists());
+    assert!(!test_split_dir.join("small.png").exists());
+    assert!(move_dir.join("test/small.png").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_move_without_directory_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    create_test_image(&test_dir.join("image1.png"), 100, 100, 3)?;
+    create_test_image(&test_dir.join("image2.png"), 100, 100, 3)?;
+
+    // Moving requires a directory
+    let args = ImageDuplicatesArgs {
+        action: DuplicateAction::Move,
+        apply: true,
+        ..duplicates_args(&test_dir)
+    };
+    assert!(image::duplicates::execute(args).is_err());
+
+    // Both files are left
+    assert!(test_dir.join("image1.png").exists());
+    assert!(test_dir.join("image2.png").exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_duplicates_same_size_different_middle() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Uncompressed images large enough for their first and last bytes not to cover them
+    let image_path = test_dir.join("a.bmp");
+    create_test_image(&image_path, 300, 300, 3)?;
+    fs::copy(&image_path, test_dir.join("b.bmp"))?;
+
+    // A copy of the same size, only differing by a pixel in the middle
+    let mut altered = ::image::open(&image_path)?.to_rgb8();
+    altered.put_pixel(150, 150, ::image::Rgb([255, 255, 255]));
+    altered.save(test_dir.join("c.bmp"))?;
+    assert_eq!(
+        fs::metadata(&image_path)?.len(),
+        fs::metadata(test_dir.join("c.bmp"))?.len()
+    );
+
+    for method in [ImageHashMethod::Sha256, ImageHashMethod::Xxh3] {
+        // Define args
+        let manifest_path = test_dir.join(format!("{:?}.parquet", method));
+        let args = ImageDuplicatesArgs {
+            method,
+            manifest: Some(manifest_path.to_string_lossy().to_string()),
+            ..duplicates_args(&test_dir)
+        };
+
+        // Execute command
+        image::duplicates::execute(args)?;
+
+        // Only the exact copy is grouped
+        assert_eq!(
+            read_manifest(&manifest_path)?,
+            vec![
+                (0, "a.bmp".to_string(), true),
+                (0, "b.bmp".to_string(), false),
+            ],
+            "{:?}",
+            method
+        );
+    }
+
     // Clean up dir
     cleanup_test_dir(&test_dir)?;
 
@@ -1,3 +1,4 @@
+mod convert;
 mod duplicates;
 mod resize;
 mod summary;
@@ -1,8 +1,25 @@
 use crate::utils::{cleanup_test_dir, create_test_image, setup_test_dir};
 use anyhow::Result;
+use clap::Parser;
 use rush::commands::image;
-use rush::ImageResizeArgs;
+use rush::{Color, ImageResizeArgs, ResizeFilter, ResizeMode};
 use std::fs;
+use std::path::Path;
+
+fn resize_args(input: &Path, output: &Path, width: u32, height: u32) -> ImageResizeArgs {
+    ImageResizeArgs {
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        legacy_size: Vec::new(),
+        height: Some(height),
+        width: Some(width),
+        max_side: None,
+        mode: ResizeMode::Stretch,
+        filter: ResizeFilter::Lanczos3,
+        pad_color: Color([0, 0, 0, 255]),
+        overwrite: false,
+    }
+}
 
 #[test]
 fn test_image_resize_file_success() -> Result<()> {
@@ -18,8 +35,13 @@ fn test_image_resize_file_success() -> Result<()> {
     let args = ImageResizeArgs {
         input: input_path.to_string_lossy().to_string(),
         output: output_path.to_string_lossy().to_string(),
-        width: 50,
-        height: 50,
+        legacy_size: Vec::new(),
+        width: Some(50),
+        height: Some(50),
+        max_side: None,
+        mode: ResizeMode::Stretch,
+        filter: ResizeFilter::Lanczos3,
+        pad_color: Color([0, 0, 0, 255]),
         overwrite: false,
     };
 
@@ -56,8 +78,13 @@ fn test_image_resize_directory_success() -> Result<()> {
     let args = ImageResizeArgs {
         input: input_dir.to_string_lossy().to_string(),
         output: output_dir.to_string_lossy().to_string(),
-        width: 50,
-        height: 50,
+        legacy_size: Vec::new(),
+        width: Some(50),
+        height: Some(50),
+        max_side: None,
+        mode: ResizeMode::Stretch,
+        filter: ResizeFilter::Lanczos3,
+        pad_color: Color([0, 0, 0, 255]),
         overwrite: false,
     };
 
@@ -87,8 +114,13 @@ fn test_image_resize_overwrite_protection_error() -> Result<()> {
     let args = ImageResizeArgs {
         input: input_path.to_string_lossy().to_string(),
         output: input_path.to_string_lossy().to_string(),
-        width: 50,
-        height: 50,
+        legacy_size: Vec::new(),
+        width: Some(50),
+        height: Some(50),
+        max_side: None,
+        mode: ResizeMode::Stretch,
+        filter: ResizeFilter::Lanczos3,
+        pad_color: Color([0, 0, 0, 255]),
         overwrite: false,
     };
 
@@ -115,8 +147,13 @@ fn test_image_resize_overwrite_success() -> Result<()> {
     let args = ImageResizeArgs {
         input: input_path.to_string_lossy().to_string(),
         output: input_path.to_string_lossy().to_string(),
-        width: 50,
-        height: 50,
+        legacy_size: Vec::new(),
+        width: Some(50),
+        height: Some(50),
+        max_side: None,
+        mode: ResizeMode::Stretch,
+        filter: ResizeFilter::Lanczos3,
+        pad_color: Color([0, 0, 0, 255]),
         overwrite: true,
     };
 
@@ -131,3 +168,221 @@ fn test_image_resize_overwrite_success() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_image_resize_fit_keeps_aspect_ratio() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a landscape image
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 200, 100, 3)?;
+
+    // Fit inside a square box
+    let args = ImageResizeArgs {
+        mode: ResizeMode::Fit,
+        ..resize_args(&input_path, &output_path, 50, 50)
+    };
+    image::resize::execute(args)?;
+
+    // The width fills the box and the height follows
+    let output = ::image::open(&output_path)?;
+    assert_eq!((output.width(), output.height()), (50, 25));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_resize_fill_crops_to_box() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a landscape image
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 200, 100, 3)?;
+
+    // Fill a square box
+    let args = ImageResizeArgs {
+        mode: ResizeMode::Fill,
+        ..resize_args(&input_path, &output_path, 50, 50)
+    };
+    image::resize::execute(args)?;
+
+    // The output has exactly the size of the box
+    let output = ::image::open(&output_path)?;
+    assert_eq!((output.width(), output.height()), (50, 50));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_resize_pad_letterboxes_with_color() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a landscape image
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 200, 100, 3)?;
+
+    // Pad to a square box with red borders
+    let args = ImageResizeArgs {
+        mode: ResizeMode::Pad,
+        pad_color: Color([255, 0, 0, 255]),
+        ..resize_args(&input_path, &output_path, 50, 50)
+    };
+    image::resize::execute(args)?;
+
+    // The image is centered between red bands above and below
+    let output = ::image::open(&output_path)?;
+    assert_eq!((output.width(), output.height()), (50, 50));
+    assert!(!output.color().has_alpha());
+    let output = output.to_rgb8();
+    assert_eq!(output.get_pixel(25, 0).0, [255, 0, 0]);
+    assert_eq!(output.get_pixel(25, 49).0, [255, 0, 0]);
+    assert_ne!(output.get_pixel(25, 25).0, [255, 0, 0]);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_resize_single_dimension_keeps_aspect_ratio() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create a landscape image
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 200, 100, 3)?;
+
+    // Only give the height
+    let args = ImageResizeArgs {
+        width: None,
+        filter: ResizeFilter::Nearest,
+        ..resize_args(&input_path, &output_path, 0, 40)
+    };
+    image::resize::execute(args)?;
+
+    // The width follows the aspect ratio
+    let output = ::image::open(&output_path)?;
+    assert_eq!((output.width(), output.height()), (80, 40));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_resize_max_side_only_downscales() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    let output_dir = test_dir.join("output");
+    fs::create_dir(&input_dir)?;
+
+    // One image larger than the maximum side, one smaller
+    create_test_image(&input_dir.join("large.png"), 100, 300, 3)?;
+    create_test_image(&input_dir.join("small.png"), 60, 40, 1)?;
+
+    // Cap the longest side
+    let args = ImageResizeArgs {
+        height: None,
+        width: None,
+        max_side: Some(150),
+        ..resize_args(&input_dir, &output_dir, 0, 0)
+    };
+    image::resize::execute(args)?;
+
+    // Only the large image is scaled down
+    let large = ::image::open(output_dir.join("large.png"))?;
+    assert_eq!((large.width(), large.height()), (50, 150));
+    let small = ::image::open(output_dir.join("small.png"))?;
+    assert_eq!((small.width(), small.height()), (60, 40));
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_resize_without_size_error() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 100, 100, 3)?;
+
+    // Give no size at all
+    let args = ImageResizeArgs {
+        height: None,
+        width: None,
+        ..resize_args(&input_path, &output_path, 0, 0)
+    };
+
+    // Execute command and expect error
+    let result = image::resize::execute(args);
+    assert!(result.is_err());
+    assert!(!output_path.exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_resize_positional_size_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    let input_path = test_dir.join("input.png");
+    let output_path = test_dir.join("output.png");
+    create_test_image(&input_path, 100, 100, 3)?;
+
+    // Execute command as `rush image resize input.png 30 60 output.png`
+    let args = ImageResizeArgs::try_parse_from([
+        "resize".to_string(),
+        input_path.to_string_lossy().to_string(),
+        "30".to_string(),
+        "60".to_string(),
+        output_path.to_string_lossy().to_string(),
+    ])?;
+    image::resize::execute(args)?;
+
+    // Verify the height comes before the width
+    let output = ::image::open(&output_path)?;
+    assert_eq!((output.width(), output.height()), (60, 30));
+
+    // Sizes can't be given both ways
+    let result = ImageResizeArgs::try_parse_from([
+        "resize",
+        "input.png",
+        "30",
+        "60",
+        "output.png",
+        "--max-side",
+        "10",
+    ]);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
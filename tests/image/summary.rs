@@ -1,7 +1,9 @@
 use crate::utils::{cleanup_test_dir, create_test_image, setup_test_dir};
 use anyhow::Result;
 use rush::commands::image;
-use rush::ImageSummaryArgs;
+use rush::commands::image::summary::ImageSummary;
+use rush::utils::{read_table, render_summary};
+use rush::{ImageSummaryArgs, OutputFormat};
 use std::fs;
 
 #[test]
@@ -16,6 +18,10 @@ fn test_image_summary_file_success() -> Result<()> {
     // Define args
     let args = ImageSummaryArgs {
         target: input_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command
@@ -46,6 +52,10 @@ fn test_image_summary_directory_success() -> Result<()> {
     // Define args
     let args = ImageSummaryArgs {
         target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command
@@ -66,6 +76,10 @@ fn test_image_summary_nonexistent_path() -> Result<()> {
     // Define args with nonexistent path
     let args = ImageSummaryArgs {
         target: nonexistent_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command and expect error
@@ -90,6 +104,10 @@ fn test_image_summary_invalid_extension() -> Result<()> {
     // Define args
     let args = ImageSummaryArgs {
         target: invalid_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command and expect error
@@ -101,3 +119,126 @@ fn test_image_summary_invalid_extension() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_image_summary_json_and_csv_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test files
+    create_test_image(&test_dir.join("test1.png"), 100, 100, 1)?;
+    create_test_image(&test_dir.join("test2.png"), 200, 100, 3)?;
+
+    // Execute command with every machine-readable format
+    for format in [OutputFormat::Json, OutputFormat::Csv] {
+        let args = ImageSummaryArgs {
+            target: test_dir.to_string_lossy().to_string(),
+            format,
+            manifest: None,
+            errors: None,
+            strict: false,
+        };
+        image::summary::execute(args)?;
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_summary_csv_keeps_field_order() -> Result<()> {
+    // Render a summary whose fields are not in alphabetical order
+    let summary = ImageSummary {
+        total_files: 2,
+        failed_files: 0,
+        shapes: vec![(100, 100), (100, 200)],
+    };
+    let csv = render_summary(&summary, OutputFormat::Csv)?;
+
+    // Verify the columns follow the structure
+    let header = csv.lines().next().unwrap_or_default();
+    assert_eq!(header, "total_files,failed_files,shapes");
+
+    Ok(())
+}
+
+#[test]
+fn test_image_summary_manifest_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    fs::create_dir(&input_dir)?;
+
+    // Create test files
+    create_test_image(&input_dir.join("test1.png"), 100, 50, 1)?;
+    create_test_image(&input_dir.join("test2.png"), 200, 100, 3)?;
+
+    // Define args
+    let manifest_path = test_dir.join("manifest.csv");
+    let args = ImageSummaryArgs {
+        target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        errors: None,
+        strict: false,
+    };
+
+    // Execute command
+    image::summary::execute(args)?;
+
+    // Verify there is one row per file
+    let df = read_table(&manifest_path)?.collect()?;
+    assert_eq!(df.height(), 2);
+    assert!(df.column("height").is_ok());
+    assert!(df.column("width").is_ok());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_summary_unreadable_files() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+    let input_dir = test_dir.join("input");
+    fs::create_dir(&input_dir)?;
+
+    // Create a valid and a corrupt file
+    create_test_image(&input_dir.join("valid.png"), 100, 100, 3)?;
+    fs::write(input_dir.join("corrupt.png"), "not an image")?;
+
+    // Without strict mode the corrupt file is reported but not fatal
+    let errors_path = test_dir.join("errors.parquet");
+    let args = ImageSummaryArgs {
+        target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: Some(errors_path.to_string_lossy().to_string()),
+        strict: false,
+    };
+    image::summary::execute(args)?;
+
+    // Verify the corrupt file has been recorded
+    let df = read_table(&errors_path)?.collect()?;
+    assert_eq!(df.height(), 1);
+
+    // In strict mode the corrupt file is an error
+    let args = ImageSummaryArgs {
+        target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: true,
+    };
+    let result = image::summary::execute(args);
+    assert!(result.is_err());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use crate::utils::{cleanup_test_dir, create_test_table, setup_test_dir};
 use anyhow::Result;
 use rush::commands::table;
-use rush::TableSummaryArgs;
+use rush::{OutputFormat, TableSummaryArgs};
 use std::fs;
 
 #[test]
@@ -19,12 +19,14 @@ fn test_table_summary_file_success() -> Result<()> {
     // Test CSV file
     let csv_args = TableSummaryArgs {
         target: csv_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
     };
     table::summary::execute(csv_args)?;
 
     // Test Parquet file
     let parquet_args = TableSummaryArgs {
         target: parquet_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
     };
     table::summary::execute(parquet_args)?;
 
@@ -43,6 +45,7 @@ fn test_table_summary_nonexistent_path() -> Result<()> {
     // Define args with nonexistent path
     let args = TableSummaryArgs {
         target: nonexistent_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
     };
 
     // Execute command and expect error
@@ -63,6 +66,7 @@ fn test_table_summary_directory() -> Result<()> {
     // Define args with directory path
     let args = TableSummaryArgs {
         target: test_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
     };
 
     // Execute command and expect error
@@ -87,6 +91,7 @@ fn test_table_summary_invalid_extension() -> Result<()> {
     // Define args
     let args = TableSummaryArgs {
         target: invalid_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
     };
 
     // Execute command and expect error
@@ -98,3 +103,27 @@ fn test_table_summary_invalid_extension() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_table_summary_json_and_csv_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let csv_path = test_dir.join("test.csv");
+    create_test_table(&csv_path)?;
+
+    // Execute command with every machine-readable format
+    for format in [OutputFormat::Json, OutputFormat::Csv] {
+        let args = TableSummaryArgs {
+            target: csv_path.to_string_lossy().to_string(),
+            format,
+        };
+        table::summary::execute(args)?;
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
@@ -56,6 +56,7 @@ pub fn create_test_wav(
             match bits_per_sample {
                 8 => writer.write_sample((sample * i8::MAX as f32) as i8)?,
                 16 => writer.write_sample((sample * i16::MAX as f32) as i16)?,
+                24 => writer.write_sample((sample * 8_388_607.0) as i32)?,
                 32 => writer.write_sample((sample * i32::MAX as f32) as i32)?,
                 _ => {
                     return Err(anyhow::anyhow!(
@@ -69,6 +70,167 @@ pub fn create_test_wav(
     Ok(())
 }
 
+/// Create sample mono 16-bit wav file alternating tones and silences, given as (duration, is_tone)
+pub fn create_test_wav_with_silence(
+    path: &Path,
+    pattern: &[(f32, bool)],
+    sample_rate: u32,
+) -> Result<()> {
+    use hound::{WavSpec, WavWriter};
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+
+    for &(duration_sec, is_tone) in pattern {
+        let num_samples = (duration_sec * sample_rate as f32) as u32;
+
+        for t in 0..num_samples {
+            let sample = match is_tone {
+                true => (t as f32 * 440.0 * 2.0 * std::f32::consts::PI / sample_rate as f32).sin(),
+                false => 0.0,
+            };
+            writer.write_sample((sample * i16::MAX as f32) as i16)?;
+        }
+    }
+    Ok(())
+}
+
+/// Create sample 32-bit float wav file
+pub fn create_test_wav_float(
+    path: &Path,
+    duration_sec: f32,
+    sample_rate: u32,
+    channels: usize,
+) -> Result<()> {
+    use hound::{WavSpec, WavWriter};
+
+    let spec = WavSpec {
+        channels: channels as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    let num_samples = (duration_sec * sample_rate as f32) as u32;
+
+    for t in 0..num_samples {
+        let sample = (t as f32 * 440.0 * 2.0 * std::f32::consts::PI / sample_rate as f32).sin();
+
+        // Write sample for each channel
+        for _ in 0..channels {
+            writer.write_sample(sample)?;
+        }
+    }
+    Ok(())
+}
+
+/// Create sample mono 32-bit float wav file with the same value in every frame
+pub fn create_test_wav_constant(
+    path: &Path,
+    value: f32,
+    frames: usize,
+    sample_rate: u32,
+) -> Result<()> {
+    use hound::{WavSpec, WavWriter};
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for _ in 0..frames {
+        writer.write_sample(value)?;
+    }
+    Ok(())
+}
+
+/// Create sample mp3 file of empty 128 kbps stereo frames at 44100 Hz, which can be probed
+/// without an encoder
+pub fn create_test_mp3(path: &Path, frames: usize) -> Result<()> {
+    // MPEG-1 Layer III header, followed by the rest of a 417 bytes frame
+    let mut frame = vec![0xFF, 0xFB, 0x90, 0x64];
+    frame.resize(417, 0);
+
+    std::fs::write(path, frame.repeat(frames))?;
+    Ok(())
+}
+
+/// Create sample mono 32-bit float wav file with a 440 Hz tone of given peak level in dBFS
+pub fn create_test_wav_tone(
+    path: &Path,
+    level_db: f32,
+    duration_sec: f32,
+    sample_rate: u32,
+) -> Result<()> {
+    use hound::{WavSpec, WavWriter};
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    let amplitude = 10_f32.powf(level_db / 20.0);
+    let num_samples = (duration_sec * sample_rate as f32) as u32;
+
+    for t in 0..num_samples {
+        let sample = (t as f32 * 440.0 * 2.0 * std::f32::consts::PI / sample_rate as f32).sin();
+        writer.write_sample(sample * amplitude)?;
+    }
+    Ok(())
+}
+
+/// Create sample mono 16-bit wav file playing a sequence of notes drawn from a seed
+pub fn create_test_wav_melody(
+    path: &Path,
+    seed: u64,
+    duration_sec: f32,
+    sample_rate: u32,
+    gain: f32,
+) -> Result<()> {
+    use hound::{WavSpec, WavWriter};
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    let num_samples = (duration_sec * sample_rate as f32) as u32;
+    let note_samples = sample_rate / 10;
+
+    // Linear congruential generator, so that the same seed gives the same notes
+    let mut state = seed;
+    let mut frequency = 0.0;
+    let mut phase = 0.0;
+    for t in 0..num_samples {
+        if t % note_samples == 0 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            frequency = 300.0 + (state >> 33) as f32 % 1700.0;
+        }
+        phase += 2.0 * std::f32::consts::PI * frequency / sample_rate as f32;
+        let sample = gain * phase.sin();
+        writer.write_sample((sample * i16::MAX as f32) as i16)?;
+    }
+    Ok(())
+}
+
 /// Create a test image with specified dimensions and channels
 pub fn create_test_image(path: &Path, width: u32, height: u32, channels: u8) -> Result<()> {
     use image::{ImageBuffer, Luma, Rgb};
@@ -100,6 +262,38 @@ pub fn create_test_image(path: &Path, width: u32, height: u32, channels: u8) ->
     Ok(())
 }
 
+/// Create an RGB image of overlapping waves, with detail at several scales like a photograph
+pub fn create_test_waves(path: &Path, width: u32, height: u32) -> Result<()> {
+    use image::{ImageBuffer, Rgb};
+
+    let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(width, height, |x, y| {
+        // Coordinates relative to the size, so that resized copies look the same
+        let u = x as f32 / width as f32 * 10.0;
+        let v = y as f32 / height as f32 * 10.0;
+        let value =
+            128.0 + 60.0 * (u * 1.3).sin() * (v * 0.7).cos() + 50.0 * ((u + 2.0 * v) * 0.4).sin();
+        let value = value.clamp(0.0, 255.0) as u8;
+        Rgb([value, value / 2 + 64, 255 - value])
+    });
+    img.save(path)?;
+
+    Ok(())
+}
+
+/// Create a black and white checkerboard image with squares of the given side
+pub fn create_test_checkerboard(path: &Path, width: u32, height: u32, square: u32) -> Result<()> {
+    use image::{ImageBuffer, Luma};
+
+    let img: ImageBuffer<Luma<u8>, Vec<u8>> =
+        ImageBuffer::from_fn(width, height, |x, y| match (x / square + y / square) % 2 {
+            0 => Luma([0]),
+            _ => Luma([255]),
+        });
+    img.save(path)?;
+
+    Ok(())
+}
+
 /// Create a test video file with specified dimensions, duration and framerate
 pub fn create_test_video(
     path: &Path,
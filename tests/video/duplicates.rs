@@ -1,7 +1,21 @@
 use crate::utils::{cleanup_test_dir, create_test_video, setup_test_dir};
 use anyhow::Result;
 use rush::commands::video;
-use rush::VideoDuplicatesArgs;
+use rush::{ContentHash, DuplicateAction, KeepPolicy, VideoDuplicatesArgs};
+use std::fs;
+use std::path::Path;
+
+fn duplicates_args(target: &Path) -> VideoDuplicatesArgs {
+    VideoDuplicatesArgs {
+        targets: vec![target.to_string_lossy().to_string()],
+        recursive: false,
+        method: ContentHash::Sha256,
+        keep: KeepPolicy::Oldest,
+        action: DuplicateAction::Report,
+        move_to: None,
+        apply: false,
+    }
+}
 
 #[test]
 fn test_video_duplicates_success() -> Result<()> {
@@ -20,9 +34,7 @@ fn test_video_duplicates_success() -> Result<()> {
     create_test_video(&video_path3, 1920, 1080, 5.0, 30)?;
 
     // Define args
-    let args = VideoDuplicatesArgs {
-        target: test_dir.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&test_dir);
 
     // Execute command
     video::duplicates::execute(args)?;
@@ -40,9 +52,7 @@ fn test_video_duplicates_nonexistent_path() -> Result<()> {
     let nonexistent_path = test_dir.join("nonexistent");
 
     // Define args with nonexistent path
-    let args = VideoDuplicatesArgs {
-        target: nonexistent_path.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&nonexistent_path);
 
     // Execute command and expect error
     let result = video::duplicates::execute(args);
@@ -64,9 +74,7 @@ fn test_video_duplicates_not_a_directory() -> Result<()> {
     create_test_video(&video_path, 1280, 720, 3.0, 24)?;
 
     // Define args with a file path instead of directory
-    let args = VideoDuplicatesArgs {
-        target: video_path.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&video_path);
 
     // Execute command and expect error
     let result = video::duplicates::execute(args);
@@ -88,9 +96,7 @@ fn test_video_duplicates_insufficient_files() -> Result<()> {
     create_test_video(&video_path, 1280, 720, 3.0, 24)?;
 
     // Define args
-    let args = VideoDuplicatesArgs {
-        target: test_dir.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&test_dir);
 
     // Execute command and expect error (needs at least 2 files)
     let result = video::duplicates::execute(args);
@@ -118,9 +124,7 @@ fn test_video_duplicates_multiple_formats() -> Result<()> {
     create_test_video(&video_path3, 1280, 720, 3.0, 24)?;
 
     // Define args
-    let args = VideoDuplicatesArgs {
-        target: test_dir.to_string_lossy().to_string(),
-    };
+    let args = duplicates_args(&test_dir);
 
     // Execute command
     video::duplicates::execute(args)?;
@@ -130,3 +134,37 @@ fn test_video_duplicates_multiple_formats() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_video_duplicates_recursive_delete() -> Result<()> {
+    // Set up the directories for testing
+    let test_dir = setup_test_dir()?;
+    let nested_dir = test_dir.join("nested");
+    fs::create_dir(&nested_dir)?;
+
+    // Two copies of a video, one of them in a subdirectory
+    let video_path = test_dir.join("video.mp4");
+    let copy_path = nested_dir.join("copy.mp4");
+    create_test_video(&video_path, 320, 240, 1.0, 24)?;
+    fs::copy(&video_path, &copy_path)?;
+
+    // Delete all but the shortest path
+    let args = VideoDuplicatesArgs {
+        recursive: true,
+        method: ContentHash::Xxh3,
+        keep: KeepPolicy::ShortestPath,
+        action: DuplicateAction::Delete,
+        apply: true,
+        ..duplicates_args(&test_dir)
+    };
+    video::duplicates::execute(args)?;
+
+    // Only the copy is gone
+    assert!(video_path.exists());
+    assert!(!copy_path.exists());
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
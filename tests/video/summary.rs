@@ -1,7 +1,8 @@
 use crate::utils::{cleanup_test_dir, create_test_video, setup_test_dir};
 use anyhow::Result;
 use rush::commands::video;
-use rush::VideoSummaryArgs;
+use rush::utils::read_table;
+use rush::{OutputFormat, VideoSummaryArgs};
 use std::fs;
 
 #[test]
@@ -16,6 +17,10 @@ fn test_video_summary_file_success() -> Result<()> {
     // Define args
     let args = VideoSummaryArgs {
         target: input_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command
@@ -46,6 +51,10 @@ fn test_video_summary_directory_success() -> Result<()> {
     // Define args
     let args = VideoSummaryArgs {
         target: input_dir.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command
@@ -66,6 +75,10 @@ fn test_video_summary_nonexistent_path() -> Result<()> {
     // Define args with nonexistent path
     let args = VideoSummaryArgs {
         target: nonexistent_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command and expect error
@@ -90,6 +103,10 @@ fn test_video_summary_invalid_extension() -> Result<()> {
     // Define args
     let args = VideoSummaryArgs {
         target: invalid_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: None,
+        errors: None,
+        strict: false,
     };
 
     // Execute command and expect error
@@ -101,3 +118,62 @@ fn test_video_summary_invalid_extension() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_video_summary_json_and_csv_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.mp4");
+    create_test_video(&input_path, 640, 480, 2.0, 25)?;
+
+    // Execute command with every machine-readable format
+    for format in [OutputFormat::Json, OutputFormat::Csv] {
+        let args = VideoSummaryArgs {
+            target: input_path.to_string_lossy().to_string(),
+            format,
+            manifest: None,
+            errors: None,
+            strict: false,
+        };
+        video::summary::execute(args)?;
+    }
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_video_summary_manifest_success() -> Result<()> {
+    // Set up the directory for testing
+    let test_dir = setup_test_dir()?;
+
+    // Create test file
+    let input_path = test_dir.join("input.mp4");
+    create_test_video(&input_path, 640, 480, 2.0, 25)?;
+
+    // Define args
+    let manifest_path = test_dir.join("manifest.parquet");
+    let args = VideoSummaryArgs {
+        target: input_path.to_string_lossy().to_string(),
+        format: OutputFormat::Text,
+        manifest: Some(manifest_path.to_string_lossy().to_string()),
+        errors: None,
+        strict: false,
+    };
+
+    // Execute command
+    video::summary::execute(args)?;
+
+    // Verify there is one row for the file
+    let df = read_table(&manifest_path)?.collect()?;
+    assert_eq!(df.height(), 1);
+
+    // Clean up dir
+    cleanup_test_dir(&test_dir)?;
+
+    Ok(())
+}